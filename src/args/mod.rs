@@ -1,4 +1,8 @@
-use crate::fs_manager;
+use std::path::{Path, PathBuf};
+
+use crate::region_parser::file::SyncMode;
+use crate::region_parser::region::Region;
+use crate::{config, consts, fs_manager, net, self_test};
 use clap::Parser;
 use log::error;
 
@@ -9,12 +13,68 @@ struct Cli {
     /// Removes all server-related files except the server executable.
     #[arg(short, long)]
     remove_files: bool,
+
+    /// Logs the files and directories the server would create, without creating them, then exits.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Only answers the status and the ping, refusing every login. The world isn't loaded.
+    #[arg(long)]
+    status_only: bool,
+
+    /// The message refusing the logins in status-only mode.
+    #[arg(long, value_name = "MESSAGE", requires = "status_only")]
+    status_only_message: Option<String>,
+
+    /// Runs round-trips through the codecs (VarInt, String, packets, region compression...), then
+    /// exits with 0 if they all pass.
+    #[arg(long)]
+    self_test: bool,
+
+    /// Rewrites a region file with its chunks packed tightly, reclaiming the unused sectors, then
+    /// exits. The write is synced to disk as set by `sync-chunk-writes`.
+    #[arg(long, value_name = "PATH")]
+    compact_region: Option<PathBuf>,
 }
 
 /// Retrieves args and initializes the argument parsing logic.
 pub fn init() {
     let args = Cli::parse();
 
+    if args.self_test {
+        let passed = self_test::run_and_report();
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
+    if let Some(path) = args.compact_region {
+        // Runs before the configuration files are created.
+        let mode = if Path::new(consts::file_paths::PROPERTIES).exists() {
+            SyncMode::from_settings(&config::Settings::new())
+        } else {
+            SyncMode::default()
+        };
+        match Region::compact(&path, mode) {
+            Ok(compaction) => {
+                println!(
+                    "Compacted {}: {} bytes, was {} bytes",
+                    path.display(),
+                    compaction.size_after,
+                    compaction.size_before
+                );
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("Failed to compact {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+    }
+
+    fs_manager::set_dry_run(args.dry_run);
+    if args.status_only {
+        net::status_only::enable(args.status_only_message);
+    }
+
     if args.remove_files {
         if let Err(e) = fs_manager::clean_files() {
             error!("Error(s) when cleaning files: {e}");
@@ -22,3 +82,25 @@ pub fn init() {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_flag() {
+        let args = Cli::try_parse_from(["cactus", "--self-test"]).unwrap();
+        assert!(args.self_test);
+        assert!(self_test::run_and_report());
+    }
+
+    #[test]
+    fn test_compact_region_flag() {
+        let args =
+            Cli::try_parse_from(["cactus", "--compact-region", "world/region/r.0.0.mca"]).unwrap();
+        assert_eq!(
+            args.compact_region,
+            Some(PathBuf::from("world/region/r.0.0.mca"))
+        );
+        assert!(Cli::try_parse_from(["cactus", "--compact-region"]).is_err());
+    }
+}
@@ -0,0 +1,446 @@
+//! World generation. Each dimension has its own `Generator`, registered in `Generators`.
+use std::collections::HashMap;
+
+use super::generator_settings::FlatSettings;
+use super::pos::ChunkPos;
+use super::{BlockState, Chunk, SECTION_WIDTH};
+use crate::config::{Settings, WorldPreset};
+use crate::world::border::WorldBorder;
+use crate::world::dimension::Dimension;
+
+// Placeholder block states, until there is a block registry.
+pub const BEDROCK: BlockState = 1;
+pub const DIRT: BlockState = 2;
+pub const GRASS: BlockState = 3;
+pub const STONE: BlockState = 4;
+pub const WATER: BlockState = 5;
+pub const NETHERRACK: BlockState = 6;
+pub const LAVA: BlockState = 7;
+pub const END_STONE: BlockState = 8;
+
+/// Generates the chunks of a dimension.
+pub trait Generator: Send + Sync {
+    /// Generates the chunk at chunk coordinates `x`, `z`. The same chunk is generated every time.
+    fn generate_chunk(&self, x: i32, z: i32) -> Chunk;
+}
+
+/// Creates an empty chunk spanning the whole height of `dimension`.
+fn empty_chunk(dimension: Dimension, x: i32, z: i32) -> Chunk {
+    let width = SECTION_WIDTH as i32;
+    Chunk::new(
+        x,
+        z,
+        dimension.min_y().div_euclid(width),
+        (dimension.height() / width) as usize,
+    )
+}
+
+/// Calls `set` with the world coordinates of every block column of chunk `x`, `z`.
+fn for_each_column(x: i32, z: i32, mut set: impl FnMut(i32, i32)) {
+    let width = SECTION_WIDTH as i32;
+    let (min_x, min_z) = ChunkPos::new(x, z).min_block();
+    for local_z in 0..width {
+        for local_x in 0..width {
+            set(min_x + local_x, min_z + local_z);
+        }
+    }
+}
+
+/// Identical layers of blocks, starting at the bottom of the dimension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuperflatGenerator {
+    dimension: Dimension,
+    /// From the bottom layer to the top one.
+    layers: Vec<BlockState>,
+}
+
+impl SuperflatGenerator {
+    pub fn new(dimension: Dimension, layers: Vec<BlockState>) -> Self {
+        Self { dimension, layers }
+    }
+
+    /// Bedrock at y=-64, dirt at y=-63 and y=-62 and grass at y=-61.
+    pub fn overworld() -> Self {
+        Self::new(Dimension::Overworld, vec![BEDROCK, DIRT, DIRT, GRASS])
+    }
+
+    /// End stone up to y=59, where the main island of the End is.
+    pub fn end() -> Self {
+        Self::new(Dimension::End, vec![END_STONE; 60])
+    }
+}
+
+impl Generator for SuperflatGenerator {
+    fn generate_chunk(&self, x: i32, z: i32) -> Chunk {
+        let mut chunk = empty_chunk(self.dimension, x, z);
+        let min_y = self.dimension.min_y();
+
+        for_each_column(x, z, |block_x, block_z| {
+            for (y, state) in (min_y..).zip(&self.layers) {
+                chunk
+                    .set_block(block_x, y, block_z, *state)
+                    .expect("superflat layers are inside the chunk");
+            }
+        });
+        chunk
+    }
+}
+
+/// The shape and the blocks of the terrain of a `NoiseGenerator`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseSettings {
+    /// The average height of the ground surface.
+    pub base_height: i32,
+    /// How far the surface goes above or below `base_height`.
+    pub amplitude: f64,
+    /// The width in blocks of a hill.
+    pub scale: f64,
+    pub filler: BlockState,
+    /// The block of the surface and of the 3 blocks below it.
+    pub surface: BlockState,
+    /// The block of the surface itself, if different from `surface`.
+    pub top: BlockState,
+    /// Fills the air up to `sea_level`.
+    pub fluid: BlockState,
+    pub sea_level: i32,
+    /// The Y coordinate of the bedrock roof, with a ceiling of `filler` hanging from it.
+    pub roof_y: Option<i32>,
+}
+
+impl NoiseSettings {
+    pub fn overworld() -> Self {
+        Self {
+            base_height: 64,
+            amplitude: 16.0,
+            scale: 64.0,
+            filler: STONE,
+            surface: DIRT,
+            top: GRASS,
+            fluid: WATER,
+            sea_level: 62,
+            roof_y: None,
+        }
+    }
+
+    pub fn nether() -> Self {
+        Self {
+            base_height: 40,
+            amplitude: 12.0,
+            scale: 32.0,
+            filler: NETHERRACK,
+            surface: NETHERRACK,
+            top: NETHERRACK,
+            fluid: LAVA,
+            sea_level: 31,
+            roof_y: Some(127),
+        }
+    }
+}
+
+/// Hills and valleys from the seed, with a bedrock floor (and roof in the Nether).
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseGenerator {
+    dimension: Dimension,
+    seed: i64,
+    settings: NoiseSettings,
+}
+
+impl NoiseGenerator {
+    pub fn new(dimension: Dimension, seed: i64, settings: NoiseSettings) -> Self {
+        Self {
+            dimension,
+            seed,
+            settings,
+        }
+    }
+
+    pub fn overworld(seed: i64) -> Self {
+        Self::new(Dimension::Overworld, seed, NoiseSettings::overworld())
+    }
+
+    pub fn nether(seed: i64) -> Self {
+        Self::new(Dimension::Nether, seed, NoiseSettings::nether())
+    }
+
+    /// The Y coordinate of the ground surface at `x`, `z`.
+    fn surface_y(&self, x: i32, z: i32) -> i32 {
+        let noise = value_noise(
+            self.seed,
+            x as f64 / self.settings.scale,
+            z as f64 / self.settings.scale,
+        );
+        self.settings.base_height + ((noise * 2.0 - 1.0) * self.settings.amplitude) as i32
+    }
+
+    /// The Y coordinate of the bottom of the ceiling at `x`, `z`, if there is a roof.
+    fn ceiling_y(&self, x: i32, z: i32) -> Option<i32> {
+        let roof_y = self.settings.roof_y?;
+        let scale = self.settings.scale;
+        let noise = value_noise(!self.seed, x as f64 / scale, z as f64 / scale);
+        Some(roof_y - 5 - (noise * self.settings.amplitude * 2.0) as i32)
+    }
+
+    /// Whether a block near the bedrock floor or roof is bedrock, `depth` (1 to 4) blocks away
+    /// from the flat layer. Like vanilla, it gets rarer the further it is.
+    fn is_bedrock(&self, x: i32, y: i32, z: i32, depth: i32) -> bool {
+        let roll = hash(self.seed ^ y as i64, x, z) % 5;
+        (roll as i32) < 5 - depth
+    }
+
+    /// The block at `x`, `y`, `z` in a column whose ground is at `surface_y` and ceiling at
+    /// `ceiling_y`. `None` for air.
+    fn block_at(
+        &self,
+        x: i32,
+        y: i32,
+        z: i32,
+        surface_y: i32,
+        ceiling_y: Option<i32>,
+    ) -> Option<BlockState> {
+        let settings = &self.settings;
+
+        let floor_depth = y - self.dimension.min_y();
+        if floor_depth == 0 || (floor_depth <= 4 && self.is_bedrock(x, y, z, floor_depth)) {
+            return Some(BEDROCK);
+        }
+        if let Some(roof_y) = settings.roof_y {
+            let roof_depth = roof_y - y;
+            if roof_depth < 0 {
+                return None;
+            }
+            if roof_depth == 0 || (roof_depth <= 4 && self.is_bedrock(x, y, z, roof_depth)) {
+                return Some(BEDROCK);
+            }
+        }
+
+        if ceiling_y.is_some_and(|ceiling_y| y >= ceiling_y) || y < surface_y - 3 {
+            Some(settings.filler)
+        } else if y < surface_y || (y == surface_y && surface_y < settings.sea_level) {
+            Some(settings.surface)
+        } else if y == surface_y {
+            Some(settings.top)
+        } else {
+            (y <= settings.sea_level).then_some(settings.fluid)
+        }
+    }
+}
+
+impl Generator for NoiseGenerator {
+    fn generate_chunk(&self, x: i32, z: i32) -> Chunk {
+        let mut chunk = empty_chunk(self.dimension, x, z);
+        let min_y = self.dimension.min_y();
+        let max_y = min_y + self.dimension.height() - 1;
+
+        for_each_column(x, z, |block_x, block_z| {
+            let surface_y = self.surface_y(block_x, block_z).clamp(min_y + 1, max_y);
+            let ceiling_y = self.ceiling_y(block_x, block_z);
+
+            for y in min_y..=max_y {
+                let Some(state) = self.block_at(block_x, y, block_z, surface_y, ceiling_y) else {
+                    continue;
+                };
+                chunk
+                    .set_block(block_x, y, block_z, state)
+                    .expect("the generated blocks are inside the chunk");
+            }
+        });
+        chunk
+    }
+}
+
+/// A pseudo-random number for the position `x`, `z` and `seed`.
+fn hash(seed: i64, x: i32, z: i32) -> u64 {
+    // SplitMix64 finalizer
+    let mut value = (seed as u64)
+        ^ (x as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u32 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+/// Smooth noise between 0 and 1, interpolated between random values at integer coordinates.
+fn value_noise(seed: i64, x: f64, z: f64) -> f64 {
+    let (cell_x, cell_z) = (x.floor(), z.floor());
+    let corner = |dx: i32, dz: i32| {
+        let value = hash(seed, cell_x as i32 + dx, cell_z as i32 + dz);
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    };
+    // Smoothstep, so that the slopes are continuous between cells.
+    let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+    let (tx, tz) = (smooth(x - cell_x), smooth(z - cell_z));
+
+    let north = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let south = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    north + (south - north) * tz
+}
+
+/// The generator of each dimension.
+#[derive(Default)]
+pub struct Generators {
+    generators: HashMap<Dimension, Box<dyn Generator>>,
+    /// The chunks entirely outside of it aren't generated. No limit if `None`.
+    border: Option<WorldBorder>,
+}
+
+impl Generators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Superflat or noise overworld depending on `level-type`, noise Nether and flat End. The
+    /// superflat layers come from `generator-settings`. `seed` is the one of the world, see
+    /// `world::level::init_seed`. The world border comes from `max-world-size`.
+    pub fn from_settings(settings: &Settings, seed: i64) -> Self {
+        let mut generators = Self::new();
+        generators.set_border(WorldBorder::from_settings(settings));
+        match settings.level_type {
+            WorldPreset::Flat => {
+                let layers = FlatSettings::from_settings(settings)
+                    .block_layers()
+                    .expect("the layers are validated when parsed");
+                let generator = SuperflatGenerator::new(Dimension::Overworld, layers);
+                generators.register(Dimension::Overworld, generator)
+            }
+            _ => generators.register(Dimension::Overworld, NoiseGenerator::overworld(seed)),
+        };
+        generators
+            .register(Dimension::Nether, NoiseGenerator::nether(seed))
+            .register(Dimension::End, SuperflatGenerator::end());
+        generators
+    }
+
+    /// Uses `generator` for `dimension`, replacing its previous one.
+    pub fn register(
+        &mut self,
+        dimension: Dimension,
+        generator: impl Generator + 'static,
+    ) -> &mut Self {
+        self.generators.insert(dimension, Box::new(generator));
+        self
+    }
+
+    /// Limits the generation to the chunks at least partly inside `border`.
+    pub fn set_border(&mut self, border: WorldBorder) -> &mut Self {
+        self.border = Some(border);
+        self
+    }
+
+    pub fn get_border(&self) -> Option<&WorldBorder> {
+        self.border.as_ref()
+    }
+
+    /// Whether chunk `x`, `z` can be generated, see `set_border`.
+    pub fn is_chunk_inside(&self, x: i32, z: i32) -> bool {
+        self.border
+            .is_none_or(|border| border.is_chunk_inside(x, z))
+    }
+
+    /// Generates chunk `x`, `z` of `dimension`, `None` if it has no generator or the chunk is
+    /// outside the world border.
+    pub fn generate_chunk(&self, dimension: Dimension, x: i32, z: i32) -> Option<Chunk> {
+        let generator = self.generators.get(&dimension)?;
+        self.is_chunk_inside(x, z)
+            .then(|| generator.generate_chunk(x, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_generators() -> Generators {
+        let mut generators = Generators::new();
+        generators
+            .register(Dimension::Overworld, NoiseGenerator::overworld(42))
+            .register(Dimension::Nether, NoiseGenerator::nether(42))
+            .register(Dimension::End, SuperflatGenerator::end());
+        generators
+    }
+
+    #[test]
+    fn test_border_limits_generation() {
+        let mut generators = default_generators();
+        assert!(generators
+            .generate_chunk(Dimension::Overworld, 100, 0)
+            .is_some());
+
+        generators.set_border(WorldBorder::from_max_world_size(100));
+        assert!(generators
+            .generate_chunk(Dimension::Overworld, 6, -7)
+            .is_some());
+        assert!(generators
+            .generate_chunk(Dimension::Overworld, 7, 0)
+            .is_none());
+        assert!(generators
+            .generate_chunk(Dimension::Nether, 0, 100)
+            .is_none());
+    }
+
+    #[test]
+    fn test_nether_bedrock_roof_and_floor() {
+        let generators = default_generators();
+        let chunk = generators.generate_chunk(Dimension::Nether, 3, -7).unwrap();
+
+        assert_eq!(chunk.get_min_y(), 0);
+        assert_eq!(chunk.get_height(), 256);
+        for_each_column(3, -7, |x, z| {
+            assert_eq!(chunk.get_block(x, 0, z), Ok(BEDROCK));
+            assert_eq!(chunk.get_block(x, 127, z), Ok(BEDROCK));
+            assert_eq!(chunk.get_block(x, 128, z), Ok(0));
+            assert_eq!(chunk.get_block(x, 5, z), Ok(NETHERRACK));
+            assert_eq!(chunk.get_block(x, 122, z), Ok(NETHERRACK));
+        });
+    }
+
+    #[test]
+    fn test_overworld_terrain() {
+        let generators = default_generators();
+        let chunk = generators
+            .generate_chunk(Dimension::Overworld, 0, 0)
+            .unwrap();
+
+        assert_eq!(chunk.get_min_y(), -64);
+        for_each_column(0, 0, |x, z| {
+            assert_eq!(chunk.get_block(x, -64, z), Ok(BEDROCK));
+            assert_eq!(chunk.get_block(x, 0, z), Ok(STONE));
+            // Ground or water at sea level, never air
+            assert_ne!(chunk.get_block(x, 62, z), Ok(0));
+            assert_eq!(chunk.get_block(x, 319, z), Ok(0));
+        });
+
+        // Same seed, same chunk
+        assert_eq!(
+            generators.generate_chunk(Dimension::Overworld, 0, 0),
+            Some(chunk)
+        );
+    }
+
+    #[test]
+    fn test_register_replaces_generator() {
+        let mut generators = default_generators();
+        generators.register(
+            Dimension::Nether,
+            SuperflatGenerator::new(Dimension::Nether, vec![LAVA]),
+        );
+
+        let chunk = generators.generate_chunk(Dimension::Nether, 0, 0).unwrap();
+        assert_eq!(chunk.get_block(0, 0, 0), Ok(LAVA));
+        assert_eq!(chunk.get_block(0, 127, 0), Ok(0));
+
+        assert!(Generators::new()
+            .generate_chunk(Dimension::End, 0, 0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_flat_end() {
+        let chunk = default_generators()
+            .generate_chunk(Dimension::End, -1, 1)
+            .unwrap();
+
+        assert_eq!(chunk.get_block(-16, 0, 16), Ok(END_STONE));
+        assert_eq!(chunk.get_block(-1, 59, 31), Ok(END_STONE));
+        assert_eq!(chunk.get_block(-1, 60, 31), Ok(0));
+    }
+}
@@ -0,0 +1,192 @@
+//! The `generator-settings` property: a JSON customizing the world preset.
+//!
+//! Only the superflat preset reads it for now, e.g.:
+//! `{"layers":[{"block":"minecraft:bedrock","height":1},{"block":"minecraft:stone","height":3}],"biome":"minecraft:plains"}`
+use log::warn;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::generator::{BEDROCK, DIRT, END_STONE, GRASS, LAVA, NETHERRACK, STONE, WATER};
+use super::BlockState;
+use crate::config::{Settings, WorldPreset};
+use crate::world::dimension::Dimension;
+
+#[derive(Error, Debug)]
+pub enum GeneratorSettingsError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unknown block {0}")]
+    UnknownBlock(String),
+
+    #[error("the layers are {0} blocks high, more than the {1} blocks of the dimension")]
+    TooHigh(u64, i32),
+}
+
+/// A layer of a superflat world.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FlatLayer {
+    pub block: String,
+    pub height: u32,
+}
+
+fn default_biome() -> String {
+    "minecraft:plains".to_string()
+}
+
+/// The generator settings of a superflat world. Missing fields are the vanilla defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FlatSettings {
+    /// From the bottom layer to the top one.
+    #[serde(default = "FlatSettings::default_layers")]
+    pub layers: Vec<FlatLayer>,
+    #[serde(default = "default_biome")]
+    pub biome: String,
+}
+
+impl Default for FlatSettings {
+    fn default() -> Self {
+        Self {
+            layers: Self::default_layers(),
+            biome: default_biome(),
+        }
+    }
+}
+
+impl FlatSettings {
+    /// Bedrock, two dirt and grass, as in vanilla.
+    fn default_layers() -> Vec<FlatLayer> {
+        [
+            ("minecraft:bedrock", 1),
+            ("minecraft:dirt", 2),
+            ("minecraft:grass_block", 1),
+        ]
+        .into_iter()
+        .map(|(block, height)| FlatLayer {
+            block: block.to_string(),
+            height,
+        })
+        .collect()
+    }
+
+    /// Parses and validates `json`. An empty string is the defaults.
+    pub fn parse(json: &str) -> Result<Self, GeneratorSettingsError> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let settings: Self = serde_json::from_str(json)?;
+        settings.block_layers()?;
+        Ok(settings)
+    }
+
+    /// Reads `generator-settings`, falling back to the defaults if it's invalid.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::parse(&settings.generator_settings).unwrap_or_else(|e| {
+            warn!("Invalid superflat generator-settings, using the defaults: {e}");
+            Self::default()
+        })
+    }
+
+    /// The block of every layer of blocks, from the bottom one.
+    pub fn block_layers(&self) -> Result<Vec<BlockState>, GeneratorSettingsError> {
+        // Summed as `u64`, as the heights of the layers may add up to more than `u32::MAX`.
+        let height: u64 = self.layers.iter().map(|layer| layer.height as u64).sum();
+        let max_height = Dimension::Overworld.height();
+        if height > max_height as u64 {
+            return Err(GeneratorSettingsError::TooHigh(height, max_height));
+        }
+
+        let mut layers = Vec::with_capacity(height as usize);
+        for layer in &self.layers {
+            let state = block_state(&layer.block)
+                .ok_or_else(|| GeneratorSettingsError::UnknownBlock(layer.block.clone()))?;
+            layers.extend(std::iter::repeat_n(state, layer.height as usize));
+        }
+        Ok(layers)
+    }
+}
+
+/// The block state of the block named `name`, with or without the `minecraft:` namespace.
+// TODO: Use the block registry once there is one.
+fn block_state(name: &str) -> Option<BlockState> {
+    Some(match name.trim_start_matches("minecraft:") {
+        "air" => 0,
+        "bedrock" => BEDROCK,
+        "dirt" => DIRT,
+        "grass_block" => GRASS,
+        "stone" => STONE,
+        "water" => WATER,
+        "netherrack" => NETHERRACK,
+        "lava" => LAVA,
+        "end_stone" => END_STONE,
+        _ => return None,
+    })
+}
+
+/// Checks that `generator-settings` is a JSON object for the presets that don't read it yet.
+/// Returns a warning otherwise.
+pub fn validate(settings: &Settings) -> Option<String> {
+    if matches!(settings.level_type, WorldPreset::Flat) {
+        return FlatSettings::parse(&settings.generator_settings)
+            .err()
+            .map(|e| format!("generator-settings is invalid, the defaults are used: {e}"));
+    }
+    let json = settings.generator_settings.trim();
+    if json.is_empty() {
+        return None;
+    }
+    match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(value) if value.is_object() => None,
+        Ok(_) => Some("generator-settings must be a JSON object".to_string()),
+        Err(e) => Some(format!("generator-settings is not valid JSON: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flat_layers() {
+        let json = r#"{"layers":[{"block":"minecraft:bedrock","height":1},{"block":"minecraft:stone","height":3},{"block":"minecraft:grass_block","height":1}],"biome":"minecraft:desert","features":false}"#;
+        let settings = FlatSettings::parse(json).unwrap();
+        assert_eq!(settings.biome, "minecraft:desert");
+        assert_eq!(
+            settings.block_layers().unwrap(),
+            vec![BEDROCK, STONE, STONE, STONE, GRASS]
+        );
+    }
+
+    #[test]
+    fn test_empty_object_is_defaults() {
+        let settings = FlatSettings::parse("{}").unwrap();
+        assert_eq!(settings, FlatSettings::default());
+        assert_eq!(
+            settings.block_layers().unwrap(),
+            vec![BEDROCK, DIRT, DIRT, GRASS]
+        );
+        assert_eq!(FlatSettings::parse("").unwrap(), FlatSettings::default());
+    }
+
+    #[test]
+    fn test_invalid_flat_settings() {
+        assert!(matches!(
+            FlatSettings::parse("{\"layers\": 3}"),
+            Err(GeneratorSettingsError::Json(_))
+        ));
+        assert!(matches!(
+            FlatSettings::parse(r#"{"layers":[{"block":"minecraft:diamond_block","height":1}]}"#),
+            Err(GeneratorSettingsError::UnknownBlock(_))
+        ));
+        assert!(matches!(
+            FlatSettings::parse(r#"{"layers":[{"block":"stone","height":400}]}"#),
+            Err(GeneratorSettingsError::TooHigh(400, 384))
+        ));
+        assert!(matches!(
+            FlatSettings::parse(
+                r#"{"layers":[{"block":"stone","height":4294967295},{"block":"dirt","height":1}]}"#
+            ),
+            Err(GeneratorSettingsError::TooHigh(4294967296, 384))
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! Heightmaps: the height of the highest block of each column of a chunk, as sent in the Chunk
+//! Data packet and stored in chunk NBT.
+use std::collections::HashMap;
+
+use nbt::Value;
+
+use super::{BlockState, Chunk, SECTION_WIDTH};
+use crate::region_parser::nbt::pack;
+
+/// The air block state.
+pub const AIR: BlockState = 0;
+
+/// Number of columns in a chunk.
+const COLUMNS: usize = SECTION_WIDTH * SECTION_WIDTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapKind {
+    /// Highest block that blocks motion or contains a fluid.
+    MotionBlocking,
+    /// Highest non-air block.
+    WorldSurface,
+}
+
+impl HeightmapKind {
+    /// The name used in NBT.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MotionBlocking => "MOTION_BLOCKING",
+            Self::WorldSurface => "WORLD_SURFACE",
+        }
+    }
+
+    /// Whether `state` counts as the top of a column.
+    ///
+    /// Until a block registry exists, every non-air block is considered solid, so both kinds
+    /// give the same heights.
+    fn matches(self, state: BlockState) -> bool {
+        match self {
+            Self::MotionBlocking | Self::WorldSurface => state != AIR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    kind: HeightmapKind,
+    min_y: i32,
+    height: i32,
+    /// Per column, indexed by `x + z * 16`: the number of blocks from the bottom of the world to
+    /// the top of the highest matching block (0 if the column has none).
+    values: [u16; COLUMNS],
+}
+
+impl Heightmap {
+    /// Computes the heightmap of `chunk`.
+    pub fn compute(chunk: &Chunk, kind: HeightmapKind) -> Self {
+        let mut values = [0; COLUMNS];
+
+        for (index, value) in values.iter_mut().enumerate() {
+            let (x, z) = (index % SECTION_WIDTH, index / SECTION_WIDTH);
+
+            'column: for (section_index, section) in chunk.get_sections().iter().enumerate().rev() {
+                for y in (0..SECTION_WIDTH).rev() {
+                    let state = section.get_block(x, y, z).unwrap_or(AIR);
+                    if kind.matches(state) {
+                        *value = (section_index * SECTION_WIDTH + y + 1) as u16;
+                        break 'column;
+                    }
+                }
+            }
+        }
+
+        Self {
+            kind,
+            min_y: chunk.get_min_y(),
+            height: chunk.get_height(),
+            values,
+        }
+    }
+
+    pub fn get_kind(&self) -> HeightmapKind {
+        self.kind
+    }
+
+    /// The Notchian value of a column (local coordinates): the number of blocks from the bottom
+    /// of the world to the top of the highest block.
+    pub fn get(&self, x: usize, z: usize) -> Option<u16> {
+        if x >= SECTION_WIDTH || z >= SECTION_WIDTH {
+            return None;
+        }
+        Some(self.values[x + z * SECTION_WIDTH])
+    }
+
+    /// The Y coordinate of the highest block of a column (local coordinates), `None` if the
+    /// column is empty.
+    pub fn get_top_block_y(&self, x: usize, z: usize) -> Option<i32> {
+        match self.get(x, z)? {
+            0 => None,
+            value => Some(self.min_y + value as i32 - 1),
+        }
+    }
+
+    /// Bits needed to store a value from 0 to the height of the world (9 for the overworld).
+    pub fn bits_per_entry(&self) -> usize {
+        (u32::BITS - (self.height as u32).leading_zeros()) as usize
+    }
+
+    /// The values packed in longs, as stored in NBT. Values never span two longs.
+    pub fn to_longs(&self) -> Vec<i64> {
+        let values: Vec<u64> = self.values.iter().map(|&value| value as u64).collect();
+        pack(&values, self.bits_per_entry())
+    }
+}
+
+/// The `Heightmaps` compound of a chunk, containing `MOTION_BLOCKING` and `WORLD_SURFACE`.
+pub fn heightmaps_nbt(chunk: &Chunk) -> Value {
+    let heightmaps = [HeightmapKind::MotionBlocking, HeightmapKind::WorldSurface]
+        .into_iter()
+        .map(|kind| {
+            let heightmap = Heightmap::compute(chunk, kind);
+            (
+                kind.name().to_string(),
+                Value::LongArray(heightmap.to_longs()),
+            )
+        })
+        .collect::<HashMap<String, Value>>();
+
+    Value::Compound(heightmaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::{generate_world, OVERWORLD_MIN_Y};
+    use crate::region_parser::nbt::unpack;
+
+    #[test]
+    fn test_superflat_heightmap() {
+        let chunk = generate_world(2, -3);
+        let grass_y = OVERWORLD_MIN_Y + 3;
+
+        for kind in [HeightmapKind::MotionBlocking, HeightmapKind::WorldSurface] {
+            let heightmap = Heightmap::compute(&chunk, kind);
+            for z in 0..SECTION_WIDTH {
+                for x in 0..SECTION_WIDTH {
+                    assert_eq!(heightmap.get_top_block_y(x, z), Some(grass_y));
+                    assert_eq!(heightmap.get(x, z), Some(4));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_heightmap_packing() {
+        let mut chunk = generate_world(0, 0);
+        chunk.set_block(5, 319, 7, 1).unwrap();
+
+        let heightmap = Heightmap::compute(&chunk, HeightmapKind::WorldSurface);
+        assert_eq!(heightmap.bits_per_entry(), 9);
+        assert_eq!(heightmap.get_top_block_y(5, 7), Some(319));
+        assert_eq!(heightmap.get(5, 7), Some(384));
+
+        // 7 values of 9 bits per long
+        let longs = heightmap.to_longs();
+        assert_eq!(longs.len(), 37);
+        let values = unpack(&longs, 9, COLUMNS);
+        assert_eq!(values[5 + 7 * 16], 384);
+        assert_eq!(values[0], 4);
+    }
+
+    #[test]
+    fn test_empty_column() {
+        let heightmap =
+            Heightmap::compute(&Chunk::new_overworld(0, 0), HeightmapKind::WorldSurface);
+        assert_eq!(heightmap.get(0, 0), Some(0));
+        assert_eq!(heightmap.get_top_block_y(0, 0), None);
+        assert_eq!(heightmap.get(16, 0), None);
+    }
+}
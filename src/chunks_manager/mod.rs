@@ -1,36 +1,335 @@
-struct ChunkSection {
-    blocks: [[[u16; 16]; 16]; 4], // 4 for the 4 layers, 16 for the chunk border, u16 for the blocks positions
+use generator::Generator;
+use pos::{BlockPos, ChunkPos};
+use thiserror::Error;
+
+pub mod generator;
+pub mod generator_settings;
+pub mod heightmap;
+pub mod neighbor_updates;
+pub mod pos;
+
+/// Number of blocks along each axis of a chunk section.
+pub const SECTION_WIDTH: usize = 16;
+
+/// Number of blocks in a chunk section.
+pub const SECTION_VOLUME: usize = SECTION_WIDTH * SECTION_WIDTH * SECTION_WIDTH;
+
+/// A block state ID, as sent over the network.
+pub type BlockState = u16;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkError {
+    #[error("Local block coordinates out of bounds: ({0}, {1}, {2})")]
+    LocalOutOfBounds(usize, usize, usize),
+
+    #[error("Block ({0}, {1}, {2}) is not inside chunk ({3}, {4})")]
+    OutsideChunk(i32, i32, i32, i32, i32),
+}
+
+/// A 16x16x16 cube of blocks.
+///
+/// Blocks are stored as `blocks[y][z][x]`, which is the same YZX ordering the protocol uses
+/// when serializing a section. Use `get_block` and `set_block` instead of indexing by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSection {
+    blocks: [[[BlockState; SECTION_WIDTH]; SECTION_WIDTH]; SECTION_WIDTH],
+}
+
+impl Default for ChunkSection {
+    fn default() -> Self {
+        Self {
+            blocks: [[[0; SECTION_WIDTH]; SECTION_WIDTH]; SECTION_WIDTH],
+        }
+    }
+}
+
+impl ChunkSection {
+    /// Returns an empty (air only) section.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the block at the section-local coordinates, or `None` if out of bounds.
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> Option<BlockState> {
+        if !Self::in_bounds(x, y, z) {
+            return None;
+        }
+        Some(self.blocks[y][z][x])
+    }
+
+    /// Sets the block at the section-local coordinates.
+    pub fn set_block(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        state: BlockState,
+    ) -> Result<(), ChunkError> {
+        if !Self::in_bounds(x, y, z) {
+            return Err(ChunkError::LocalOutOfBounds(x, y, z));
+        }
+        self.blocks[y][z][x] = state;
+        Ok(())
+    }
+
+    /// Returns every block of the section in YZX order (x changes fastest).
+    pub fn states(&self) -> impl Iterator<Item = BlockState> + '_ {
+        self.blocks.iter().flatten().flatten().copied()
+    }
+
+    /// Builds a section from `SECTION_VOLUME` blocks in YZX order (x changes fastest).
+    pub fn from_states(states: &[BlockState]) -> Option<Self> {
+        if states.len() != SECTION_VOLUME {
+            return None;
+        }
+
+        let mut section = Self::default();
+        for (i, state) in states.iter().enumerate() {
+            let x = i % SECTION_WIDTH;
+            let z = (i / SECTION_WIDTH) % SECTION_WIDTH;
+            let y = i / (SECTION_WIDTH * SECTION_WIDTH);
+            section.blocks[y][z][x] = *state;
+        }
+        Some(section)
+    }
+
+    fn in_bounds(x: usize, y: usize, z: usize) -> bool {
+        x < SECTION_WIDTH && y < SECTION_WIDTH && z < SECTION_WIDTH
+    }
 }
-pub struct Chunck {
+
+/// Lowest block Y coordinate of the overworld.
+pub const OVERWORLD_MIN_Y: i32 = -64;
+
+/// Height of the overworld in blocks (from y=-64 to y=319).
+pub const OVERWORLD_HEIGHT: i32 = 384;
+
+/// Number of sections in an overworld chunk.
+pub const OVERWORLD_SECTION_COUNT: usize = OVERWORLD_HEIGHT as usize / SECTION_WIDTH;
+
+/// A column of chunk sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
     x: i32,
     z: i32,
+    /// Section Y coordinate (block Y / 16) of the lowest section. -4 in the overworld.
+    min_section_y: i32,
     sections: Vec<ChunkSection>,
 }
 
-fn generate_world(x: i32, z: i32) -> Chunck {
-    let mut blocks = [[[0u16; 16]; 16]; 4];
+impl Chunk {
+    /// Creates an empty chunk at chunk coordinates `x`, `z` with `section_count` sections, the
+    /// lowest one being at section Y coordinate `min_section_y`.
+    pub fn new(x: i32, z: i32, min_section_y: i32, section_count: usize) -> Self {
+        Self {
+            x,
+            z,
+            min_section_y,
+            sections: vec![ChunkSection::default(); section_count],
+        }
+    }
+
+    /// Creates an empty overworld chunk (y=-64 to y=319) at chunk coordinates `x`, `z`.
+    pub fn new_overworld(x: i32, z: i32) -> Self {
+        Self::new(
+            x,
+            z,
+            OVERWORLD_MIN_Y.div_euclid(SECTION_WIDTH as i32),
+            OVERWORLD_SECTION_COUNT,
+        )
+    }
 
-    for y in 0..4 {
-        //height
-        for z in 0..16 {
-            // widht
-            for x in 0..16 {
-                //widht
-                blocks[y][z][x] = match y {
-                    0 => 1,     //bedrock
-                    1 | 2 => 2, //dirt 1 | 2 -> layers 1 and 2
-                    3 => 3,     //grass
-                    _ => 0,     //air
-                };
-            }
+    /// Creates a chunk at chunk coordinates `x`, `z` from already built sections, the lowest one
+    /// being at section Y coordinate `min_section_y`.
+    pub fn from_sections(x: i32, z: i32, min_section_y: i32, sections: Vec<ChunkSection>) -> Self {
+        Self {
+            x,
+            z,
+            min_section_y,
+            sections,
         }
     }
 
-    todo!();
+    /// The chunk X coordinate.
+    pub fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    /// The chunk Z coordinate.
+    pub fn get_z(&self) -> i32 {
+        self.z
+    }
 
-    //Chunck {
-    //    x,
-    //    z,
-    //    sections: blocks,
-    //}
+    /// The section Y coordinate of the lowest section (`yPos` in the chunk NBT).
+    pub fn get_min_section_y(&self) -> i32 {
+        self.min_section_y
+    }
+
+    /// The lowest block Y coordinate of this chunk.
+    pub fn get_min_y(&self) -> i32 {
+        self.min_section_y * SECTION_WIDTH as i32
+    }
+
+    /// The number of blocks between the lowest and the highest block of this chunk.
+    pub fn get_height(&self) -> i32 {
+        (self.sections.len() * SECTION_WIDTH) as i32
+    }
+
+    /// The sections of this chunk, from the bottom one to the top one.
+    pub fn get_sections(&self) -> &[ChunkSection] {
+        &self.sections
+    }
+
+    /// Returns the block at world coordinates `x`, `y`, `z`.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Result<BlockState, ChunkError> {
+        let (section, local_x, local_y, local_z) = self.to_local(x, y, z)?;
+        self.sections[section]
+            .get_block(local_x, local_y, local_z)
+            .ok_or(ChunkError::LocalOutOfBounds(local_x, local_y, local_z))
+    }
+
+    /// Sets the block at world coordinates `x`, `y`, `z`.
+    pub fn set_block(
+        &mut self,
+        x: i32,
+        y: i32,
+        z: i32,
+        state: BlockState,
+    ) -> Result<(), ChunkError> {
+        let (section, local_x, local_y, local_z) = self.to_local(x, y, z)?;
+        self.sections[section].set_block(local_x, local_y, local_z, state)
+    }
+
+    /// Converts world coordinates into (section index, local x, local y, local z).
+    fn to_local(&self, x: i32, y: i32, z: i32) -> Result<(usize, usize, usize, usize), ChunkError> {
+        let block = BlockPos::new(x, y, z);
+        let outside = ChunkError::OutsideChunk(x, y, z, self.x, self.z);
+
+        if block.chunk() != ChunkPos::new(self.x, self.z) {
+            return Err(outside);
+        }
+
+        let section = block.section_y() - self.min_section_y;
+        if section < 0 || section as usize >= self.sections.len() {
+            return Err(outside);
+        }
+
+        let (local_x, local_y, local_z) = block.local_in_section();
+        Ok((section as usize, local_x, local_y, local_z))
+    }
+}
+
+/// Generates a superflat overworld chunk at chunk coordinates `x`, `z`.
+///
+/// The layers start at the bottom of the world: bedrock at y=-64, dirt at y=-63 and y=-62 and
+/// grass at y=-61.
+pub fn generate_world(x: i32, z: i32) -> Chunk {
+    generator::SuperflatGenerator::overworld().generate_chunk(x, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_corner_blocks() {
+        let mut section = ChunkSection::new();
+
+        section.set_block(0, 0, 0, 1).unwrap();
+        section.set_block(15, 15, 15, 2).unwrap();
+        section.set_block(15, 0, 0, 3).unwrap();
+
+        assert_eq!(section.get_block(0, 0, 0), Some(1));
+        assert_eq!(section.get_block(15, 15, 15), Some(2));
+        assert_eq!(section.get_block(15, 0, 0), Some(3));
+        assert_eq!(section.get_block(0, 0, 15), Some(0));
+    }
+
+    #[test]
+    fn test_section_states_roundtrip() {
+        let mut section = ChunkSection::new();
+        section.set_block(1, 2, 3, 9).unwrap();
+
+        let states: Vec<BlockState> = section.states().collect();
+        assert_eq!(states.len(), SECTION_VOLUME);
+        assert_eq!(states[(2 * SECTION_WIDTH + 3) * SECTION_WIDTH + 1], 9);
+        assert_eq!(ChunkSection::from_states(&states), Some(section));
+        assert_eq!(ChunkSection::from_states(&states[1..]), None);
+    }
+
+    #[test]
+    fn test_section_out_of_bounds() {
+        let mut section = ChunkSection::new();
+
+        assert_eq!(section.get_block(16, 0, 0), None);
+        assert_eq!(
+            section.set_block(0, 16, 0, 1),
+            Err(ChunkError::LocalOutOfBounds(0, 16, 0))
+        );
+    }
+
+    #[test]
+    fn test_chunk_cross_section_y() {
+        let mut chunk = Chunk::new(0, 0, 0, 2);
+
+        chunk.set_block(3, 15, 4, 5).unwrap();
+        chunk.set_block(3, 16, 4, 6).unwrap();
+
+        assert_eq!(chunk.get_block(3, 15, 4), Ok(5));
+        assert_eq!(chunk.get_block(3, 16, 4), Ok(6));
+        assert_eq!(chunk.get_sections()[0].get_block(3, 15, 4), Some(5));
+        assert_eq!(chunk.get_sections()[1].get_block(3, 0, 4), Some(6));
+        assert!(chunk.get_block(3, 32, 4).is_err());
+    }
+
+    #[test]
+    fn test_chunk_negative_coordinates() {
+        let mut chunk = Chunk::new(-1, -1, 0, 1);
+
+        // Block (-1, _, -1) is the last block of chunk (-1, -1).
+        chunk.set_block(-1, 0, -1, 7).unwrap();
+        chunk.set_block(-16, 0, -16, 8).unwrap();
+
+        assert_eq!(chunk.get_sections()[0].get_block(15, 0, 15), Some(7));
+        assert_eq!(chunk.get_sections()[0].get_block(0, 0, 0), Some(8));
+        assert!(chunk.set_block(0, 0, 0, 1).is_err());
+        assert!(chunk.set_block(-17, 0, -1, 1).is_err());
+    }
+
+    #[test]
+    fn test_chunk_negative_y() {
+        let mut chunk = Chunk::new_overworld(0, 0);
+
+        chunk.set_block(0, -64, 0, 1).unwrap();
+        chunk.set_block(0, -1, 0, 2).unwrap();
+        chunk.set_block(0, 319, 0, 3).unwrap();
+
+        assert_eq!(chunk.get_sections()[0].get_block(0, 0, 0), Some(1));
+        assert_eq!(chunk.get_sections()[3].get_block(0, 15, 0), Some(2));
+        assert_eq!(chunk.get_sections()[23].get_block(0, 15, 0), Some(3));
+        assert!(chunk.get_block(0, -65, 0).is_err());
+        assert!(chunk.get_block(0, 320, 0).is_err());
+    }
+
+    #[test]
+    fn test_generate_world_height() {
+        let chunk = generate_world(0, 0);
+
+        assert_eq!(chunk.get_sections().len(), 24);
+        assert_eq!(chunk.get_min_section_y(), -4);
+        assert_eq!(chunk.get_min_y(), -64);
+        assert_eq!(chunk.get_height(), 384);
+        // The lowest section holds y=-64, so bedrock is at its local y=0.
+        assert_eq!(chunk.get_sections()[0].get_block(0, 0, 0), Some(1));
+    }
+
+    #[test]
+    fn test_generate_world_layers() {
+        let chunk = generate_world(2, -3);
+
+        assert_eq!(chunk.get_block(32, -64, -48), Ok(1));
+        assert_eq!(chunk.get_block(47, -62, -33), Ok(2));
+        assert_eq!(chunk.get_block(40, -61, -40), Ok(3));
+        assert_eq!(chunk.get_block(40, -60, -40), Ok(0));
+    }
 }
@@ -0,0 +1,149 @@
+//! The propagation of a block change to the neighboring blocks, e.g. redstone.
+//!
+//! A change can update a neighbor, which changes and updates its own neighbors, and so on. Like
+//! vanilla, the chain started by a single change stops after `max-chained-neighbor-updates`
+//! updates, so that a contraption can't freeze the server with an update storm.
+use std::collections::VecDeque;
+
+use log::warn;
+
+use super::pos::BlockPos;
+use crate::config::Settings;
+
+/// The neighbors updated by a change, in vanilla's order: west, east, down, up, north, south.
+const NEIGHBORS: [(i32, i32, i32); 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// How a chain of updates ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Propagation {
+    /// The number of neighbor updates done.
+    pub updates: usize,
+    /// The first update that wasn't done, if the chain reached the limit.
+    pub first_skipped: Option<BlockPos>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NeighborUpdater {
+    /// The number of updates a chain can do. `None` means no limit.
+    max_chained_updates: Option<usize>,
+}
+
+impl NeighborUpdater {
+    pub fn new(max_chained_updates: Option<usize>) -> Self {
+        Self {
+            max_chained_updates,
+        }
+    }
+
+    /// Uses the `max-chained-neighbor-updates` property, no limit if it's empty or negative.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let max = settings
+            .max_chained_neighbor_updates
+            .and_then(|max| usize::try_from(max).ok());
+        Self::new(max)
+    }
+
+    /// Updates the neighbors of the block changed at `origin`. `update` is called for each
+    /// updated block, and returns whether the block changed, its own neighbors being then updated
+    /// too.
+    // TODO: Call it when a block changes, once the blocks have a behavior.
+    pub fn propagate(
+        &self,
+        origin: BlockPos,
+        mut update: impl FnMut(BlockPos) -> bool,
+    ) -> Propagation {
+        let mut pending: VecDeque<BlockPos> = neighbors(origin).collect();
+        let mut updates = 0;
+
+        while let Some(block) = pending.pop_front() {
+            if self.max_chained_updates.is_some_and(|max| updates >= max) {
+                warn!(
+                    "Too many chained neighbor updates. Skipping the rest. First skipped position: {} {} {}",
+                    block.x, block.y, block.z
+                );
+                return Propagation {
+                    updates,
+                    first_skipped: Some(block),
+                };
+            }
+
+            updates += 1;
+            if update(block) {
+                pending.extend(neighbors(block));
+            }
+        }
+
+        Propagation {
+            updates,
+            first_skipped: None,
+        }
+    }
+}
+
+fn neighbors(block: BlockPos) -> impl Iterator<Item = BlockPos> {
+    NEIGHBORS
+        .into_iter()
+        .map(move |(dx, dy, dz)| BlockPos::new(block.x + dx, block.y + dy, block.z + dz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn from_property(max_chained_neighbor_updates: &str) -> NeighborUpdater {
+        NeighborUpdater::from_settings(&Settings::with(&[(
+            "max-chained-neighbor-updates",
+            max_chained_neighbor_updates,
+        )]))
+    }
+
+    #[test]
+    fn test_chain_stops_at_cap() {
+        let updater = from_property("100");
+        let mut updated = Vec::new();
+
+        // Every block changes, so the chain never ends by itself.
+        let propagation = updater.propagate(BlockPos::new(0, 64, 0), |block| {
+            updated.push(block);
+            true
+        });
+
+        assert_eq!(propagation.updates, 100);
+        assert_eq!(updated.len(), 100);
+        assert!(propagation.first_skipped.is_some());
+        assert_eq!(updated[0], BlockPos::new(-1, 64, 0));
+    }
+
+    #[test]
+    fn test_chain_ends_before_cap() {
+        let updater = NeighborUpdater::new(Some(100));
+
+        // Only the neighbors of the origin are updated.
+        let propagation = updater.propagate(BlockPos::new(0, 64, 0), |_| false);
+        assert_eq!(
+            propagation,
+            Propagation {
+                updates: 6,
+                first_skipped: None,
+            }
+        );
+
+        // A chain along a line, each block changing once, with no limit.
+        let updater = from_property("-1");
+        let mut changed = HashSet::new();
+        let propagation = updater.propagate(BlockPos::new(0, 64, 0), |block| {
+            let on_line = block.y == 64 && block.z == 0 && (1..1000).contains(&block.x);
+            on_line && changed.insert(block)
+        });
+        assert_eq!(propagation.updates, 6 + 999 * 6);
+        assert_eq!(propagation.first_skipped, None);
+    }
+}
@@ -0,0 +1,147 @@
+//! Block, chunk and region coordinates, and the conversions between them.
+//!
+//! The conversions round toward negative infinity: block x=-1 is in chunk x=-1, not 0. That's
+//! what arithmetic shifts do on `i32`, unlike `/`.
+
+/// Log2 of the width of a chunk in blocks.
+const CHUNK_SHIFT: u32 = 4;
+/// Log2 of the width of a region in chunks.
+const REGION_SHIFT: u32 = 5;
+
+/// The width of a region in chunks.
+pub const REGION_WIDTH: usize = 1 << REGION_SHIFT;
+
+/// The coordinates of a block in the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl BlockPos {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// The chunk containing the block.
+    pub fn chunk(self) -> ChunkPos {
+        ChunkPos::from_block(self)
+    }
+
+    /// The Y coordinate of the chunk section containing the block.
+    pub fn section_y(self) -> i32 {
+        self.y >> CHUNK_SHIFT
+    }
+
+    /// The coordinates of the block inside its chunk section, each in `0..16`.
+    pub fn local_in_section(self) -> (usize, usize, usize) {
+        let mask = (1 << CHUNK_SHIFT) - 1;
+        (
+            (self.x & mask) as usize,
+            (self.y & mask) as usize,
+            (self.z & mask) as usize,
+        )
+    }
+}
+
+/// The coordinates of a chunk, in chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk containing `block`.
+    pub fn from_block(block: BlockPos) -> Self {
+        Self::new(block.x >> CHUNK_SHIFT, block.z >> CHUNK_SHIFT)
+    }
+
+    /// The region containing the chunk.
+    pub fn region(self) -> RegionPos {
+        RegionPos::from_chunk(self)
+    }
+
+    /// The coordinates of the chunk inside its region, each in `0..32`.
+    pub fn local_in_region(self) -> (usize, usize) {
+        let mask = REGION_WIDTH as i32 - 1;
+        ((self.x & mask) as usize, (self.z & mask) as usize)
+    }
+
+    /// The index of the chunk in the location table of its region file.
+    pub fn index_in_region(self) -> usize {
+        let (x, z) = self.local_in_region();
+        x + z * REGION_WIDTH
+    }
+
+    /// The lowest X and Z block coordinates of the chunk.
+    pub fn min_block(self) -> (i32, i32) {
+        (self.x << CHUNK_SHIFT, self.z << CHUNK_SHIFT)
+    }
+}
+
+/// The coordinates of a region, in regions. A region is 32x32 chunks stored in one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegionPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl RegionPos {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// The region containing `chunk`.
+    pub fn from_chunk(chunk: ChunkPos) -> Self {
+        Self::new(chunk.x >> REGION_SHIFT, chunk.z >> REGION_SHIFT)
+    }
+
+    /// The name of the region file, e.g. `r.0.-1.mca`.
+    pub fn file_name(self) -> String {
+        format!("r.{}.{}.mca", self.x, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negative_block() {
+        // `-1 / 16` is 0, but block -1 is in chunk -1.
+        let block = BlockPos::new(-1, -64, -17);
+        assert_eq!(block.chunk(), ChunkPos::new(-1, -2));
+        assert_eq!(block.section_y(), -4);
+        assert_eq!(block.local_in_section(), (15, 0, 15));
+
+        let block = BlockPos::new(16, 15, 0);
+        assert_eq!(block.chunk(), ChunkPos::new(1, 0));
+        assert_eq!(block.local_in_section(), (0, 15, 0));
+    }
+
+    #[test]
+    fn test_negative_chunk() {
+        let chunk = ChunkPos::new(-1, -33);
+        assert_eq!(chunk.region(), RegionPos::new(-1, -2));
+        assert_eq!(chunk.local_in_region(), (31, 31));
+        assert_eq!(chunk.index_in_region(), 1023);
+        assert_eq!(chunk.min_block(), (-16, -528));
+
+        let chunk = ChunkPos::new(32, 5);
+        assert_eq!(chunk.region(), RegionPos::new(1, 0));
+        assert_eq!(chunk.local_in_region(), (0, 5));
+        assert_eq!(chunk.index_in_region(), 160);
+    }
+
+    #[test]
+    fn test_region_file_name() {
+        let region = ChunkPos::new(-1, 40).region();
+        assert_eq!(region.file_name(), "r.-1.1.mca");
+    }
+}
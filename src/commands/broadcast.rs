@@ -0,0 +1,139 @@
+//! Sends the output of the console and RCON commands to the online operators, as set by the
+//! `broadcast-console-to-ops` and `broadcast-rcon-to-ops` properties.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use super::CommandSource;
+use crate::config::Settings;
+use crate::player::online::{self, OnlinePlayers};
+
+static OP_BROADCAST: Lazy<Arc<OpBroadcast>> =
+    Lazy::new(|| Arc::new(OpBroadcast::new(online::global())));
+
+/// Returns the broadcast of the server's command outputs.
+pub fn global() -> Arc<OpBroadcast> {
+    OP_BROADCAST.clone()
+}
+
+/// Reads the broadcast properties of `settings`. Nothing is broadcast before.
+pub fn init(settings: &Settings) {
+    OP_BROADCAST.configure(settings);
+}
+
+#[derive(Debug)]
+pub struct OpBroadcast {
+    online: Arc<RwLock<OnlinePlayers>>,
+    console: AtomicBool,
+    rcon: AtomicBool,
+}
+
+impl OpBroadcast {
+    /// A broadcast to the operators of `online`, off until configured.
+    pub fn new(online: Arc<RwLock<OnlinePlayers>>) -> Self {
+        Self {
+            online,
+            console: AtomicBool::new(false),
+            rcon: AtomicBool::new(false),
+        }
+    }
+
+    pub fn configure(&self, settings: &Settings) {
+        self.console
+            .store(settings.broadcast_console_to_ops, Ordering::Relaxed);
+        self.rcon
+            .store(settings.broadcast_rcon_to_ops, Ordering::Relaxed);
+    }
+
+    /// Sends `output`, of a command executed by `source`, to the online operators if the
+    /// properties say so. Returns how many operators received it.
+    pub fn send(&self, source: &CommandSource, output: &str) -> usize {
+        let (enabled, name) = match source {
+            CommandSource::Console => (&self.console, "Server"),
+            CommandSource::Rcon => (&self.rcon, "Rcon"),
+            _ => return 0,
+        };
+        if output.is_empty() || !enabled.load(Ordering::Relaxed) {
+            return 0;
+        }
+
+        let Ok(online) = self.online.read() else {
+            return 0;
+        };
+        // Like vanilla, e.g. "[Server: Made Steve a server operator]".
+        online.send_to_ops(&format!("[{name}: {output}]"), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::commands::{Command, CommandError, CommandRegistry};
+
+    struct SayCommand;
+
+    #[async_trait]
+    impl Command for SayCommand {
+        fn name(&self) -> &'static str {
+            "say"
+        }
+
+        fn usage(&self) -> &'static str {
+            "say <message>"
+        }
+
+        async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+            Ok(args.join(" "))
+        }
+    }
+
+    fn settings(console: &str) -> Settings {
+        Settings::with(&[
+            ("broadcast-console-to-ops", console),
+            ("broadcast-rcon-to-ops", "false"),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_console_to_ops() {
+        let online = Arc::new(RwLock::new(OnlinePlayers::default()));
+        let mut op = online.write().unwrap().join("Alice", 4);
+        let mut player = online.write().unwrap().join("Bob", 0);
+        let broadcast = Arc::new(OpBroadcast::new(online));
+        broadcast.configure(&settings("true"));
+
+        let mut registry = CommandRegistry::new();
+        registry
+            .broadcast_to_ops(broadcast.clone())
+            .register(SayCommand);
+
+        registry
+            .dispatch(&CommandSource::Console, "say hello")
+            .await
+            .unwrap();
+        assert_eq!(op.try_recv().unwrap(), "[Server: hello]");
+        assert!(player.try_recv().is_err());
+
+        // Not from RCON, whose broadcast is off, nor from a player.
+        registry
+            .dispatch(&CommandSource::Rcon, "say hi")
+            .await
+            .unwrap();
+        let source = CommandSource::Player {
+            name: "Alice".to_string(),
+            op_level: 4,
+        };
+        registry.dispatch(&source, "say hey").await.unwrap();
+        assert!(op.try_recv().is_err());
+
+        broadcast.configure(&settings("false"));
+        registry
+            .dispatch(&CommandSource::Console, "say hello")
+            .await
+            .unwrap();
+        assert!(op.try_recv().is_err());
+    }
+}
@@ -1,64 +1,219 @@
-use std::{thread, time::Duration};
+use std::sync::{Arc, Mutex};
 
-use colored::Colorize;
 use log::{debug, info, warn};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use tokio::sync::mpsc;
 
-use crate::{consts, fs_manager, player};
+use super::history::{self, History};
+use super::{CommandRegistry, CommandSource};
 
 // Asynchronously handles user input. It never returns
+pub async fn handle_input(registry: CommandRegistry) -> ! {
+    let registry = Arc::new(registry);
+    let (sender, lines) = mpsc::unbounded_channel();
+    let history = history::global();
+    let helper = ConsoleHelper {
+        registry: registry.clone(),
+    };
+    // The line editor blocks on the terminal.
+    std::thread::Builder::new()
+        .name("Console".to_string())
+        .spawn(move || edit_lines(helper, &history, sender))
+        .expect("Failed to spawn the console thread");
+    handle_lines(&registry, lines).await;
 
-// TODO: IMPLEMENT COMMANDS SEPARATELY FROM THIS FUNCTION, otherwise the code will just be as good as a dumpster fire
-// TODO: use the 'Command Pattern' and command handlers
-pub async fn handle_input() -> ! {
-    let mut reader = BufReader::new(tokio::io::stdin());
-    let mut buffer = String::new();
+    // The console is closed (EOF), the server keeps running without it.
+    std::future::pending().await
+}
+
+/// Completes the console input with Tab, see `CommandRegistry::completions`.
+struct ConsoleHelper {
+    registry: Arc<CommandRegistry>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        // The completions replace the word under the cursor.
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        Ok((start, self.registry.completions(line)))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+/// Reads the console with a line editor, until the end of the input: the line can be edited, the
+/// previous commands recalled with the arrow keys and the commands completed with Tab. Each line
+/// is expanded and recorded in `history` by `accept`, then sent to `lines`.
+fn edit_lines(
+    helper: ConsoleHelper,
+    history: &Mutex<History>,
+    lines: mpsc::UnboundedSender<String>,
+) {
+    let mut editor = match Editor::<ConsoleHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            warn!("Failed to open the console, commands can't be entered: {e}");
+            return;
+        }
+    };
+    editor.set_helper(Some(helper));
+    for entry in history.lock().unwrap().entries() {
+        let _ = editor.add_history_entry(entry);
+    }
 
     loop {
-        buffer.clear();
-        if let Ok(bytes_read) = reader.read_line(&mut buffer).await {
-            if bytes_read == 0 {
-                continue; // EOF
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // The terminal doesn't send Ctrl+C to the Ctrl+C handler while editing.
+            Err(ReadlineError::Interrupted) => "stop".to_string(),
+            Err(ReadlineError::Eof) => return,
+            Err(e) => {
+                warn!("Failed to read the console input: {e}");
+                return;
             }
+        };
+
+        let Some(line) = accept(history, &line) else {
+            continue;
+        };
+        let _ = editor.add_history_entry(&line);
+        if lines.send(line).is_err() {
+            return;
+        }
+    }
+}
+
+/// Expands the recalls of a line typed in the console (e.g. `!!`) and records it in `history`.
+/// `None` if there is nothing to execute.
+pub fn accept(history: &Mutex<History>, line: &str) -> Option<String> {
+    debug!("you entered: {line}");
+
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let mut history = history.lock().unwrap();
+    let Some(line) = history.expand(line) else {
+        warn!("No command in the history matches {}", line.trim());
+        return None;
+    };
+    history.push(&line);
+    Some(line)
+}
+
+/// Executes every line of `lines` as a console command, until the console closes.
+pub async fn handle_lines(registry: &CommandRegistry, mut lines: mpsc::UnboundedReceiver<String>) {
+    while let Some(line) = lines.recv().await {
+        match registry.dispatch(&CommandSource::Console, &line).await {
+            Ok(output) => info!("{output}"),
+            Err(e) => warn!("{e}"),
         }
+    }
+}
 
-        debug!("you entered: {buffer}");
-        // Debug/test logic down here
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::commands::{Command, CommandError};
+
+    /// Records its executions instead of acting, e.g. a `stop` that doesn't exit.
+    struct RecordingCommand {
+        name: &'static str,
+        executed: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Command for RecordingCommand {
+        fn name(&self) -> &'static str {
+            self.name
+        }
 
-        if buffer.trim().to_lowercase() == "stop" {
-            let content = "Server will stop in few second…";
-            warn!("{}", content.red().bold());
-            thread::sleep(Duration::from_secs(1));
-            crate::gracefully_exit(-1000);
+        fn usage(&self) -> &'static str {
+            self.name
         }
-        //made a server operator (level 4)
-
-        if buffer.trim().to_lowercase().starts_with("op") {
-            let mut parts = buffer.split_whitespace();
-            parts.next();
-
-            if let Some(element) = parts.next() {
-                let uuid = match player::get_uuid(element).await {
-                    Ok(body) => body,
-                    Err(_) => String::from("not found"),
-                };
-                let content = match fs_manager::write_ops_json(
-                    consts::file_paths::OPERATORS,
-                    uuid.as_str(),
-                    element,
-                    4,
-                    true,
-                ) {
-                    Ok(_) => format!("Made {} a server operator.", element),
-                    Err(e) => format!(
-                        "Failed to make {} as a server operator, error: {} ",
-                        element, e
-                    ),
-                };
-                info!("{}", content);
-            } else {
-                warn!("Missing one argument: op <-")
+
+        async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+            let line = [self.name].iter().chain(args).copied().collect::<Vec<_>>();
+            self.executed.lock().unwrap().push(line.join(" "));
+            Ok(String::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_input() {
+        let executed = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = CommandRegistry::new();
+        for name in ["op", "stop"] {
+            registry.register(RecordingCommand {
+                name,
+                executed: executed.clone(),
+            });
+        }
+
+        let history = Mutex::new(History::new(history::CAPACITY));
+        let (sender, lines) = mpsc::unbounded_channel();
+        for line in ["op Steve", "", "unknown", "stop", "!op", "!missing"] {
+            if let Some(line) = accept(&history, line) {
+                sender.send(line).unwrap();
             }
         }
+        drop(sender);
+        handle_lines(&registry, lines).await;
+
+        assert_eq!(*executed.lock().unwrap(), ["op Steve", "stop", "op Steve"]);
+        let history = history.into_inner().unwrap();
+        let entries: Vec<&str> = history.entries().collect();
+        assert_eq!(entries, ["op Steve", "unknown", "stop", "op Steve"]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let executed = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = CommandRegistry::new();
+        registry
+            .register(RecordingCommand {
+                name: "stop",
+                executed,
+            })
+            .alias("halt", "stop");
+        let helper = ConsoleHelper {
+            registry: Arc::new(registry),
+        };
+        let history = DefaultHistory::new();
+        let ctx = Context::new(&history);
+
+        assert_eq!(
+            helper.complete("st", 2, &ctx).unwrap(),
+            (0, vec!["stop".to_string()])
+        );
+        assert_eq!(
+            helper.complete("h stop", 1, &ctx).unwrap(),
+            (0, vec!["halt".to_string()])
+        );
+        assert_eq!(helper.complete("stop n", 6, &ctx).unwrap(), (5, vec![]));
     }
 }
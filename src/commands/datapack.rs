@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use log::warn;
+
+use super::{Command, CommandError};
+use crate::world::datapack::{self, DataPacks};
+
+/// Lists, enables and disables the data packs of the world, without a restart.
+// TODO: Resend the registries to the players (reconfiguration) once the Configuration state
+// exists.
+pub struct DatapackCommand {
+    packs: Arc<RwLock<DataPacks>>,
+    /// The `datapacks` folder of the world.
+    dir: PathBuf,
+}
+
+impl DatapackCommand {
+    pub fn new(packs: Arc<RwLock<DataPacks>>, dir: PathBuf) -> Self {
+        Self { packs, dir }
+    }
+
+    fn list(&self, packs: &DataPacks) -> String {
+        let available: Vec<String> = datapack::available(&self.dir)
+            .into_iter()
+            .filter(|pack| !packs.is_enabled(pack))
+            .collect();
+
+        let enabled = format!(
+            "There are {} data pack(s) enabled: {}",
+            packs.enabled().len(),
+            brackets(packs.enabled())
+        );
+        if available.is_empty() {
+            return format!("{enabled}\nThere are no more data packs available");
+        }
+        format!(
+            "{enabled}\nThere are {} data pack(s) available: {}",
+            available.len(),
+            brackets(&available)
+        )
+    }
+
+    fn enable(&self, packs: &mut DataPacks, pack: &str) -> Result<String, CommandError> {
+        if !datapack::available(&self.dir)
+            .iter()
+            .any(|available| available == pack)
+        {
+            return Err(CommandError::Failed(format!("Unknown data pack '{pack}'")));
+        }
+        if !packs.enable(pack) {
+            return Err(CommandError::Failed(format!(
+                "Pack '{pack}' is already enabled!"
+            )));
+        }
+        Ok(format!("Enabled data pack [{pack}]"))
+    }
+
+    fn disable(&self, packs: &mut DataPacks, pack: &str) -> Result<String, CommandError> {
+        if !packs.disable(pack) {
+            return Err(CommandError::Failed(format!(
+                "Pack '{pack}' is not enabled!"
+            )));
+        }
+        Ok(format!("Disabled data pack [{pack}]"))
+    }
+
+    /// Looks at the `datapacks` folder again, disabling the enabled packs that are gone.
+    fn reload(&self, packs: &mut DataPacks) -> String {
+        let available = datapack::available(&self.dir);
+        let gone: Vec<String> = packs
+            .enabled()
+            .iter()
+            .filter(|pack| !available.contains(pack))
+            .cloned()
+            .collect();
+        for pack in &gone {
+            warn!("Data pack {pack} is missing, disabling it");
+            packs.disable(pack);
+        }
+        format!(
+            "Reloaded {} enabled data pack(s) out of {} available",
+            packs.enabled().len(),
+            available.len()
+        )
+    }
+}
+
+/// `[a], [b]`, like vanilla lists the packs.
+fn brackets(packs: &[String]) -> String {
+    packs
+        .iter()
+        .map(|pack| format!("[{pack}]"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[async_trait]
+impl Command for DatapackCommand {
+    fn name(&self) -> &'static str {
+        "datapack"
+    }
+
+    fn usage(&self) -> &'static str {
+        "datapack <list|enable <name>|disable <name>|reload>"
+    }
+
+    fn required_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let mut packs = self
+            .packs
+            .write()
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+
+        match args {
+            ["list"] => Ok(self.list(&packs)),
+            ["enable", pack] => self.enable(&mut packs, pack),
+            ["disable", pack] => self.disable(&mut packs, pack),
+            ["reload"] => Ok(self.reload(&mut packs)),
+            _ => Err(CommandError::InvalidUsage(self.usage().to_string())),
+        }
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        ["list", "enable", "disable", "reload"]
+            .map(String::from)
+            .to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn command(dir: &std::path::Path) -> (DatapackCommand, Arc<RwLock<DataPacks>>) {
+        let packs = Arc::new(RwLock::new(DataPacks::default()));
+        (
+            DatapackCommand::new(packs.clone(), dir.to_path_buf()),
+            packs,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_enable_updates_enabled_packs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("custom")).unwrap();
+        let (command, packs) = command(temp_dir.path());
+
+        assert_eq!(
+            command.execute(&["enable", "file/custom"]).await.unwrap(),
+            "Enabled data pack [file/custom]"
+        );
+        assert_eq!(packs.read().unwrap().enabled(), ["vanilla", "file/custom"]);
+
+        assert!(command.execute(&["enable", "file/custom"]).await.is_err());
+        assert_eq!(
+            command.execute(&["enable", "file/missing"]).await,
+            Err(CommandError::Failed(
+                "Unknown data pack 'file/missing'".to_string()
+            ))
+        );
+
+        assert!(command.execute(&["disable", "vanilla"]).await.is_ok());
+        assert!(command.execute(&["disable", "vanilla"]).await.is_err());
+        assert_eq!(packs.read().unwrap().enabled(), ["file/custom"]);
+
+        // The pack is deleted.
+        fs::remove_dir(temp_dir.path().join("custom")).unwrap();
+        command.execute(&["reload"]).await.unwrap();
+        assert!(packs.read().unwrap().enabled().is_empty());
+
+        assert!(command.execute(&["enable"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("custom")).unwrap();
+        let (command, _) = command(temp_dir.path());
+
+        assert_eq!(
+            command.execute(&["list"]).await.unwrap(),
+            "There are 1 data pack(s) enabled: [vanilla]\n\
+             There are 4 data pack(s) available: [trade_rebalance], [redstone_experiments], \
+             [minecart_improvements], [file/custom]"
+        );
+
+        for pack in [
+            "trade_rebalance",
+            "redstone_experiments",
+            "minecart_improvements",
+            "file/custom",
+        ] {
+            command.execute(&["enable", pack]).await.unwrap();
+        }
+        assert!(command
+            .execute(&["list"])
+            .await
+            .unwrap()
+            .ends_with("There are no more data packs available"));
+    }
+}
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::metrics;
+
+/// Prints a snapshot of the server metrics.
+pub struct DebugCommand;
+
+#[async_trait]
+impl Command for DebugCommand {
+    fn name(&self) -> &'static str {
+        "debug"
+    }
+
+    fn usage(&self) -> &'static str {
+        "debug"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+        Ok(format!("Server metrics:\n{}", metrics::global().snapshot()))
+    }
+}
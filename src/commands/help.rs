@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+
+/// Lists the usage of every command.
+pub struct HelpCommand {
+    /// Sorted, including this command.
+    usages: Vec<&'static str>,
+}
+
+impl HelpCommand {
+    /// `usages` are the usages of the other commands, see `CommandRegistry::usages`.
+    pub fn new(mut usages: Vec<&'static str>) -> Self {
+        usages.push("help");
+        usages.sort_unstable();
+        Self { usages }
+    }
+}
+
+#[async_trait]
+impl Command for HelpCommand {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn usage(&self) -> &'static str {
+        "help"
+    }
+
+    fn required_level(&self) -> u8 {
+        0
+    }
+
+    async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+        Ok(format!("Commands:\n{}", self.usages.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_help() {
+        let help = HelpCommand::new(vec!["weather <clear|rain|thunder>", "debug"]);
+        assert_eq!(
+            help.execute(&[]).await,
+            Ok("Commands:\ndebug\nhelp\nweather <clear|rain|thunder>".to_string())
+        );
+    }
+}
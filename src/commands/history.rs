@@ -0,0 +1,225 @@
+//! The history of the console commands, kept across restarts in `logs/command_history.txt`.
+//!
+//! A previous command is recalled with the arrow keys of the console, or like in a shell: `!!` is
+//! the last command, `!<n>` the n-th of `history`, and `!<prefix>` the last one starting with
+//! `prefix`.
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use log::debug;
+use once_cell::sync::Lazy;
+
+use super::{Command, CommandError};
+use crate::consts;
+
+/// Number of commands kept.
+pub const CAPACITY: usize = 500;
+
+static HISTORY: Lazy<Arc<Mutex<History>>> = Lazy::new(|| {
+    let path = Path::new(consts::file_paths::COMMAND_HISTORY);
+    Arc::new(Mutex::new(History::load(path, CAPACITY)))
+});
+
+/// Returns the history of the console.
+pub fn global() -> Arc<Mutex<History>> {
+    HISTORY.clone()
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    /// The oldest first.
+    entries: VecDeque<String>,
+    capacity: usize,
+    /// Where the commands are appended, `None` to keep them in memory only.
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// A history kept in memory only.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+            path: None,
+        }
+    }
+
+    /// Reads the last `capacity` commands of the file at `path`, missing if there is no history
+    /// yet. The file is trimmed to them, and the next commands are appended to it.
+    pub fn load(path: &Path, capacity: usize) -> Self {
+        let mut history = Self::new(capacity);
+        if let Ok(content) = fs::read_to_string(path) {
+            content.lines().for_each(|line| history.remember(line));
+            if content.lines().count() > capacity {
+                if let Err(e) = history.rewrite(path) {
+                    debug!("Failed to trim the command history: {e}");
+                }
+            }
+        }
+        history.path = Some(path.to_path_buf());
+        history
+    }
+
+    /// Replaces the file at `path` with the commands kept.
+    fn rewrite(&self, path: &Path) -> io::Result<()> {
+        let content: String = self.entries().map(|entry| format!("{entry}\n")).collect();
+        fs::write(path, content)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Records a submitted command, unless it's blank or the same as the previous one.
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.back().is_some_and(|last| last == line) {
+            return;
+        }
+        self.remember(line);
+
+        if let Some(path) = &self.path {
+            // The console works without it, e.g. before `logs/` is created.
+            if let Err(e) = append_line(path, line) {
+                debug!("Failed to save the command history: {e}");
+            }
+        }
+    }
+
+    fn remember(&mut self, line: &str) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(line.to_string());
+    }
+
+    /// Replaces a recall (`!!`, `!<n>` or `!<prefix>`) with the command it designates. Other lines
+    /// are returned as is. `None` if nothing matches the recall.
+    pub fn expand(&self, line: &str) -> Option<String> {
+        let line = line.trim();
+        let Some(recall) = line.strip_prefix('!') else {
+            return Some(line.to_string());
+        };
+
+        let entry = match recall {
+            "!" => self.entries.back(),
+            _ => match recall.parse::<usize>() {
+                Ok(n) => n.checked_sub(1).and_then(|i| self.entries.get(i)),
+                Err(_) => self
+                    .entries
+                    .iter()
+                    .rev()
+                    .find(|entry| !recall.is_empty() && entry.starts_with(recall)),
+            },
+        };
+        entry.cloned()
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Lists the previous commands, numbered for `!<n>`.
+pub struct HistoryCommand {
+    history: Arc<Mutex<History>>,
+}
+
+impl HistoryCommand {
+    pub fn new(history: Arc<Mutex<History>>) -> Self {
+        Self { history }
+    }
+}
+
+#[async_trait]
+impl Command for HistoryCommand {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn usage(&self) -> &'static str {
+        "history"
+    }
+
+    async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+        let history = self
+            .history
+            .lock()
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+
+        let lines: Vec<String> = history
+            .entries()
+            .enumerate()
+            .map(|(i, entry)| format!("{:>4}  {entry}", i + 1))
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut history = History::new(2);
+        history.push("op Steve\n");
+        history.push("op Steve");
+        history.push("  ");
+        history.push("time set day");
+        history.push("weather rain");
+
+        let entries: Vec<&str> = history.entries().collect();
+        assert_eq!(entries, ["time set day", "weather rain"]);
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut history = History::new(CAPACITY);
+        for line in ["time set day", "op Steve", "time set night"] {
+            history.push(line);
+        }
+
+        assert_eq!(history.expand("!!").unwrap(), "time set night");
+        assert_eq!(history.expand("!2").unwrap(), "op Steve");
+        assert_eq!(history.expand("!op").unwrap(), "op Steve");
+        assert_eq!(history.expand("!time").unwrap(), "time set night");
+        assert_eq!(history.expand("stop").unwrap(), "stop");
+        assert_eq!(history.expand("!0"), None);
+        assert_eq!(history.expand("!4"), None);
+        assert_eq!(history.expand("!weather"), None);
+        assert_eq!(history.expand("!"), None);
+    }
+
+    #[test]
+    fn test_persisted() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("command_history.txt");
+
+        let mut history = History::load(&path, CAPACITY);
+        history.push("op Steve");
+        history.push("stop");
+
+        let reloaded = History::load(&path, CAPACITY);
+        assert_eq!(reloaded.entries().collect::<Vec<_>>(), ["op Steve", "stop"]);
+    }
+
+    #[test]
+    fn test_load_trims_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("command_history.txt");
+        fs::write(&path, "time set day\nop Steve\nstop\n").unwrap();
+
+        let mut history = History::load(&path, 2);
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["op Steve", "stop"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "op Steve\nstop\n");
+
+        history.push("list");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "op Steve\nstop\nlist\n");
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::logging::file::LogFile;
+
+/// Shows the current log file, or archives it and starts a fresh one.
+pub struct LogCommand {
+    log_file: Arc<Mutex<Option<LogFile>>>,
+}
+
+impl LogCommand {
+    pub fn new(log_file: Arc<Mutex<Option<LogFile>>>) -> Self {
+        Self { log_file }
+    }
+}
+
+#[async_trait]
+impl Command for LogCommand {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn usage(&self) -> &'static str {
+        "log [roll]"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let mut log_file = self
+            .log_file
+            .lock()
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+        let Some(log_file) = log_file.as_mut() else {
+            return Err(CommandError::Failed(
+                "The logs are not written to a file".to_string(),
+            ));
+        };
+
+        match args {
+            [] => Ok(format!("Logging to {}", log_file.path().display())),
+            [roll] if roll.eq_ignore_ascii_case("roll") => {
+                let archive = log_file
+                    .roll()
+                    .map_err(|e| CommandError::Failed(format!("Failed to roll the log: {e}")))?;
+                Ok(format!("Log archived to {}", archive.display()))
+            }
+            _ => Err(CommandError::InvalidUsage(self.usage().to_string())),
+        }
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        vec!["roll".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::logging::file::LATEST_LOG;
+
+    #[tokio::test]
+    async fn test_log_roll() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut log_file = LogFile::open(temp_dir.path()).unwrap();
+        log_file.write_line("[INFO] Before the roll").unwrap();
+        let command = LogCommand::new(Arc::new(Mutex::new(Some(log_file))));
+
+        let message = command.execute(&["roll"]).await.unwrap();
+
+        let latest = temp_dir.path().join(LATEST_LOG);
+        assert_eq!(fs::read_to_string(&latest).unwrap(), "");
+        let archive = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.to_string_lossy().ends_with("-1.log.gz"))
+            .expect("The log should be archived");
+        assert_eq!(message, format!("Log archived to {}", archive.display()));
+
+        let mut archived = String::new();
+        GzDecoder::new(fs::File::open(&archive).unwrap())
+            .read_to_string(&mut archived)
+            .unwrap();
+        assert_eq!(archived, "[INFO] Before the roll\n");
+
+        // The fresh file is written to, and the next archive of the day gets another name.
+        let log_file = command.log_file.clone();
+        log_file
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .write_line("[INFO] After the roll")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&latest).unwrap(),
+            "[INFO] After the roll\n"
+        );
+        let second = command.execute(&["roll"]).await.unwrap();
+        assert!(second.ends_with("-2.log.gz"));
+    }
+
+    #[tokio::test]
+    async fn test_log_without_file() {
+        let command = LogCommand::new(Arc::new(Mutex::new(None)));
+        assert!(command.execute(&["roll"]).await.is_err());
+    }
+}
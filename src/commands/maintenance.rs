@@ -0,0 +1,80 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::net::maintenance::Maintenance;
+
+/// Turns the maintenance mode on or off.
+pub struct MaintenanceCommand {
+    maintenance: Arc<RwLock<Maintenance>>,
+}
+
+impl MaintenanceCommand {
+    pub fn new(maintenance: Arc<RwLock<Maintenance>>) -> Self {
+        Self { maintenance }
+    }
+}
+
+#[async_trait]
+impl Command for MaintenanceCommand {
+    fn name(&self) -> &'static str {
+        "maintenance"
+    }
+
+    fn usage(&self) -> &'static str {
+        "maintenance <on|off> [message]"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    // TODO: Kick the connected players too, once they can be disconnected.
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let mut maintenance = self
+            .maintenance
+            .write()
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+
+        match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            Some("on") => {
+                let message = Some(args[1..].join(" ")).filter(|message| !message.is_empty());
+                maintenance.enable(message);
+                Ok("Maintenance mode on, new logins are refused".to_string())
+            }
+            Some("off") if args.len() == 1 => {
+                maintenance.disable();
+                Ok("Maintenance mode off".to_string())
+            }
+            _ => Err(CommandError::InvalidUsage(self.usage().to_string())),
+        }
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        ["on", "off"].map(String::from).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_maintenance_command() {
+        let maintenance = Arc::new(RwLock::new(Maintenance::default()));
+        let command = MaintenanceCommand::new(maintenance.clone());
+
+        assert!(command.execute(&["on", "Back", "at", "5"]).await.is_ok());
+        assert_eq!(
+            maintenance.read().unwrap().login_refusal(),
+            Some("Back at 5")
+        );
+
+        assert!(command.execute(&["off"]).await.is_ok());
+        assert!(!maintenance.read().unwrap().is_enabled());
+
+        assert!(command.execute(&["maybe"]).await.is_err());
+        assert!(command.execute(&[]).await.is_err());
+    }
+}
@@ -1,9 +1,74 @@
+pub mod broadcast;
 mod command_line;
+mod datapack;
+mod debug;
+mod help;
+pub mod history;
+mod log;
+mod maintenance;
+mod op;
+mod pvp;
+mod registry;
+mod reload;
+mod source;
+mod spawn;
+mod stop;
+mod time;
+mod weather;
+
+pub use registry::{Command, CommandError, CommandRegistry};
+pub use source::CommandSource;
+
+use std::path::PathBuf;
+
+use crate::{config, consts, logging, net, world};
 
-// TODO: I'll need to implement the 'Command Pattern' here.
 // TODO: I'll also need to implement a sort of queue that stores all received commands.
 
+/// Returns a registry containing every console command.
+pub fn default_registry() -> CommandRegistry {
+    let mut registry = CommandRegistry::new();
+    registry
+        .broadcast_to_ops(broadcast::global())
+        .register(stop::StopCommand)
+        .register(debug::DebugCommand)
+        .register(history::HistoryCommand::new(history::global()))
+        .register(log::LogCommand::new(logging::file::global()))
+        .register(datapack::DatapackCommand::new(
+            world::datapack::global(),
+            PathBuf::from(consts::directory_paths::DATAPACKS),
+        ))
+        .register(op::OpCommand)
+        .register(pvp::PvpCommand::new(world::pvp::global()))
+        .register(maintenance::MaintenanceCommand::new(
+            net::maintenance::global(),
+        ))
+        .register(reload::ReloadIconCommand::new(
+            net::slp::icon::global(),
+            PathBuf::from(consts::file_paths::SERVER_ICON),
+        ))
+        .register(reload::ReloadCommand::new(reload::ReloadIconCommand::new(
+            net::slp::icon::global(),
+            PathBuf::from(consts::file_paths::SERVER_ICON),
+        )))
+        .register(spawn::SpawnCommand::new(
+            config::Settings::new(),
+            PathBuf::from(consts::file_paths::LEVEL_DAT),
+        ))
+        .register(weather::WeatherCommand::new(world::weather::global()))
+        .register(time::TimeCommand::new(
+            world::time::global(),
+            PathBuf::from(consts::file_paths::LEVEL_DAT),
+        ));
+    // TODO: Alias "tp" once there is a "teleport" command.
+    let usages = registry.usages();
+    registry
+        .register(help::HelpCommand::new(usages))
+        .alias("?", "help");
+    registry
+}
+
 // Initializes the listening for cli commands
 pub async fn listen_console_commands() {
-    tokio::spawn(command_line::handle_input());
+    tokio::spawn(command_line::handle_input(default_registry()));
 }
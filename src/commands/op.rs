@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::{config, consts, fs_manager, player};
+
+/// Makes a player a server operator, with the level set by `op-permission-level`.
+pub struct OpCommand;
+
+#[async_trait]
+impl Command for OpCommand {
+    fn name(&self) -> &'static str {
+        "op"
+    }
+
+    fn usage(&self) -> &'static str {
+        "op <player>"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        player::online::global().read().unwrap().names()
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let Some(&name) = args.first() else {
+            return Err(CommandError::InvalidUsage(self.usage().to_string()));
+        };
+
+        let uuid = match player::get_uuid(name).await {
+            Ok(body) => body,
+            Err(_) => String::from("not found"),
+        };
+
+        let level = config::Settings::new().op_permission_level;
+        match fs_manager::write_ops_json(consts::file_paths::OPERATORS, &uuid, name, level, false) {
+            Ok(_) => Ok(format!("Made {} a server operator.", name)),
+            Err(e) => Err(CommandError::Failed(format!(
+                "Failed to make {} as a server operator, error: {} ",
+                name, e
+            ))),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::world::pvp::Pvp;
+
+/// Lets players hurt each other or not, until the server restarts. Without argument, tells
+/// whether they can.
+pub struct PvpCommand {
+    pvp: Arc<RwLock<Pvp>>,
+}
+
+impl PvpCommand {
+    pub fn new(pvp: Arc<RwLock<Pvp>>) -> Self {
+        Self { pvp }
+    }
+}
+
+#[async_trait]
+impl Command for PvpCommand {
+    fn name(&self) -> &'static str {
+        "pvp"
+    }
+
+    fn usage(&self) -> &'static str {
+        "pvp [on|off]"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let mut pvp = self
+            .pvp
+            .write()
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+
+        match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            Some("on") if args.len() == 1 => pvp.set_enabled(true),
+            Some("off") if args.len() == 1 => pvp.set_enabled(false),
+            None => {}
+            _ => return Err(CommandError::InvalidUsage(self.usage().to_string())),
+        }
+
+        Ok(if pvp.is_enabled() {
+            "PvP is on, players can hurt each other".to_string()
+        } else {
+            "PvP is off, players can't hurt each other".to_string()
+        })
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        ["on", "off"].map(String::from).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::pvp::Combatant;
+
+    #[tokio::test]
+    async fn test_pvp_command() {
+        let pvp = Arc::new(RwLock::new(Pvp::default()));
+        let command = PvpCommand::new(pvp.clone());
+
+        assert!(command.execute(&["off"]).await.unwrap().contains("off"));
+        assert!(!pvp.read().unwrap().is_enabled());
+        assert!(!pvp
+            .read()
+            .unwrap()
+            .allows_damage(Combatant::Player, Combatant::Player));
+        assert!(command.execute(&[]).await.unwrap().contains("off"));
+
+        assert!(command.execute(&["ON"]).await.is_ok());
+        assert!(pvp
+            .read()
+            .unwrap()
+            .allows_damage(Combatant::Player, Combatant::Player));
+
+        assert!(command.execute(&["maybe"]).await.is_err());
+        assert!(command.execute(&["on", "now"]).await.is_err());
+        assert!(pvp.read().unwrap().is_enabled());
+    }
+}
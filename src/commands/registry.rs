@@ -0,0 +1,300 @@
+//! The 'Command Pattern': every command implements `Command` and is stored in a
+//! `CommandRegistry`, which parses the input line and dispatches it to the right command.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use super::broadcast::OpBroadcast;
+use super::CommandSource;
+use crate::fs_manager::MAX_OP_LEVEL;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+
+    #[error("Invalid usage, expected: {0}")]
+    InvalidUsage(String),
+
+    #[error("You do not have permission.")]
+    NoPermission,
+
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// A command that can be executed from the console.
+#[async_trait]
+pub trait Command: Send + Sync {
+    /// The name used to invoke the command.
+    fn name(&self) -> &'static str;
+
+    /// How to use the command, e.g. "weather <clear|rain|thunder>".
+    fn usage(&self) -> &'static str;
+
+    /// Executes the command with its arguments (the words after the name).
+    /// Returns the message to show to whoever executed the command.
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError>;
+
+    /// The operator level needed to execute the command. Defaults to the highest level, so that
+    /// commands are restricted unless stated otherwise.
+    fn required_level(&self) -> u8 {
+        MAX_OP_LEVEL
+    }
+
+    /// Possible values of the first argument, used for completion. Must be cheap to compute.
+    fn suggestions(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Stores every known command, by name.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: HashMap<&'static str, Arc<dyn Command>>,
+    /// Alias -> name of the aliased command.
+    aliases: HashMap<&'static str, &'static str>,
+    /// Where the outputs of the console and RCON commands are sent.
+    broadcast: Option<Arc<OpBroadcast>>,
+}
+
+impl CommandRegistry {
+    /// Returns an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a command. A command with the same name is replaced.
+    pub fn register<C: Command + 'static>(&mut self, command: C) -> &mut Self {
+        self.commands.insert(command.name(), Arc::new(command));
+        self
+    }
+
+    /// Makes `alias` invoke the command registered under `name` (e.g. "tp" for "teleport").
+    pub fn alias(&mut self, alias: &'static str, name: &'static str) -> &mut Self {
+        self.aliases.insert(alias, name);
+        self
+    }
+
+    /// Sends the outputs of the commands executed from the console or RCON to the operators,
+    /// according to `broadcast`.
+    pub fn broadcast_to_ops(&mut self, broadcast: Arc<OpBroadcast>) -> &mut Self {
+        self.broadcast = Some(broadcast);
+        self
+    }
+
+    /// Returns the command registered under `name`, or aliased by `name`.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Command>> {
+        let name = self.aliases.get(name).copied().unwrap_or(name);
+        self.commands.get(name).cloned()
+    }
+
+    /// The usages of the registered commands, sorted.
+    pub fn usages(&self) -> Vec<&'static str> {
+        let mut usages: Vec<_> = self
+            .commands
+            .values()
+            .map(|command| command.usage())
+            .collect();
+        usages.sort_unstable();
+        usages
+    }
+
+    /// Completes a partial input line.
+    ///
+    /// Returns the sorted command names and aliases starting with `prefix` if it has only one
+    /// word, or the first-argument suggestions of the command matching the partial argument.
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.trim_start();
+
+        let mut completions: Vec<String> = match prefix.split_once(char::is_whitespace) {
+            None => {
+                let prefix = prefix.to_lowercase();
+                self.commands
+                    .keys()
+                    .chain(self.aliases.keys())
+                    .filter(|name| name.starts_with(&prefix))
+                    .map(|name| name.to_string())
+                    .collect()
+            }
+            Some((name, argument)) => {
+                let argument = argument.trim_start().to_lowercase();
+                // Only the first argument is completed.
+                if argument.contains(char::is_whitespace) {
+                    return Vec::new();
+                }
+
+                self.get(&name.to_lowercase())
+                    .map(|command| command.suggestions())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|suggestion| suggestion.to_lowercase().starts_with(&argument))
+                    .collect()
+            }
+        };
+
+        completions.sort();
+        completions
+    }
+
+    /// Parses an input line (e.g. "weather rain") and executes the matching command, if `source`
+    /// has the permission to.
+    pub async fn dispatch(
+        &self,
+        source: &CommandSource,
+        input: &str,
+    ) -> Result<String, CommandError> {
+        let mut parts = input.split_whitespace();
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let args: Vec<&str> = parts.collect();
+
+        let command = self
+            .get(&name)
+            .ok_or_else(|| CommandError::UnknownCommand(name.clone()))?;
+
+        if source.permission_level() < command.required_level() {
+            return Err(CommandError::NoPermission);
+        }
+
+        let output = command.execute(&args).await?;
+        if let Some(broadcast) = &self.broadcast {
+            broadcast.send(source, &output);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoCommand;
+
+    #[async_trait]
+    impl Command for EchoCommand {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn usage(&self) -> &'static str {
+            "echo <text>"
+        }
+
+        fn required_level(&self) -> u8 {
+            0
+        }
+
+        async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+            if args.is_empty() {
+                return Err(CommandError::InvalidUsage(self.usage().to_string()));
+            }
+            Ok(args.join(" "))
+        }
+    }
+
+    struct WhitelistCommand;
+
+    #[async_trait]
+    impl Command for WhitelistCommand {
+        fn name(&self) -> &'static str {
+            "whitelist"
+        }
+
+        fn usage(&self) -> &'static str {
+            "whitelist <on|off|add|remove|list>"
+        }
+
+        async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+            Ok(String::new())
+        }
+
+        fn suggestions(&self) -> Vec<String> {
+            ["on", "off", "add", "remove", "list"]
+                .map(String::from)
+                .to_vec()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let mut registry = CommandRegistry::new();
+        registry.register(EchoCommand);
+
+        assert_eq!(
+            registry
+                .dispatch(&CommandSource::Console, "ECHO hello  world\n")
+                .await,
+            Ok("hello world".to_string())
+        );
+        assert_eq!(
+            registry.dispatch(&CommandSource::Console, "echo").await,
+            Err(CommandError::InvalidUsage("echo <text>".to_string()))
+        );
+        assert_eq!(
+            registry.dispatch(&CommandSource::Console, "unknown").await,
+            Err(CommandError::UnknownCommand("unknown".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alias() {
+        let mut registry = CommandRegistry::new();
+        registry.register(EchoCommand).alias("say", "echo");
+
+        assert_eq!(
+            registry.dispatch(&CommandSource::Console, "say hi").await,
+            Ok("hi".to_string())
+        );
+        assert!(Arc::ptr_eq(
+            &registry.get("say").unwrap(),
+            &registry.get("echo").unwrap()
+        ));
+        assert_eq!(registry.usages(), ["echo <text>"]);
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut registry = CommandRegistry::new();
+        registry
+            .register(EchoCommand)
+            .register(WhitelistCommand)
+            .alias("wl", "whitelist");
+
+        assert!(registry
+            .completions("wh")
+            .contains(&"whitelist".to_string()));
+        assert_eq!(registry.completions("W"), ["whitelist", "wl"]);
+        assert_eq!(registry.completions(""), ["echo", "whitelist", "wl"]);
+        assert_eq!(registry.completions("wl o"), ["off", "on"]);
+        assert_eq!(
+            registry.completions("whitelist "),
+            ["add", "list", "off", "on", "remove"]
+        );
+        assert!(registry.completions("whitelist add Notch").is_empty());
+        assert!(registry.completions("echo h").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_permission() {
+        let mut registry = CommandRegistry::new();
+        registry.register(EchoCommand).register(WhitelistCommand);
+        let player = |op_level| CommandSource::Player {
+            name: "Notch".to_string(),
+            op_level,
+        };
+
+        // WhitelistCommand requires the default level, 4
+        assert_eq!(
+            registry.dispatch(&player(1), "whitelist on").await,
+            Err(CommandError::NoPermission)
+        );
+        assert!(registry.dispatch(&player(4), "whitelist on").await.is_ok());
+        assert!(registry
+            .dispatch(&CommandSource::Console, "whitelist on")
+            .await
+            .is_ok());
+        assert!(registry.dispatch(&player(0), "echo hi").await.is_ok());
+    }
+}
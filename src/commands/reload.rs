@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::net::slp::{cache, icon::ServerIcon};
+
+/// Reads server-icon.png again, so that a replaced icon shows without a restart.
+pub struct ReloadIconCommand {
+    icon: Arc<RwLock<ServerIcon>>,
+    path: PathBuf,
+}
+
+impl ReloadIconCommand {
+    pub fn new(icon: Arc<RwLock<ServerIcon>>, path: PathBuf) -> Self {
+        Self { icon, path }
+    }
+
+    fn reload(&self) -> Result<String, CommandError> {
+        self.icon
+            .write()
+            .map_err(|e| CommandError::Failed(e.to_string()))?
+            .reload(&self.path)
+            .map_err(|e| CommandError::Failed(e.to_string()))?;
+        // The cached status JSON has the old icon.
+        cache::global().invalidate();
+        Ok(format!("Reloaded {}", self.path.display()))
+    }
+}
+
+#[async_trait]
+impl Command for ReloadIconCommand {
+    fn name(&self) -> &'static str {
+        "reload-icon"
+    }
+
+    fn usage(&self) -> &'static str {
+        "reload-icon"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        if !args.is_empty() {
+            return Err(CommandError::InvalidUsage(self.usage().to_string()));
+        }
+        self.reload()
+    }
+}
+
+/// Reloads everything that can be reloaded at runtime.
+pub struct ReloadCommand {
+    icon: ReloadIconCommand,
+}
+
+impl ReloadCommand {
+    pub fn new(icon: ReloadIconCommand) -> Self {
+        Self { icon }
+    }
+}
+
+#[async_trait]
+impl Command for ReloadCommand {
+    fn name(&self) -> &'static str {
+        "reload"
+    }
+
+    fn usage(&self) -> &'static str {
+        "reload"
+    }
+
+    fn required_level(&self) -> u8 {
+        3
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        if !args.is_empty() {
+            return Err(CommandError::InvalidUsage(self.usage().to_string()));
+        }
+        self.icon.reload()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::net::packet::data_types::string;
+    use crate::net::packet::Packet;
+    use crate::net::slp::{icon, status_response_bytes};
+
+    /// Not the MOTD of the other tests, so that they don't share the cached response.
+    const MOTD: &str = "Reload test";
+
+    /// Writes a blank PNG image of `size`x`size` pixels, of `color`, to `path`.
+    fn write_png(path: &std::path::Path, size: u32, color: u8) {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(size, size, image::Rgba([color; 4]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        std::fs::write(path, png).unwrap();
+    }
+
+    fn favicon(icon: &RwLock<ServerIcon>) -> Option<String> {
+        icon.read().unwrap().data_uri().map(str::to_string)
+    }
+
+    /// The favicon of the cached Status Response, as sent to the clients.
+    fn cached_favicon() -> Option<String> {
+        let packet = Packet::new(&*status_response_bytes(Some(MOTD))).unwrap();
+        let (json, _) = string::read(packet.get_payload()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        json["favicon"].as_str().map(str::to_string)
+    }
+
+    #[tokio::test]
+    async fn test_reload_icon_command() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("server-icon.png");
+        let icon = icon::global();
+        let original = icon.read().unwrap().clone();
+        let command = ReloadIconCommand::new(icon.clone(), path.clone());
+
+        write_png(&path, 64, 0);
+        assert!(command.execute(&[]).await.is_ok());
+        let before = cached_favicon().unwrap();
+
+        write_png(&path, 64, 255);
+        assert!(command.execute(&[]).await.is_ok());
+        let after = cached_favicon().unwrap();
+        assert_ne!(after, before);
+
+        // An invalid icon is reported, the previous one stays.
+        write_png(&path, 32, 0);
+        assert!(matches!(
+            command.execute(&[]).await,
+            Err(CommandError::Failed(_))
+        ));
+        assert_eq!(cached_favicon(), Some(after));
+
+        *icon.write().unwrap() = original;
+        cache::global().invalidate();
+    }
+
+    #[tokio::test]
+    async fn test_reload_command() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("server-icon.png");
+        let icon = Arc::new(RwLock::new(ServerIcon::default()));
+        let command = ReloadCommand::new(ReloadIconCommand::new(icon.clone(), path.clone()));
+
+        write_png(&path, 64, 0);
+        assert!(command.execute(&[]).await.is_ok());
+        assert!(favicon(&icon).is_some());
+        assert!(command.execute(&["now"]).await.is_err());
+    }
+}
@@ -0,0 +1,94 @@
+//! Who executes a command, and with which permission level.
+use crate::config::Settings;
+use crate::fs_manager::{self, MAX_OP_LEVEL};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandSource {
+    /// The server console, which has every permission.
+    Console,
+    /// A remote console, which has every permission too.
+    Rcon,
+    /// A player, with its operator level (0 if it isn't an operator).
+    Player { name: String, op_level: u8 },
+    /// A function (datapack) or a command block, whose level is set by
+    /// `function-permission-level`.
+    Function { permission_level: u8 },
+}
+
+impl CommandSource {
+    /// A player, with the operator level found in 'ops.json'.
+    pub fn player(name: &str, uuid: &str) -> Self {
+        Self::Player {
+            name: name.to_string(),
+            op_level: fs_manager::op_level(uuid).unwrap_or(0),
+        }
+    }
+
+    /// A function or command block, with the level of the `function-permission-level` property.
+    pub fn function(settings: &Settings) -> Self {
+        Self::Function {
+            permission_level: settings.function_permission_level.min(MAX_OP_LEVEL),
+        }
+    }
+
+    /// The permission level commands are checked against.
+    pub fn permission_level(&self) -> u8 {
+        match self {
+            Self::Console | Self::Rcon => MAX_OP_LEVEL,
+            Self::Player { op_level, .. } => *op_level,
+            Self::Function { permission_level } => *permission_level,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{Command, CommandError, CommandRegistry};
+    use async_trait::async_trait;
+
+    /// A command requiring the level it holds.
+    struct LevelCommand(&'static str, u8);
+
+    #[async_trait]
+    impl Command for LevelCommand {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn usage(&self) -> &'static str {
+            self.0
+        }
+
+        fn required_level(&self) -> u8 {
+            self.1
+        }
+
+        async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+            Ok(String::new())
+        }
+    }
+
+    fn settings(function_permission_level: &str) -> Settings {
+        Settings::with(&[("function-permission-level", function_permission_level)])
+    }
+
+    #[tokio::test]
+    async fn test_function_permission_level() {
+        let mut registry = CommandRegistry::new();
+        registry
+            .register(LevelCommand("two", 2))
+            .register(LevelCommand("three", 3));
+
+        let function = CommandSource::function(&settings("2"));
+        assert_eq!(function.permission_level(), 2);
+        assert!(registry.dispatch(&function, "two").await.is_ok());
+        assert_eq!(
+            registry.dispatch(&function, "three").await,
+            Err(CommandError::NoPermission)
+        );
+
+        let function = CommandSource::function(&settings("3"));
+        assert!(registry.dispatch(&function, "three").await.is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use super::{Command, CommandError};
+use crate::chunks_manager::generator::Generators;
+use crate::config::Settings;
+use crate::world::level::{self, LevelData};
+use crate::world::spawn;
+
+/// Regenerates the spawn chunks and recomputes the world spawn point, to test the generation
+/// without creating a new world.
+pub struct SpawnCommand {
+    /// The generation settings (`level-type`, `generator-settings`).
+    settings: Settings,
+    /// Where the seed is read and the spawn point saved.
+    level_path: PathBuf,
+}
+
+impl SpawnCommand {
+    pub fn new(settings: Settings, level_path: PathBuf) -> Self {
+        Self {
+            settings,
+            level_path,
+        }
+    }
+}
+
+#[async_trait]
+impl Command for SpawnCommand {
+    fn name(&self) -> &'static str {
+        "spawn"
+    }
+
+    fn usage(&self) -> &'static str {
+        "spawn"
+    }
+
+    fn required_level(&self) -> u8 {
+        2
+    }
+
+    // TODO: Teleport the player executing the command (or the one given) to the spawn point,
+    // once the players can be teleported.
+    async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+        let seed = level::init_seed(&self.level_path, &self.settings)
+            .map_err(|e| CommandError::Failed(format!("Failed to read level.dat: {e}")))?;
+        let generators = Generators::from_settings(&self.settings, seed);
+        let spawn = spawn::find_spawn(&generators).ok_or_else(|| {
+            CommandError::Failed("No safe spawn point in the spawn chunks".to_string())
+        })?;
+
+        let mut level = LevelData::load(&self.level_path)
+            .map_err(|e| CommandError::Failed(format!("Failed to read level.dat: {e}")))?;
+        level.set_spawn(spawn);
+        level
+            .save(&self.level_path)
+            .map_err(|e| CommandError::Failed(format!("Failed to save level.dat: {e}")))?;
+
+        Ok(format!(
+            "Set the world spawn point to {} {} {}",
+            spawn.x, spawn.y, spawn.z
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::pos::BlockPos;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_spawn_command_superflat() {
+        let settings = Settings::with(&[("level-type", "minecraft:flat")]);
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("level.dat");
+        let command = SpawnCommand::new(settings, path.clone());
+
+        assert_eq!(
+            command.execute(&[]).await.unwrap(),
+            "Set the world spawn point to 0 -60 0"
+        );
+        assert_eq!(
+            LevelData::load(&path).unwrap().get_spawn(),
+            Some(BlockPos::new(0, -60, 0))
+        );
+    }
+}
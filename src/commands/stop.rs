@@ -0,0 +1,28 @@
+use std::{thread, time::Duration};
+
+use async_trait::async_trait;
+use colored::Colorize;
+use log::warn;
+
+use super::{Command, CommandError};
+
+/// Stops the server.
+pub struct StopCommand;
+
+#[async_trait]
+impl Command for StopCommand {
+    fn name(&self) -> &'static str {
+        "stop"
+    }
+
+    fn usage(&self) -> &'static str {
+        "stop"
+    }
+
+    async fn execute(&self, _args: &[&str]) -> Result<String, CommandError> {
+        let content = "Server will stop in few second…";
+        warn!("{}", content.red().bold());
+        thread::sleep(Duration::from_secs(1));
+        crate::gracefully_exit(-1000);
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use log::warn;
+
+use super::{Command, CommandError};
+use crate::net;
+use crate::net::packet::packet_types::EncodablePacket;
+use crate::world::level::LevelData;
+use crate::world::time::{self, WorldTime};
+
+/// Sets, adds to or queries the time of the world.
+pub struct TimeCommand {
+    time: Arc<RwLock<WorldTime>>,
+    /// Where the new time is persisted.
+    level_path: PathBuf,
+}
+
+impl TimeCommand {
+    pub fn new(time: Arc<RwLock<WorldTime>>, level_path: PathBuf) -> Self {
+        Self { time, level_path }
+    }
+
+    /// Changes the time of the day, then tells the players and saves it into `level.dat`.
+    fn update(&self, change: impl FnOnce(&mut WorldTime)) -> Result<WorldTime, CommandError> {
+        let updated = {
+            let mut time = self
+                .time
+                .write()
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            change(&mut time);
+            *time
+        };
+
+        match updated.to_packet().build() {
+            Ok(packet) => {
+                net::broadcast(packet);
+            }
+            Err(e) => warn!("Failed to build the Update Time packet: {e}"),
+        }
+
+        let mut level = LevelData::load(&self.level_path)
+            .map_err(|e| CommandError::Failed(format!("Failed to read level.dat: {e}")))?;
+        updated.write_to(&mut level);
+        level
+            .save(&self.level_path)
+            .map_err(|e| CommandError::Failed(format!("Failed to save level.dat: {e}")))?;
+
+        Ok(updated)
+    }
+
+    fn read(&self) -> Result<WorldTime, CommandError> {
+        self.time
+            .read()
+            .map(|time| *time)
+            .map_err(|e| CommandError::Failed(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Command for TimeCommand {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn usage(&self) -> &'static str {
+        "time <set|add|query> <value>"
+    }
+
+    fn required_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let usage = || CommandError::InvalidUsage(self.usage().to_string());
+        let value = args.get(1).map(|arg| arg.to_lowercase());
+
+        match (args.first().copied(), value.as_deref()) {
+            (Some("set"), Some(value)) => {
+                let ticks = time::named_time(value)
+                    .or_else(|| value.parse::<i64>().ok())
+                    .ok_or_else(usage)?;
+                let updated = self.update(|time| time.set_day_time(ticks))?;
+                Ok(format!("Set the time to {}", updated.get_day_time()))
+            }
+            (Some("add"), Some(value)) => {
+                let ticks = value.parse::<i64>().map_err(|_| usage())?;
+                let updated = self.update(|time| time.add_day_time(ticks))?;
+                Ok(format!("Set the time to {}", updated.get_day_time()))
+            }
+            (Some("query"), Some(value)) => {
+                let time = self.read()?;
+                let queried = match value {
+                    "daytime" => time.get_day_time(),
+                    "gametime" => time.get_game_time(),
+                    "day" => time.get_day(),
+                    _ => return Err(usage()),
+                };
+                Ok(format!("The time is {queried}"))
+            }
+            _ => Err(usage()),
+        }
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        ["set", "add", "query"].map(String::from).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn command(day_time: i64) -> (TimeCommand, Arc<RwLock<WorldTime>>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let time = Arc::new(RwLock::new(WorldTime::new(0, day_time)));
+        let command = TimeCommand::new(time.clone(), temp_dir.path().join("level.dat"));
+        (command, time, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_time_set_day() {
+        let (command, time, temp_dir) = command(18000);
+
+        let output = command.execute(&["set", "day"]).await.unwrap();
+        assert_eq!(output, "Set the time to 1000");
+        assert_eq!(time.read().unwrap().get_day_time(), 1000);
+
+        let level = LevelData::load(&temp_dir.path().join("level.dat")).unwrap();
+        assert_eq!(level.get_day_time(), Some(1000));
+    }
+
+    #[tokio::test]
+    async fn test_time_add_wraparound() {
+        let (command, time, _temp_dir) = command(23950);
+
+        command.execute(&["add", "100"]).await.unwrap();
+        assert_eq!(time.read().unwrap().get_day_time(), 50);
+
+        let output = command.execute(&["add", &i64::MAX.to_string()]).await;
+        assert_eq!(output, Ok("Set the time to 7857".to_string()));
+        // The lock isn't poisoned.
+        assert_eq!(time.read().unwrap().get_day_time(), 7857);
+    }
+
+    #[tokio::test]
+    async fn test_time_query_and_errors() {
+        let (command, _time, _temp_dir) = command(6000);
+
+        assert_eq!(
+            command.execute(&["query", "daytime"]).await,
+            Ok("The time is 6000".to_string())
+        );
+        assert!(command.execute(&["set", "teatime"]).await.is_err());
+        assert!(command.execute(&["add"]).await.is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use log::warn;
+
+use super::{Command, CommandError};
+use crate::net;
+use crate::net::packet::packet_types::EncodablePacket;
+use crate::world::weather::Weather;
+
+/// Changes the weather and tells every player about it.
+pub struct WeatherCommand {
+    weather: Arc<RwLock<Weather>>,
+}
+
+impl WeatherCommand {
+    pub fn new(weather: Arc<RwLock<Weather>>) -> Self {
+        Self { weather }
+    }
+}
+
+#[async_trait]
+impl Command for WeatherCommand {
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+
+    fn usage(&self) -> &'static str {
+        "weather <clear|rain|thunder>"
+    }
+
+    fn required_level(&self) -> u8 {
+        2
+    }
+
+    async fn execute(&self, args: &[&str]) -> Result<String, CommandError> {
+        let weather = match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            Some("clear") => Weather::Clear,
+            Some("rain") => Weather::Rain,
+            Some("thunder") => Weather::Thunder,
+            _ => return Err(CommandError::InvalidUsage(self.usage().to_string())),
+        };
+
+        *self
+            .weather
+            .write()
+            .map_err(|e| CommandError::Failed(e.to_string()))? = weather;
+
+        for event in weather.game_events() {
+            match event.build() {
+                Ok(packet) => {
+                    net::broadcast(packet);
+                }
+                Err(e) => warn!("Failed to build the weather Game Event packet: {e}"),
+            }
+        }
+
+        Ok(format!("Set the weather to {}", weather.describe()))
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        ["clear", "rain", "thunder"].map(String::from).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_weather_command() {
+        let weather = Arc::new(RwLock::new(Weather::Clear));
+        let command = WeatherCommand::new(weather.clone());
+
+        assert!(command.execute(&["rain"]).await.is_ok());
+        assert_eq!(*weather.read().unwrap(), Weather::Rain);
+
+        assert!(command.execute(&["snow"]).await.is_err());
+        assert_eq!(*weather.read().unwrap(), Weather::Rain);
+    }
+}
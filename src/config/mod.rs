@@ -1,13 +1,12 @@
 //! This module is the interface between the server.properties file. Querying for server settings.
-// !TODO generator_settings
-// !Todo text-filtering-config
 // use dot_properties::{read_properties, Properties};
-use std::fs::File;
-use std::io::BufReader;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
 use std::io::{Error, ErrorKind};
-use std::net::Ipv4Addr;
 use std::path::Path;
 
+use once_cell::sync::Lazy;
 use read_properties::Properties;
 pub mod read_properties;
 //use std::sync::Arc;
@@ -28,8 +27,9 @@ pub mod read_properties;
 /// println!("{max_players}");
 /// ```
 ///
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Difficulty {
+    Peaceful,
     Easy,
     Normal,
     Hard,
@@ -52,13 +52,51 @@ pub enum WorldPreset {
     SingleBiomeSurface,
 }
 
+impl Difficulty {
+    /// The value of the difficulty property.
+    pub fn as_property(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl Gamemode {
+    /// The value of the gamemode property.
+    pub fn as_property(&self) -> &'static str {
+        match self {
+            Gamemode::Adventure => "adventure",
+            Gamemode::Survival => "survival",
+            Gamemode::Creative => "creative",
+            Gamemode::Spectator => "spectator",
+        }
+    }
+}
+
+impl WorldPreset {
+    /// The value of the level-type property.
+    pub fn as_property(&self) -> &'static str {
+        match self {
+            WorldPreset::Normal => "minecraft:normal",
+            WorldPreset::Flat => "minecraft:flat",
+            WorldPreset::LargeBiomes => "minecraft:large_biomes",
+            WorldPreset::Amplified => "minecraft:amplified",
+            WorldPreset::SingleBiomeSurface => "minecraft:single_biome_surface",
+        }
+    }
+}
+
 // TODO: Maybe make Settings a singleton
 
 #[derive(Debug)]
 pub struct Settings {
     pub enable_jmx_monitoring: bool,
     pub rcon_port: u16,
-    pub level_seed: Option<i64>,
+    /// As written, see `seed_hasher::world_seed`. Only used when the world is created.
+    pub level_seed: Option<String>,
     pub gamemode: Gamemode,
     pub enable_command_block: bool,
     pub enable_query: bool,
@@ -81,7 +119,8 @@ pub struct Settings {
     pub initial_disabled_packs: Option<String>,
     pub broadcast_rcon_to_ops: bool,
     pub view_distance: u8,
-    pub server_ip: Option<Ipv4Addr>,
+    /// Parsed by `net::bind_address`, so that a malformed address is reported when binding.
+    pub server_ip: Option<String>,
     pub resource_pack_prompt: Option<String>,
     pub allow_nether: bool,
     pub server_port: u16,
@@ -111,10 +150,26 @@ pub struct Settings {
     pub spawn_protection: u16,
     pub resource_pack_sha1: Option<String>,
     pub max_world_size: u32,
-    //generator_settings:todo!(),
-    //text_filtering_config:todo!(),
+    /// Seconds without any player before the server slows down ticking. Disabled if `<= 0`.
+    pub pause_when_empty_seconds: i32,
+    /// JSON customizing the world preset, see `chunks_manager::generator_settings`.
+    pub generator_settings: String,
+    /// Path of the chat filter's word list, see `player::chat_filter`.
+    pub text_filtering_config: Option<String>,
+    /// Where to report the bugs, given on internal errors and crashes.
+    pub bug_report_link: Option<String>,
+    /// Every property read from the file, including the non-standard ones.
+    properties: Properties,
 }
 
+/// Keys of the default server.properties file. Any other key is a non-standard one.
+static STANDARD_KEYS: Lazy<HashSet<String>> = Lazy::new(|| {
+    let content = crate::consts::file_contents::server_properties();
+    read_properties::read_properties(&mut content.as_bytes())
+        .map(|properties| properties.iter().map(|(key, _)| key.to_string()).collect())
+        .unwrap_or_default()
+});
+
 fn read(filepath: &Path) -> std::io::Result<Properties> {
     let file = File::open(filepath)?;
     let mut reader = BufReader::new(file);
@@ -122,11 +177,34 @@ fn read(filepath: &Path) -> std::io::Result<Properties> {
         .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
 }
 
+/// Saves `properties` to `filepath`.
+///
+/// An existing file is updated in place: only the changed values are rewritten and the admin's
+/// ordering and comments are kept. A fresh file starts from the default template.
+pub fn save(filepath: &Path, properties: &Properties) -> std::io::Result<()> {
+    let content = match fs::read_to_string(filepath) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            crate::consts::file_contents::server_properties()
+        }
+        Err(e) => return Err(e),
+    };
+
+    fs::write(
+        filepath,
+        read_properties::update_properties(&content, properties),
+    )
+}
+
 impl Settings {
     pub fn new() -> Self {
         let config_file = read(Path::new(crate::consts::file_paths::PROPERTIES))
             .expect("Error reading {server.properties} file");
 
+        Self::from_properties(config_file)
+    }
+
+    pub fn from_properties(config_file: Properties) -> Self {
         Self {
             enable_jmx_monitoring: config_file
                 .get_property("enable-jmx-monitoring")
@@ -140,7 +218,7 @@ impl Settings {
                 .unwrap(),
             level_seed: match config_file.get_property("level-seed").unwrap() {
                 "" => None,
-                s => Some(s.parse::<i64>().unwrap()),
+                s => Some(s.to_string()),
             },
             gamemode: match config_file
                 .get_property("gamemode")
@@ -200,6 +278,7 @@ impl Settings {
                 s => Some(s.parse::<i32>().unwrap()),
             },
             difficulty: match config_file.get_property("difficulty").unwrap() {
+                "peaceful" => Difficulty::Peaceful,
                 "normal" => Difficulty::Normal,
                 "easy" => Difficulty::Easy,
                 "hard" => Difficulty::Hard,
@@ -264,7 +343,7 @@ impl Settings {
                 .unwrap(),
             server_ip: match config_file.get_property("server-ip").unwrap() {
                 "" => None,
-                s => Some(s.parse::<Ipv4Addr>().unwrap()),
+                s => Some(s.to_string()),
             },
             resource_pack_prompt: match config_file.get_property("resource-pack-prompt").unwrap() {
                 "" => None,
@@ -324,7 +403,7 @@ impl Settings {
                 s => Some(s.parse::<String>().unwrap()),
             },
             player_idle_timeout: config_file
-                .get_property("simulation-distance")
+                .get_property("player-idle-timeout")
                 .unwrap()
                 .parse::<i32>()
                 .unwrap(),
@@ -334,7 +413,7 @@ impl Settings {
                 .parse::<bool>()
                 .unwrap(),
             rate_limit: config_file
-                .get_property("simulation-distance")
+                .get_property("rate-limit")
                 .unwrap()
                 .parse::<u32>()
                 .unwrap(),
@@ -378,13 +457,17 @@ impl Settings {
                 .unwrap()
                 .parse::<String>()
                 .unwrap(),
-            // level-type and also be "minecraft\:normal"
-            level_type: match config_file.get_property("level-type").unwrap() {
+            // level-type is a namespaced ID, "minecraft:normal", but used to be "normal".
+            level_type: match config_file
+                .get_property("level-type")
+                .unwrap()
+                .trim_start_matches("minecraft:")
+            {
                 "normal" => WorldPreset::Normal,
                 "flat" => WorldPreset::Flat,
                 "large_biomes" => WorldPreset::LargeBiomes,
-                "amplified" => WorldPreset::SingleBiomeSurface,
-                "single_biome_surface" => WorldPreset::Amplified,
+                "amplified" => WorldPreset::Amplified,
+                "single_biome_surface" => WorldPreset::SingleBiomeSurface,
                 _ => WorldPreset::Normal, // default value
             },
             spawn_monsters: config_file
@@ -411,10 +494,422 @@ impl Settings {
                 .unwrap()
                 .parse::<u32>()
                 .unwrap(),
-            //generator_settings: todo!(),
-            //text_filtering_config: todo!(),
+            // Added in 1.21.2, older server.properties files don't have it.
+            pause_when_empty_seconds: config_file
+                .get_property("pause-when-empty-seconds")
+                .ok()
+                .and_then(|s| s.parse::<i32>().ok())
+                .unwrap_or(60),
+            // Older server.properties files may not have it.
+            generator_settings: config_file
+                .get_property("generator-settings")
+                .unwrap_or("{}")
+                .to_string(),
+            text_filtering_config: match config_file
+                .get_property("text-filtering-config")
+                .unwrap_or("")
+            {
+                "" => None,
+                s => Some(s.to_string()),
+            },
+            // Added in 1.21, older server.properties files don't have it.
+            bug_report_link: match config_file.get_property("bug-report-link").unwrap_or("") {
+                "" => None,
+                s => Some(s.to_string()),
+            },
+            properties: config_file,
+        }
+    }
+
+    /// Gets the value of a non-standard property, e.g. added by an admin for a plugin.
+    pub fn extra(&self, key: &str) -> Option<&str> {
+        if STANDARD_KEYS.contains(key) {
+            return None;
+        }
+        self.properties.get_property(key).ok()
+    }
+
+    /// Sets the value of a non-standard property. Returns `false` (and changes nothing) if `key`
+    /// is a standard property, those are set through the typed fields.
+    pub fn set_extra(&mut self, key: &str, value: &str) -> bool {
+        if STANDARD_KEYS.contains(key) {
+            return false;
+        }
+        self.properties.set_property(key, value);
+        true
+    }
+
+    /// Writes the properties back, in the order they were read. New non-standard keys come last.
+    /// Changes made to the typed fields are written too.
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        read_properties::write_properties(writer, &self.to_properties())
+    }
+
+    /// Saves the properties to `filepath`, see `save`. Changes made to the typed fields are
+    /// saved too.
+    pub fn save(&self, filepath: &Path) -> std::io::Result<()> {
+        save(filepath, &self.to_properties())
+    }
+
+    /// The properties read from the file, with the typed fields that were changed since.
+    ///
+    /// A typed field is only written back when it differs from what its property parses to, so
+    /// that the unchanged values keep their spelling (e.g. "normal" for "minecraft:normal").
+    fn to_properties(&self) -> Properties {
+        let mut properties = self.properties.clone();
+        let read = Self::from_properties(self.properties.clone()).typed_properties();
+        for ((key, value), (_, read_value)) in self.typed_properties().into_iter().zip(read) {
+            if value != read_value {
+                properties.set_property(key, &value);
+            }
+        }
+        properties
+    }
+
+    /// The typed fields, as properties.
+    fn typed_properties(&self) -> Vec<(&'static str, String)> {
+        fn optional<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+
+        vec![
+            (
+                "enable-jmx-monitoring",
+                self.enable_jmx_monitoring.to_string(),
+            ),
+            ("rcon.port", self.rcon_port.to_string()),
+            ("level-seed", optional(&self.level_seed)),
+            ("gamemode", self.gamemode.as_property().to_string()),
+            (
+                "enable-command-block",
+                self.enable_command_block.to_string(),
+            ),
+            ("enable-query", self.enable_query.to_string()),
+            (
+                "enforce-secure-profile",
+                self.enforce_secure_profile.to_string(),
+            ),
+            ("level-name", optional(&self.level_name)),
+            ("motd", optional(&self.motd)),
+            ("query.port", self.query_port.to_string()),
+            ("pvp", self.pvp.to_string()),
+            ("generate-structures", self.generate_structures.to_string()),
+            (
+                "max-chained-neighbor-updates",
+                optional(&self.max_chained_neighbor_updates),
+            ),
+            ("difficulty", self.difficulty.as_property().to_string()),
+            (
+                "network-compression-threshold",
+                self.network_compression_threshold.to_string(),
+            ),
+            ("max-tick-time", self.max_tick_time.to_string()),
+            (
+                "require-resource-pack",
+                self.require_resource_pack.to_string(),
+            ),
+            (
+                "use-native-transport",
+                self.use_native_transport.to_string(),
+            ),
+            ("max-players", self.max_players.to_string()),
+            ("online-mode", self.online_mode.to_string()),
+            ("enable-status", self.enable_status.to_string()),
+            ("allow-flight", self.allow_flight.to_string()),
+            (
+                "initial-disabled-packs",
+                optional(&self.initial_disabled_packs),
+            ),
+            (
+                "broadcast-rcon-to-ops",
+                self.broadcast_rcon_to_ops.to_string(),
+            ),
+            ("view-distance", self.view_distance.to_string()),
+            ("server-ip", optional(&self.server_ip)),
+            ("resource-pack-prompt", optional(&self.resource_pack_prompt)),
+            ("allow-nether", self.allow_nether.to_string()),
+            ("server-port", self.server_port.to_string()),
+            ("enable-rcon", self.enable_rcon.to_string()),
+            ("sync-chunk-writes", self.sync_chunk_writes.to_string()),
+            ("op-permission-level", self.op_permission_level.to_string()),
+            (
+                "prevent-proxy-connections",
+                self.prevent_proxy_connections.to_string(),
+            ),
+            ("hide-online-players", self.hide_online_players.to_string()),
+            ("resource-pack", optional(&self.resource_pack)),
+            (
+                "entity-broadcast-range-percentage",
+                self.entity_broadcast_range_percentage.to_string(),
+            ),
+            ("simulation-distance", self.simulation_distance.to_string()),
+            ("rcon.password", optional(&self.rcon_password)),
+            ("player-idle-timeout", self.player_idle_timeout.to_string()),
+            ("force-gamemode", self.force_gamemode.to_string()),
+            ("rate-limit", self.rate_limit.to_string()),
+            ("hardcore", self.hardcore.to_string()),
+            ("white-list", self.white_list.to_string()),
+            (
+                "broadcast-console-to-ops",
+                self.broadcast_console_to_ops.to_string(),
+            ),
+            ("spawn-npcs", self.spawn_npcs.to_string()),
+            ("spawn-animals", self.spawn_animals.to_string()),
+            ("log-ips", self.log_ips.to_string()),
+            (
+                "function-permission-level",
+                self.function_permission_level.to_string(),
+            ),
+            ("initial-enabled-packs", self.initial_enabled_packs.clone()),
+            ("level-type", self.level_type.as_property().to_string()),
+            ("spawn-monsters", self.spawn_monsters.to_string()),
+            ("enforce-whitelist", self.enforce_whitelist.to_string()),
+            ("spawn-protection", self.spawn_protection.to_string()),
+            ("resource-pack-sha1", optional(&self.resource_pack_sha1)),
+            ("max-world-size", self.max_world_size.to_string()),
+            (
+                "pause-when-empty-seconds",
+                self.pause_when_empty_seconds.to_string(),
+            ),
+            ("generator-settings", self.generator_settings.clone()),
+            (
+                "text-filtering-config",
+                optional(&self.text_filtering_config),
+            ),
+            ("bug-report-link", optional(&self.bug_report_link)),
+        ]
+    }
+
+    /// Looks for contradictory or out-of-range properties. Returns a warning telling how to fix
+    /// each one, the server still starts with them.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.enforce_whitelist && !self.white_list {
+            warnings.push(
+                "enforce-whitelist=true has no effect while white-list=false, \
+                 set white-list=true to use the whitelist"
+                    .to_string(),
+            );
+        }
+        if self.prevent_proxy_connections && !self.online_mode {
+            warnings.push(
+                "prevent-proxy-connections=true has no effect while online-mode=false".to_string(),
+            );
+        }
+        if self.enforce_secure_profile && !self.online_mode {
+            warnings.push(
+                "enforce-secure-profile=true has no effect while online-mode=false".to_string(),
+            );
+        }
+        for (key, value) in [
+            ("view-distance", self.view_distance),
+            ("simulation-distance", self.simulation_distance),
+        ] {
+            if !(3..=32).contains(&value) {
+                warnings.push(format!(
+                    "{key}={value} is out of range, use a value between 3 and 32"
+                ));
+            }
+        }
+        if self.simulation_distance > self.view_distance {
+            warnings.push(format!(
+                "simulation-distance={} is greater than view-distance={}, the chunks beyond the \
+                 view distance are never simulated: lower simulation-distance",
+                self.simulation_distance, self.view_distance
+            ));
+        }
+        for (key, value) in [
+            ("op-permission-level", self.op_permission_level),
+            ("function-permission-level", self.function_permission_level),
+        ] {
+            if !(1..=4).contains(&value) {
+                warnings.push(format!(
+                    "{key}={value} is out of range, use a value between 1 and 4"
+                ));
+            }
+        }
+        if self.network_compression_threshold < -1 {
+            warnings.push(format!(
+                "network-compression-threshold={} is invalid, use -1 to disable compression",
+                self.network_compression_threshold
+            ));
+        }
+        if let Some(warning) = crate::chunks_manager::generator_settings::validate(self) {
+            warnings.push(warning);
+        }
+        if let Some(warning) = crate::world::datapack::validate(self) {
+            warnings.push(warning);
         }
+        warnings.extend(crate::world::view_distance::validate(self));
+        if self.enable_rcon && self.rcon_password.is_none() {
+            warnings.push(
+                "enable-rcon=true without an rcon.password, RCON can't be used: set one"
+                    .to_string(),
+            );
+        }
+        if self.enable_rcon && self.rcon_port == self.server_port {
+            warnings.push(format!(
+                "rcon.port and server-port are both {}, use different ports",
+                self.server_port
+            ));
+        }
+        if self.enable_query && self.enable_rcon && self.query_port == self.rcon_port {
+            warnings.push(format!(
+                "query.port and rcon.port are both {}, use different ports",
+                self.query_port
+            ));
+        }
+
+        warnings
     }
     //fn gamemode_to_enum(inp)
 }
 
+#[cfg(test)]
+impl Settings {
+    /// The default settings, with `overrides` applied.
+    pub fn with(overrides: &[(&str, &str)]) -> Self {
+        let content = crate::consts::file_contents::server_properties();
+        let mut properties = read_properties::read_properties(&mut content.as_bytes()).unwrap();
+        for (key, value) in overrides {
+            properties.set_property(key, value);
+        }
+        Self::from_properties(properties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Settings {
+        Settings::from_properties(
+            read_properties::read_properties(&mut content.as_bytes()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_extra_roundtrip() {
+        let content = format!(
+            "{}\nmy-plugin-enabled=true\n",
+            crate::consts::file_contents::server_properties()
+        );
+        let mut settings = parse(&content);
+
+        assert_eq!(settings.extra("my-plugin-enabled"), Some("true"));
+        assert_eq!(settings.extra("max-players"), None);
+        assert!(!settings.set_extra("max-players", "1"));
+        assert!(settings.set_extra("my-plugin-mode", "fast"));
+
+        let mut written = Vec::new();
+        settings.write(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(
+            written.ends_with("white-list=false\nmy-plugin-enabled=true\nmy-plugin-mode=fast\n")
+        );
+
+        // Writing the re-parsed settings gives the same file
+        let reparsed = parse(&written);
+        assert_eq!(reparsed.extra("my-plugin-enabled"), Some("true"));
+        let mut rewritten = Vec::new();
+        reparsed.write(&mut rewritten).unwrap();
+        assert_eq!(String::from_utf8(rewritten).unwrap(), written);
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        // Later lines override the template's.
+        let content = crate::consts::file_contents::server_properties()
+            + "\nserver-port=25500\nquery.port=25501\nrcon.port=25502\nrcon.password=p=ss:w0rd==\n";
+        let properties = read_properties::read_properties(&mut content.as_bytes()).unwrap();
+        let settings = Settings::from_properties(properties);
+
+        assert_eq!(
+            (
+                settings.server_port,
+                settings.query_port,
+                settings.rcon_port
+            ),
+            (25500, 25501, 25502)
+        );
+        assert_eq!(settings.rcon_password.as_deref(), Some("p=ss:w0rd=="));
+    }
+
+    #[test]
+    fn test_validate_defaults() {
+        assert_eq!(Settings::with(&[]).validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_validate_warnings() {
+        let warnings =
+            Settings::with(&[("enforce-whitelist", "true"), ("white-list", "false")]).validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("enforce-whitelist=true has no effect"));
+
+        let warnings =
+            Settings::with(&[("simulation-distance", "12"), ("view-distance", "8")]).validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("lower simulation-distance"));
+
+        let warnings =
+            Settings::with(&[("online-mode", "false"), ("view-distance", "40")]).validate();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("enforce-secure-profile=true"));
+        assert!(warnings[1].starts_with("view-distance=40 is out of range"));
+    }
+
+    #[test]
+    fn test_save() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("server.properties");
+
+        // A fresh file starts from the template
+        let mut properties = Properties::default();
+        properties.set_property("max-players", "50");
+        save(&path, &properties).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Minecraft server properties\n"));
+        assert!(content.contains("\nmax-players=50\n"));
+
+        // An existing file keeps its comments
+        let content = format!("# My server\n\n{content}");
+        fs::write(&path, &content).unwrap();
+        let mut settings = Settings::from_properties(read(&path).unwrap());
+        settings.set_extra("my-plugin-enabled", "true");
+        settings.save(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{content}\nmy-plugin-enabled=true\n")
+        );
+    }
+
+    #[test]
+    fn test_save_typed_fields() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("server.properties");
+        save(&path, &Properties::default()).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+
+        let mut settings = Settings::from_properties(read(&path).unwrap());
+        settings.max_players = 42;
+        settings.gamemode = Gamemode::Creative;
+        settings.motd = None;
+        settings.save(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let changed: Vec<_> = content
+            .lines()
+            .zip(saved.lines())
+            .filter(|(before, after)| before != after)
+            .map(|(_, after)| after)
+            .collect();
+        assert_eq!(changed, ["gamemode=creative", "max-players=42", "motd="]);
+
+        let settings = Settings::from_properties(read(&path).unwrap());
+        assert_eq!(settings.max_players, 42);
+        assert!(matches!(settings.gamemode, Gamemode::Creative));
+        assert_eq!(settings.motd, None);
+    }
+}
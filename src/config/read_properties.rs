@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::{fmt, io};
 
 #[derive(Debug)]
@@ -14,23 +14,48 @@ impl<'a> fmt::Display for PropertyNotFoundError<'a> {
     }
 }
 
+/// Key-value pairs, remembering the order in which the keys were first seen.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct Properties(HashMap<String, String>);
+pub struct Properties {
+    values: HashMap<String, String>,
+    order: Vec<String>,
+}
 
 impl From<HashMap<String, String>> for Properties {
-    fn from(value: HashMap<String, String>) -> Self {
-        Self(value)
+    /// The keys are sorted, as a `HashMap` has no order.
+    fn from(values: HashMap<String, String>) -> Self {
+        let mut order: Vec<String> = values.keys().cloned().collect();
+        order.sort();
+        Self { values, order }
     }
 }
 
 impl Properties {
     /// Gets the corresponding value for a property key.
     pub fn get_property<'a>(&self, key: &'a str) -> Result<&'_ str, PropertyNotFoundError<'a>> {
-        self.0
+        self.values
             .get(key)
             .map(String::as_ref)
             .ok_or(PropertyNotFoundError(key))
     }
+
+    /// Sets the value of a property. New keys are added after the existing ones.
+    pub fn set_property(&mut self, key: &str, value: &str) {
+        if self
+            .values
+            .insert(key.to_string(), value.to_string())
+            .is_none()
+        {
+            self.order.push(key.to_string());
+        }
+    }
+
+    /// Iterates over the key-value pairs, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.order
+            .iter()
+            .map(|key| (key.as_str(), self.values[key].as_str()))
+    }
 }
 
 #[derive(Debug)]
@@ -116,14 +141,231 @@ pub fn read_properties<R: BufRead>(reader: &mut R) -> Result<Properties, Propert
             continue;
         }
 
-        let (field, value) = line
-            .split_once('=')
+        let (field, value) = split_key_value(line)
             .ok_or_else(|| PropertiesParseError::new_invalid_kvp(line_number, line))?;
 
-        let key = field.trim().to_string();
-        let value = value.trim().to_string();
-        properties.0.insert(key, value);
+        properties.set_property(&unescape(field.trim()), &unescape(value.trim()));
     }
 
     Ok(properties)
 }
+
+/// Splits a line on the first unescaped `=` or `:`, like Java's `Properties`.
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' | ':' => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replaces the escape sequences of the Java `Properties` format (e.g. `minecraft\:normal`).
+fn unescape(escaped: &str) -> String {
+    let mut result = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{000c}'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    // Invalid sequences are kept as is.
+                    None => {
+                        result.push_str("\\u");
+                        result.push_str(&code);
+                    }
+                }
+            }
+            // Any other escaped character (`\:`, `\=`, `\\`, ...) stands for itself.
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+
+    result
+}
+
+/// Escapes a key or a value for the Java `Properties` format, the reverse of `unescape`.
+fn escape(unescaped: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(unescaped.len());
+
+    for (i, c) in unescaped.chars().enumerate() {
+        match c {
+            '\\' | ':' | '=' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            ' ' if is_key || i == 0 => result.push_str("\\ "),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\u{000c}' => result.push_str("\\f"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Writes `properties` as `key=value` lines, in order.
+pub fn write_properties<W: Write>(writer: &mut W, properties: &Properties) -> io::Result<()> {
+    for (key, value) in properties.iter() {
+        writeln!(writer, "{}={}", escape(key, true), escape(value, false))?;
+    }
+    Ok(())
+}
+
+/// Updates the content of an existing properties file with `properties`.
+///
+/// Only the values that changed are rewritten, everything else (key order, spacing, blank lines,
+/// comments) is kept verbatim. Keys missing from `content` are appended at the end.
+pub fn update_properties(content: &str, properties: &Properties) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut seen = HashSet::new();
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+            result.push_str(line);
+            continue;
+        }
+
+        let Some((field, value)) = split_key_value(line) else {
+            result.push_str(line);
+            continue;
+        };
+
+        let key = unescape(field.trim());
+        match properties.get_property(&key) {
+            Ok(new_value) if new_value != unescape(value.trim()) => {
+                let separator = &line[field.len()..=field.len()];
+                let line_ending = &line[line.trim_end().len()..];
+                result.push_str(&format!(
+                    "{field}{separator}{}{line_ending}",
+                    escape(new_value, false)
+                ));
+            }
+            _ => result.push_str(line),
+        }
+        seen.insert(key);
+    }
+
+    for (key, value) in properties.iter() {
+        if !seen.contains(key) {
+            if !result.is_empty() && !result.ends_with('\n') {
+                result.push('\n');
+            }
+            result.push_str(&format!("{}={}\n", escape(key, true), escape(value, false)));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_properties_order() {
+        let input = "# comment\nb=2\na=1\n\nc = 3\nb=4\n";
+        let properties = read_properties(&mut input.as_bytes()).unwrap();
+
+        assert_eq!(properties.get_property("b").unwrap(), "4");
+        assert_eq!(
+            properties.iter().collect::<Vec<_>>(),
+            [("b", "4"), ("a", "1"), ("c", "3")]
+        );
+
+        let mut output = Vec::new();
+        write_properties(&mut output, &properties).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "b=4\na=1\nc=3\n");
+    }
+
+    #[test]
+    fn test_update_properties_preserves_lines() {
+        let content = "#Minecraft server properties\n\
+                       ! A custom comment\n\
+                       \n\
+                       motd = A Minecraft Server\n\
+                       max-players=20\r\n\
+                       pvp=true\n";
+        let mut properties = read_properties(&mut content.as_bytes()).unwrap();
+        properties.set_property("max-players", "50");
+
+        assert_eq!(
+            update_properties(content, &properties),
+            content.replace("max-players=20", "max-players=50")
+        );
+
+        // Unchanged properties give the same file
+        let properties = read_properties(&mut content.as_bytes()).unwrap();
+        assert_eq!(update_properties(content, &properties), content);
+    }
+
+    #[test]
+    fn test_update_properties_new_keys() {
+        let mut properties = Properties::default();
+        properties.set_property("pvp", "true");
+        properties.set_property("my-plugin-enabled", "false");
+
+        assert_eq!(
+            update_properties("# comment\npvp=true", &properties),
+            "# comment\npvp=true\nmy-plugin-enabled=false\n"
+        );
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        let input = "query.port=25565\nrcon.port : 25575\nrcon.password=a=b\n";
+        let properties = read_properties(&mut input.as_bytes()).unwrap();
+
+        assert_eq!(properties.get_property("query.port").unwrap(), "25565");
+        assert_eq!(properties.get_property("rcon.port").unwrap(), "25575");
+        // Only the first separator splits the line.
+        assert_eq!(properties.get_property("rcon.password").unwrap(), "a=b");
+    }
+
+    #[test]
+    fn test_escaped_values() {
+        let input = "level-type=minecraft\\:normal\nmotd=A \\u00e9 \\= \\\\ server\nkey\\=with\\:separators = x\n";
+        let properties = read_properties(&mut input.as_bytes()).unwrap();
+
+        assert_eq!(
+            properties.get_property("level-type").unwrap(),
+            "minecraft:normal"
+        );
+        assert_eq!(
+            properties.get_property("motd").unwrap(),
+            "A \u{e9} = \\ server"
+        );
+        assert_eq!(properties.get_property("key=with:separators").unwrap(), "x");
+
+        let mut output = Vec::new();
+        write_properties(&mut output, &properties).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("level-type=minecraft\\:normal\n"));
+        assert_eq!(read_properties(&mut output.as_bytes()).unwrap(), properties);
+
+        // Unchanged escaped values are kept verbatim, changed ones are escaped
+        assert_eq!(update_properties(input, &properties), input);
+        let mut changed = properties.clone();
+        changed.set_property("level-type", "minecraft:flat");
+        assert!(update_properties(input, &changed).starts_with("level-type=minecraft\\:flat\n"));
+    }
+}
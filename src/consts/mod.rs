@@ -6,39 +6,90 @@
 pub mod minecraft {
     pub const VERSION: &str = "1.21.4";
     pub const PROTOCOL_VERSION: usize = 769;
+    /// The world data version, stored in chunks and `level.dat`.
+    pub const DATA_VERSION: i32 = 4189;
 }
 
 /// Server logging messages.
 pub mod messages {
 
     use colored::*;
-    use once_cell::sync::Lazy;
 
-    use super::minecraft::VERSION;
+    use super::minecraft::{PROTOCOL_VERSION, VERSION};
+    use crate::config::{Gamemode, Settings};
+    use crate::i18n::tr;
 
-    pub static SERVER_STARTING: Lazy<String> = Lazy::new(|| {
-        format!("Starting minecraft server version {}", VERSION)
-            .bold()
-            .to_string()
-    });
+    // Functions rather than statics, so the messages are in the language of the moment.
+
+    pub fn server_starting() -> String {
+        tr("server.starting", &[VERSION]).bold().to_string()
+    }
 
-    pub static SERVER_STARTED: Lazy<String> =
-        Lazy::new(|| "[ SERVER STARTED ]".bright_green().bold().to_string());
+    pub fn server_started() -> String {
+        tr("server.started", &[]).bright_green().bold().to_string()
+    }
+
+    pub fn server_shutdown() -> String {
+        tr("server.shut_down", &[]).bright_red().bold().to_string()
+    }
 
-    pub static SERVER_SHUTDOWN: Lazy<String> =
-        Lazy::new(|| "[ SERVER SHUT DOWN ]".bright_red().bold().to_string());
+    pub fn greet() -> String {
+        tr("server.greet", &[]).green().bold().to_string()
+    }
 
-    pub static GREET: Lazy<String> =
-        Lazy::new(|| "Hello, world from Cactus!".green().bold().to_string());
+    /// Used once the server accepts connections, like vanilla's "Done (Xs)!".
+    pub fn server_ready(startup: std::time::Duration) -> String {
+        let seconds = format!("{:.3}", startup.as_secs_f64());
+        tr("server.ready", &[&server_started(), &seconds])
+    }
 
     /// Used when exiting the server with an exit code.
     pub fn server_shutdown_code(code: i32) -> String {
-        format!("[ server shutdown with code: {code}]")
+        tr("server.shutdown_code", &[&code.to_string()])
             .to_uppercase()
             .bright_red()
             .bold()
             .to_string()
     }
+
+    /// Logged when the server panics, with where to report it if `bug-report-link` is set.
+    pub fn crash(bug_report_link: Option<&str>) -> String {
+        let crashed = tr("server.crashed", &[]).bright_red().bold().to_string();
+        match bug_report_link {
+            Some(link) => format!("{crashed} {}", tr("bug_report.link", &[link])),
+            None => crashed,
+        }
+    }
+
+    /// The first lines of the log: what runs, where, and with which world.
+    pub fn banner(settings: &Settings) -> String {
+        let address = format!(
+            "{}:{}",
+            settings.server_ip.as_deref().unwrap_or("*"),
+            settings.server_port
+        );
+        let gamemode = match settings.gamemode {
+            Gamemode::Survival => "SURVIVAL",
+            Gamemode::Adventure => "ADVENTURE",
+            Gamemode::Creative => "CREATIVE",
+            Gamemode::Spectator => "SPECTATOR",
+        };
+        let world = settings.level_name.as_deref().unwrap_or("world");
+
+        [
+            tr("banner.cactus", &[env!("CARGO_PKG_VERSION")])
+                .green()
+                .bold()
+                .to_string(),
+            tr(
+                "banner.minecraft",
+                &[VERSION, &PROTOCOL_VERSION.to_string()],
+            ),
+            tr("banner.address", &[&address.bold().to_string()]),
+            tr("banner.world", &[world, gamemode]),
+        ]
+        .join("\n")
+    }
 }
 
 /// Module used to store file paths relative to the server binary.
@@ -53,6 +104,12 @@ pub mod file_paths {
     pub const USERCACHE: &str = "usercache.json";
     pub const SESSION: &str = "session.lock";
     pub const SERVER_ICON: &str = "server-icon.png";
+    /// Optional, the logical servers sharing the port. See `net::virtual_host`.
+    pub const VIRTUAL_HOSTS: &str = "virtual-hosts.json";
+    /// Global information of the world (time, seed, ...).
+    pub const LEVEL_DAT: &str = "world/level.dat";
+    /// The commands entered in the console, see `commands::history`.
+    pub const COMMAND_HISTORY: &str = "logs/command_history.txt";
 }
 
 pub mod directory_paths {
@@ -60,6 +117,9 @@ pub mod directory_paths {
     pub const THE_END: &str = "world/DIM1/";
     pub const NETHER: &str = "world/DIM-1/";
     pub const OVERWORLD: &str = "world/region/";
+    pub const PLAYER_DATA: &str = "world/playerdata/";
+    /// The data packs of the world, see `world::datapack`.
+    pub const DATAPACKS: &str = "world/datapacks/";
     pub const LOGS: &str = "logs/";
 }
 
@@ -114,6 +174,7 @@ motd=A Minecraft Server
 network-compression-threshold=256
 online-mode=true
 op-permission-level=4
+pause-when-empty-seconds=60
 player-idle-timeout=0
 prevent-proxy-connections=false
 pvp=true
@@ -151,63 +212,29 @@ white-list=false"#;
 /// Strings for packets
 pub mod protocol {
 
-    use base64::{engine::general_purpose, Engine};
-    use image::{GenericImageView, ImageFormat};
-    use log::error;
     use serde_json::json;
 
-    use crate::{config::Settings, gracefully_exit};
-
-    use super::file_paths::SERVER_ICON;
-
-    /// Returns the Base64-encoded server icon.
-    /// The image must be a 64x64 PNG image as the file server-icon.png
-    fn get_favicon() -> Result<String, Box<dyn std::error::Error>> {
-        let file_data = std::fs::read(SERVER_ICON)?;
-
-        // Guess the image format
-        let format = image::guess_format(&file_data)?;
-        if format != ImageFormat::Png {
-            return Err("The server icon must be in PNG format".into());
-        }
-
-        // Load the image to verify dimensions
-        let img = image::load_from_memory_with_format(&file_data, format)?;
-        if img.dimensions() != (64, 64) {
-            return Err("The server icon must have dimensions of 64x64".into());
-        }
-
-        // Encode to Base64
-        let base64_icon = general_purpose::STANDARD.encode(file_data);
-
-        // Construct the Data URI
-        let favicon = format!("data:image/png;base64,{base64_icon}");
-        Ok(favicon)
-    }
+    use crate::config::Settings;
 
     /// Returns the Status Response JSON.
-    pub fn status_response_json() -> String {
+    /// `motd` replaces the one of the settings, e.g. for a virtual host. `favicon` is the data URI
+    /// of the server icon, see `net::slp::icon`.
+    pub fn status_response_json(
+        motd: Option<&str>,
+        online_players: usize,
+        favicon: Option<&str>,
+    ) -> String {
         let config = Settings::new();
 
         let version_name = super::minecraft::VERSION;
         let protocol = super::minecraft::PROTOCOL_VERSION;
         let max_players = config.max_players;
 
-        // TODO: This does not mirror the server's current state.
-        let online_players = 0;
-
-        let description_text = config.motd;
-
-        // TODO: Implement logic such that, if no icon is provided, not include it in the JSON.
-        if let Err(err) = get_favicon() {
-            error!("Server icon not found: {err}. Shutting down the server...");
-            gracefully_exit(1);
-        }
-        let favicon = get_favicon().unwrap();
+        let description_text = motd.map(str::to_string).or(config.motd);
 
         let enforces_secure_chat = config.enforce_secure_profile;
 
-        let json_data = json!({
+        let mut json_data = json!({
             "version": {
                 "name": version_name,
                 "protocol": protocol
@@ -219,10 +246,37 @@ pub mod protocol {
             "description": {
                 "text": description_text
             },
-            "favicon": favicon,
             "enforcesSecureChat": enforces_secure_chat
         });
+        if let Some(favicon) = favicon {
+            json_data["favicon"] = favicon.into();
+        }
 
         serde_json::to_string(&json_data).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+
+    #[test]
+    fn test_banner() {
+        let settings = Settings::with(&[("gamemode", "creative"), ("server-port", "25570")]);
+        let banner = messages::banner(&settings);
+
+        assert!(banner.contains(&minecraft::PROTOCOL_VERSION.to_string()));
+        assert!(banner.contains(minecraft::VERSION));
+        assert!(banner.contains(env!("CARGO_PKG_VERSION")));
+        assert!(banner.contains("CREATIVE"));
+        assert!(banner.contains("25570"));
+    }
+
+    #[test]
+    fn test_crash_bug_report_link() {
+        let crash = messages::crash(Some("https://example.com/issues"));
+        assert!(crash.ends_with("https://example.com/issues"));
+        assert!(!messages::crash(None).contains("http"));
+    }
+}
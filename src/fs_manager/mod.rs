@@ -1,60 +1,131 @@
-use std::fs::{self, File, OpenOptions};
+use std::fs::{self, File};
 use std::io::{self, BufRead};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::vec;
 mod utils;
+use crate::player::{self, uuid};
 use crate::{consts, gracefully_exit};
 use colored::Colorize;
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::io::Read;
 use std::io::Write;
 
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Makes `init`, `create_dirs` and `create_other_files` only log the files and directories they
+/// would create, without touching the disk.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
 // Initializes the server's required files and directories
 pub fn init() -> std::io::Result<()> {
-    eula()?;
-    create_server_properties()
+    let root = Path::new("");
+    if is_initialized(root) {
+        debug!("The server files are already initialized");
+        return Ok(());
+    }
+    report_created(&init_in(root, is_dry_run())?, is_dry_run());
+    Ok(())
+}
+
+/// Whether every file and directory of the server exists under `root`, and the eula is agreed:
+/// there is nothing left to create.
+fn is_initialized(root: &Path) -> bool {
+    let files = [consts::file_paths::PROPERTIES]
+        .into_iter()
+        .chain(OTHER_FILES)
+        .all(|file| root.join(file).is_file());
+    let directories = DIRECTORIES
+        .into_iter()
+        .all(|(dir, _)| root.join(dir).is_dir());
+    let eula = root.join(consts::file_paths::EULA);
+
+    files && directories && eula.is_file() && check_eula(&eula).unwrap_or(false)
+}
+
+/// Logs what was created, or what would be if `dry_run`. Silent if nothing was.
+fn report_created(created: &[PathBuf], dry_run: bool) {
+    for message in creation_messages(created, dry_run) {
+        info!("{message}");
+    }
+}
+
+fn creation_messages(created: &[PathBuf], dry_run: bool) -> Vec<String> {
+    created
+        .iter()
+        .map(|path| match (dry_run, path.is_dir()) {
+            (true, _) => format!("[dry-run] Would create {}", path.display()),
+            _ if path.ends_with(consts::directory_paths::WORLDS_DIRECTORY) => {
+                "No existing world data, creating new world".to_string()
+            }
+            (false, true) => format!("Created directory {}", path.display()),
+            (false, false) => format!("Created file {}", path.display()),
+        })
+        .collect()
+}
+
+/// Same as `init`, relative to `root`. Returns the files created (or that would be created).
+fn init_in(root: &Path, dry_run: bool) -> io::Result<Vec<PathBuf>> {
+    let mut created = eula(root, dry_run)?;
+    let properties = root.join(consts::file_paths::PROPERTIES);
+    if create_file(
+        &properties,
+        &consts::file_contents::server_properties(),
+        dry_run,
+    )? {
+        created.push(properties);
+    }
+    Ok(created)
 }
 
 /// Checks if the eula is agreed, if not creates it.
-fn eula() -> io::Result<()> {
-    let path = Path::new(consts::file_paths::EULA);
+fn eula(root: &Path, dry_run: bool) -> io::Result<Vec<PathBuf>> {
+    let path = root.join(consts::file_paths::EULA);
     if !path.exists() {
-        create_eula()?;
+        create_file(&path, &consts::file_contents::eula(), dry_run)?;
+        if dry_run {
+            return Ok(vec![path]);
+        }
         let content = "Please agree to the 'eula.txt' and start the server again.";
         warn!("{}", content.bright_red().bold());
         gracefully_exit(0);
     } else {
-        let is_agreed_eula = check_eula()?;
+        let is_agreed_eula = check_eula(&path)?;
         if !is_agreed_eula {
             let error_content = "Cannot start the server, please agree to the 'eula.txt'";
+            if dry_run {
+                warn!("[dry-run] {error_content}");
+                return Ok(Vec::new());
+            }
             error!("{}", error_content.bright_red().bold().blink());
             gracefully_exit(-1);
         }
-        Ok(())
+        Ok(Vec::new())
     }
 }
 
-/// Creates the 'server.properties' file if it does not already exist.
-fn create_server_properties() -> io::Result<()> {
-    let path = Path::new(consts::file_paths::PROPERTIES);
-    let content = consts::file_contents::server_properties();
-
-    utils::create_file(path, &content)
-}
-
-/// Creates the 'eula.txt' file if it does not already exist.
-fn create_eula() -> io::Result<()> {
-    let path = Path::new(consts::file_paths::EULA);
-    let content = consts::file_contents::eula();
-
-    utils::create_file(path, &content)
+/// Creates a file if it does not already exist, unless `dry_run`.
+/// Returns whether the file was (or would be) created.
+fn create_file(path: &Path, content: &str, dry_run: bool) -> io::Result<bool> {
+    if path.exists() {
+        return Ok(false);
+    }
+    if dry_run {
+        return Ok(true);
+    }
+    utils::create_file(path, content)?;
+    Ok(true)
 }
 
 /// Check if the 'eula.txt' has been agreed to.
-fn check_eula() -> io::Result<bool> {
-    let file = File::open(Path::new(consts::file_paths::EULA))?;
+fn check_eula(path: &Path) -> io::Result<bool> {
+    let file = File::open(path)?;
     let reader = io::BufReader::new(file);
 
     for line in reader.lines() {
@@ -68,110 +139,114 @@ fn check_eula() -> io::Result<bool> {
     Ok(false)
 }
 
+/// The files created empty if missing, besides the eula and server.properties.
+const OTHER_FILES: [&str; 6] = [
+    consts::file_paths::BANNED_IP,
+    consts::file_paths::BANNED_PLAYERS,
+    consts::file_paths::OPERATORS,
+    consts::file_paths::SESSION,
+    consts::file_paths::USERCACHE,
+    consts::file_paths::WHITELIST,
+];
+
+/// The server directories, and whether they are essential.
+const DIRECTORIES: [(&str, bool); 5] = [
+    (consts::directory_paths::LOGS, true),
+    (consts::directory_paths::WORLDS_DIRECTORY, true),
+    (consts::directory_paths::OVERWORLD, false),
+    (consts::directory_paths::THE_END, false),
+    (consts::directory_paths::NETHER, false),
+];
+
 pub fn create_other_files() {
-    match utils::create_file_nn(Path::new(consts::file_paths::BANNED_IP)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::BANNED_IP),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::BANNED_IP,
-            e
-        ),
-    }
-    match utils::create_file_nn(Path::new(consts::file_paths::BANNED_PLAYERS)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::BANNED_PLAYERS),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::BANNED_PLAYERS,
-            e
-        ),
-    }
-    match utils::create_file_nn(Path::new(consts::file_paths::OPERATORS)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::OPERATORS),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::OPERATORS,
-            e
-        ),
-    }
-    match utils::create_file_nn(Path::new(consts::file_paths::SESSION)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::SESSION),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::SESSION,
-            e
-        ),
-    }
-    match utils::create_file_nn(Path::new(consts::file_paths::USERCACHE)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::USERCACHE),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::USERCACHE,
-            e
-        ),
-    }
-    match utils::create_file_nn(Path::new(consts::file_paths::WHITELIST)) {
-        Ok(_) => info!("Created file {}", consts::file_paths::WHITELIST),
-        Err(e) => info!(
-            "Failed to create the file {} as error:{}",
-            consts::file_paths::WHITELIST,
-            e
-        ),
-    }
+    report_created(
+        &create_other_files_in(Path::new(""), is_dry_run()),
+        is_dry_run(),
+    );
 }
-pub fn create_dirs() {
-    match utils::create_dir(Path::new(consts::directory_paths::LOGS)) {
-        Ok(_) => info!("Created dir{}", consts::directory_paths::LOGS),
-        Err(e) => info!(
-            "Failed to create dir{} as error: {}",
-            consts::directory_paths::LOGS,
-            e
-        ),
-    }
 
-    match utils::create_dir(Path::new(consts::directory_paths::WORLDS_DIRECTORY)) {
-        Ok(_) => info!("No existing world data, creating new world"),
-        Err(e) => info!(
-            "Failed to create dir{} as error: {}",
-            consts::directory_paths::WORLDS_DIRECTORY,
-            e
-        ),
+/// Same as `create_other_files`, relative to `root`. Returns the files created (or that would be
+/// created).
+fn create_other_files_in(root: &Path, dry_run: bool) -> Vec<PathBuf> {
+    let mut created = Vec::new();
+    for file in OTHER_FILES {
+        let path = root.join(file);
+        match create_file(&path, "", dry_run) {
+            Ok(true) => created.push(path),
+            Ok(false) => (),
+            Err(e) => info!("Failed to create the file {} as error:{}", file, e),
+        }
     }
+    created
+}
 
-    match utils::create_dir(Path::new(consts::directory_paths::OVERWORLD)) {
-        Ok(_) => info!("Created dir{}", consts::directory_paths::OVERWORLD),
-        Err(e) => info!(
-            "Failed to create dir{} as error: {}",
-            consts::directory_paths::OVERWORLD,
-            e
-        ),
-    }
+/// Creates the server directories. Fails if an essential one (logs, world) can't be created, the
+/// server can't run without them. Other failures are only logged.
+pub fn create_dirs() -> io::Result<()> {
+    report_created(&create_dirs_in(Path::new(""), is_dry_run())?, is_dry_run());
+    Ok(())
+}
 
-    match utils::create_dir(Path::new(consts::directory_paths::THE_END)) {
-        Ok(_) => info!("Created dir{}", consts::directory_paths::THE_END),
-        Err(e) => info!(
-            "Failed to create dir{} as error: {}",
-            consts::directory_paths::THE_END,
-            e
-        ),
-    }
+/// Same as `create_dirs`, relative to `root`. Returns the directories created (or that would be
+/// created).
+fn create_dirs_in(root: &Path, dry_run: bool) -> io::Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    for (dir, essential) in DIRECTORIES {
+        let path = root.join(dir);
+        if path.is_dir() {
+            continue;
+        }
+        if dry_run {
+            created.push(path);
+            continue;
+        }
 
-    match utils::create_dir(Path::new(consts::directory_paths::NETHER)) {
-        Ok(_) => info!("Created dir{}", consts::directory_paths::NETHER),
-        Err(e) => info!(
-            "Failed to create dir{} as error: {}",
-            consts::directory_paths::NETHER,
-            e
-        ),
+        match utils::create_dir(&path) {
+            Ok(_) => created.push(path),
+            Err(e) if essential => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("failed to create the essential directory {dir}: {e}"),
+                ));
+            }
+            Err(e) => warn!("Failed to create dir{} as error: {}", dir, e),
+        }
     }
+    Ok(created)
 }
-#[derive(Serialize, Deserialize)]
-struct Player {
-    uuid: String,
-    name: String,
-    level: u8,
-    bypassesplayerlimit: bool,
+
+/// Highest operator permission level.
+pub const MAX_OP_LEVEL: u8 = 4;
+
+/// An entry of the 'ops.json' file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+    pub uuid: String,
+    pub name: String,
+    /// Permission level, from 1 to `MAX_OP_LEVEL`.
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit")]
+    pub bypasses_player_limit: bool,
 }
 
+/// Reads every operator of an 'ops.json' file. An empty file has no operators.
+/// The UUIDs are returned in the canonical dashed form, however they were written.
+pub fn read_ops_json(filename: &str) -> std::io::Result<Vec<Player>> {
+    let content = fs::read_to_string(filename)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut ops: Vec<Player> = serde_json::from_str(&content)?;
+    for op in &mut ops {
+        if let Some(canonical) = uuid::to_dashed(&op.uuid) {
+            op.uuid = canonical;
+        }
+    }
+    Ok(ops)
+}
+
+/// Adds an operator to an 'ops.json' file, or updates it if it's already an operator.
+/// Fails if `level` isn't between 1 and `MAX_OP_LEVEL`.
 pub fn write_ops_json(
     filename: &str,
     uuid: &str,
@@ -179,34 +254,124 @@ pub fn write_ops_json(
     level: u8,
     bypasses_player_limit: bool,
 ) -> std::io::Result<()> {
-    let mut file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .truncate(true)
-        .open(filename)?;
+    if !(1..=MAX_OP_LEVEL).contains(&level) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid operator level {level}, expected 1 to {MAX_OP_LEVEL}"),
+        ));
+    }
 
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
+    let mut ops = match read_ops_json(filename) {
+        Ok(ops) => ops,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
 
-    let mut json_data: Vec<Value> = if content.trim().is_empty() {
-        vec![]
-    } else {
-        serde_json::from_str(&content)?
+    let player = Player {
+        uuid: uuid::to_dashed(uuid).unwrap_or_else(|| uuid.to_string()),
+        name: name.to_string(),
+        level,
+        bypasses_player_limit,
     };
-    let new_object = json!({
-        "name": name,
-        "uuid": uuid,
-        "level": level,
-        "bypassesPlayerLimit": bypasses_player_limit
-    });
-    json_data.push(new_object);
-    file.set_len(0)?;
-    if let Err(e) = file.write_all(serde_json::to_string_pretty(&json_data)?.as_bytes()) {
+    match ops.iter_mut().find(|op| uuid::same_uuid(&op.uuid, uuid)) {
+        Some(op) => *op = player,
+        None => ops.push(player),
+    }
+
+    let mut file = File::create(filename)?;
+    if let Err(e) = file.write_all(serde_json::to_string_pretty(&ops)?.as_bytes()) {
         warn!("Failed to write to ops: {e}");
     }
     Ok(())
 }
 
+/// Returns the operator level of the player `uuid`, `None` if it isn't an operator.
+pub fn op_level(uuid: &str) -> Option<u8> {
+    op_level_in(consts::file_paths::OPERATORS, uuid)
+}
+
+fn op_level_in(filename: &str, uuid: &str) -> Option<u8> {
+    read_ops_json(filename)
+        .ok()?
+        .into_iter()
+        .find(|op| uuid::same_uuid(&op.uuid, uuid))
+        .map(|op| op.level)
+}
+
+/// The files listing players by UUID, whose UUIDs `migrate_uuids` rewrites.
+const UUID_FILES: [&str; 4] = [
+    consts::file_paths::OPERATORS,
+    consts::file_paths::WHITELIST,
+    consts::file_paths::BANNED_PLAYERS,
+    consts::file_paths::USERCACHE,
+];
+
+/// Rewrites the UUIDs of the player lists and the names of the player data files in the canonical
+/// dashed form, as older versions or other tools may have written them without dashes. What is
+/// already canonical is left untouched, so it only changes something once.
+pub fn migrate_uuids() {
+    match migrate_uuids_in(Path::new("")) {
+        Ok(migrated) => {
+            for path in migrated {
+                info!(
+                    "Migrated the UUIDs of {} to the dashed form",
+                    path.display()
+                );
+            }
+        }
+        Err(e) => error!("Failed to migrate the UUIDs to the dashed form: {e}"),
+    }
+}
+
+/// Same as `migrate_uuids`, relative to `root`. Returns the files rewritten, and the player data
+/// directory if files were renamed in it.
+fn migrate_uuids_in(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut migrated = Vec::new();
+    for file in UUID_FILES {
+        let path = root.join(file);
+        if normalize_uuid_file(&path)? {
+            migrated.push(path);
+        }
+    }
+
+    let player_data = root.join(consts::directory_paths::PLAYER_DATA);
+    if player::data::migrate_file_names(&player_data)? > 0 {
+        migrated.push(player_data);
+    }
+    Ok(migrated)
+}
+
+/// Rewrites the `uuid` fields of a JSON list of players in the canonical form. Returns whether the
+/// file changed. A missing or empty file has nothing to migrate.
+fn normalize_uuid_file(path: &Path) -> io::Result<bool> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if content.trim().is_empty() {
+        return Ok(false);
+    }
+
+    let mut entries: Vec<serde_json::Value> = serde_json::from_str(&content)?;
+    let mut changed = false;
+    for entry in &mut entries {
+        let Some(field) = entry.get_mut("uuid") else {
+            continue;
+        };
+        let canonical = field.as_str().and_then(uuid::to_dashed);
+        if let Some(canonical) = canonical.filter(|canonical| field != canonical.as_str()) {
+            *field = serde_json::Value::String(canonical);
+            changed = true;
+        }
+    }
+
+    if changed {
+        fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+    }
+    Ok(changed)
+}
+
 /// Removes all files related to the server, excluding the server.
 ///
 /// I am not sure if this is a good idea, because it takes some time to maintain and is not very
@@ -274,3 +439,145 @@ pub fn clean_files() -> Result<(), std::io::Error> {
     info!("Files cleaned successfully before starting the server.");
     gracefully_exit(0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dry_run_creates_nothing() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+
+        let mut planned = init_in(root, true)?;
+        planned.extend(create_dirs_in(root, true)?);
+        planned.extend(create_other_files_in(root, true));
+
+        assert_eq!(fs::read_dir(root)?.count(), 0);
+        assert!(planned.contains(&root.join(consts::file_paths::EULA)));
+        assert!(planned.contains(&root.join(consts::file_paths::PROPERTIES)));
+        assert!(planned.contains(&root.join(consts::file_paths::OPERATORS)));
+        assert!(planned.contains(&root.join(consts::directory_paths::OVERWORLD)));
+        assert_eq!(planned.len(), 2 + 5 + 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_dirs_and_files() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+
+        assert_eq!(create_dirs_in(root, false)?.len(), 5);
+        assert_eq!(create_other_files_in(root, false).len(), 6);
+        assert!(root.join(consts::directory_paths::NETHER).is_dir());
+        assert!(root.join(consts::file_paths::WHITELIST).is_file());
+
+        // Nothing left to create
+        assert!(create_dirs_in(root, true)?.is_empty());
+        assert!(create_other_files_in(root, true).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_second_init_creates_nothing() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join(consts::file_paths::EULA), "eula=true\n")?;
+
+        let mut created = init_in(root, false)?;
+        created.extend(create_dirs_in(root, false)?);
+        created.extend(create_other_files_in(root, false));
+        assert_eq!(created.len(), 1 + 5 + 6);
+        assert!(creation_messages(&created, false)
+            .contains(&"No existing world data, creating new world".to_string()));
+        assert!(is_initialized(root));
+
+        let properties = root.join(consts::file_paths::PROPERTIES);
+        fs::write(&properties, "motd=Kept\n")?;
+        let mut created = init_in(root, false)?;
+        created.extend(create_dirs_in(root, false)?);
+        created.extend(create_other_files_in(root, false));
+        assert!(created.is_empty());
+        assert!(creation_messages(&created, false).is_empty());
+        assert_eq!(fs::read_to_string(properties)?, "motd=Kept\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_essential_dir_failure() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        // A file where the world directory should be.
+        fs::write(root.join("world"), "")?;
+
+        let error = create_dirs_in(root, false).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(consts::directory_paths::WORLDS_DIRECTORY));
+        assert!(root.join(consts::directory_paths::LOGS).is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ops_json() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("ops.json");
+        let path = path.to_str().unwrap();
+        let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+        write_ops_json(path, uuid, "Notch", 3, false)?;
+        assert_eq!(op_level_in(path, uuid), Some(3));
+        assert_eq!(
+            op_level_in(path, "00000000-0000-0000-0000-000000000000"),
+            None
+        );
+        assert!(fs::read_to_string(path)?.contains("\"bypassesPlayerLimit\": false"));
+
+        // Updating an operator doesn't duplicate it
+        write_ops_json(path, uuid, "Notch", 4, true)?;
+        assert_eq!(read_ops_json(path)?.len(), 1);
+        assert_eq!(op_level_in(path, uuid), Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_undashed_ops_json() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join(consts::file_paths::OPERATORS);
+        fs::write(
+            &path,
+            r#"[{"uuid": "069A79F444E94726A5BEFCA90E38AAF5", "name": "Notch", "level": 4, "bypassesPlayerLimit": false}]"#,
+        )?;
+        let path_str = path.to_str().unwrap();
+        let dashed = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+        assert_eq!(op_level_in(path_str, dashed), Some(4));
+        assert_eq!(read_ops_json(path_str)?[0].uuid, dashed);
+
+        assert_eq!(migrate_uuids_in(temp_dir.path())?, vec![path.clone()]);
+        assert!(fs::read_to_string(&path)?.contains(dashed));
+        assert_eq!(
+            op_level_in(path_str, "069a79f444e94726a5befca90e38aaf5"),
+            Some(4)
+        );
+
+        // Nothing left to migrate.
+        assert!(migrate_uuids_in(temp_dir.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ops_json_invalid_level() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("ops.json");
+        let path = path.to_str().unwrap();
+
+        for level in [0, 5] {
+            let error = write_ops_json(path, "uuid", "Notch", level, false).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(!Path::new(path).exists());
+        Ok(())
+    }
+}
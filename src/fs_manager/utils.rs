@@ -1,4 +1,4 @@
-use log::{debug, info};
+use log::debug;
 use std::fs::metadata;
 use std::{
     fs::{self, File, OpenOptions},
@@ -41,9 +41,7 @@ pub fn create_file_nn(path: &Path) -> io::Result<()> {
 
 /// Creates a file given its path.
 pub fn create_dir(path: &Path) -> io::Result<()> {
-    fs::create_dir(path)?;
-    info!("Created directory: '{}'", path.to_string_lossy());
-    Ok(())
+    fs::create_dir(path)
 }
 
 /// Opens an already existing file and overwrites all content with `content`.
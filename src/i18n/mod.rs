@@ -0,0 +1,254 @@
+//! Localized log and disconnect messages. The language comes from the non-standard `lang`
+//! property, English being the default.
+//!
+//! Messages are templates where `{0}`, `{1}`, ... are replaced with the arguments.
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use log::warn;
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+/// The messages in English. Every key must be here, it's the fallback of the other languages.
+const EN_US: &[(&str, &str)] = &[
+    ("server.starting", "Starting minecraft server version {0}"),
+    ("server.started", "[ SERVER STARTED ]"),
+    ("server.shut_down", "[ SERVER SHUT DOWN ]"),
+    ("server.greet", "Hello, world from Cactus!"),
+    ("banner.cactus", "Cactus {0}"),
+    ("banner.minecraft", "Minecraft {0} (protocol {1})"),
+    ("banner.address", "Address: {0}"),
+    ("banner.world", "World: {0}, default game type: {1}"),
+    ("server.ready", "{0} Done ({1}s)!"),
+    ("server.shutdown_code", "[ server shutdown with code: {0}]"),
+    ("disconnect.server_full", "The server is full!"),
+    (
+        "disconnect.not_whitelisted",
+        "You are not white-listed on this server!",
+    ),
+    (
+        "disconnect.outdated_client",
+        "Outdated client! Please use {0}",
+    ),
+    (
+        "disconnect.outdated_server",
+        "Outdated server! I'm still on {0}",
+    ),
+    (
+        "disconnect.internal_error",
+        "An internal server error occurred.",
+    ),
+    ("disconnect.protocol_error", "Network Protocol Error"),
+    (
+        "disconnect.not_implemented",
+        "Joining the game isn't implemented yet.",
+    ),
+    (
+        "disconnect.missing_public_key",
+        "Missing profile public key.\nThis server requires secure profiles.",
+    ),
+    (
+        "disconnect.expired_public_key",
+        "Expired profile public key. Check that your system time is synchronized, and try restarting your game.",
+    ),
+    (
+        "disconnect.invalid_public_key_signature",
+        "Invalid signature for profile public key.\nTry restarting your game.",
+    ),
+    ("bug_report.link", "Please report it at {0}"),
+    ("server.crashed", "The server crashed!"),
+];
+
+const FR_FR: &[(&str, &str)] = &[
+    (
+        "server.starting",
+        "Démarrage du serveur minecraft version {0}",
+    ),
+    ("server.started", "[ SERVEUR DÉMARRÉ ]"),
+    ("server.shut_down", "[ SERVEUR ARRÊTÉ ]"),
+    ("server.greet", "Bonjour, le monde, de la part de Cactus !"),
+    ("banner.cactus", "Cactus {0}"),
+    ("banner.minecraft", "Minecraft {0} (protocole {1})"),
+    ("banner.address", "Adresse : {0}"),
+    ("banner.world", "Monde : {0}, mode de jeu par défaut : {1}"),
+    ("server.ready", "{0} Terminé ({1}s) !"),
+    (
+        "server.shutdown_code",
+        "[ arrêt du serveur avec le code : {0}]",
+    ),
+    ("disconnect.server_full", "Le serveur est plein !"),
+    (
+        "disconnect.not_whitelisted",
+        "Vous n'êtes pas sur la liste blanche de ce serveur !",
+    ),
+    (
+        "disconnect.outdated_client",
+        "Client obsolète ! Veuillez utiliser la {0}",
+    ),
+    (
+        "disconnect.outdated_server",
+        "Serveur obsolète ! J'utilise encore la {0}",
+    ),
+    (
+        "disconnect.internal_error",
+        "Une erreur interne du serveur est survenue.",
+    ),
+    ("disconnect.protocol_error", "Erreur de protocole réseau"),
+    (
+        "disconnect.not_implemented",
+        "Rejoindre la partie n'est pas encore possible.",
+    ),
+    (
+        "disconnect.missing_public_key",
+        "Clé publique de profil manquante.\nCe serveur requiert des profils sécurisés.",
+    ),
+    (
+        "disconnect.expired_public_key",
+        "Clé publique de profil expirée. Vérifiez que l'heure de votre système est synchronisée et essayez de redémarrer votre jeu.",
+    ),
+    (
+        "disconnect.invalid_public_key_signature",
+        "Signature invalide pour la clé publique de profil.\nEssayez de redémarrer votre jeu.",
+    ),
+    ("bug_report.link", "Merci de la signaler sur {0}"),
+    ("server.crashed", "Le serveur a planté !"),
+];
+
+/// The messages of each language, by key.
+type Messages = HashMap<Lang, HashMap<&'static str, &'static str>>;
+
+static MESSAGES: Lazy<Messages> = Lazy::new(|| {
+    [(Lang::EnUs, EN_US), (Lang::FrFr, FR_FR)]
+        .into_iter()
+        .map(|(lang, messages)| (lang, messages.iter().copied().collect()))
+        .collect()
+});
+
+static LANG: RwLock<Lang> = RwLock::new(Lang::EnUs);
+
+/// The languages with bundled messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Lang {
+    #[default]
+    EnUs,
+    FrFr,
+}
+
+impl Lang {
+    /// Parses a language code like vanilla's, e.g. `en_us`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_lowercase().replace('-', "_").as_str() {
+            "en_us" | "en" => Some(Self::EnUs),
+            "fr_fr" | "fr" => Some(Self::FrFr),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the language of the messages from the non-standard `lang` property.
+pub fn init(settings: &Settings) {
+    let Some(code) = settings.extra("lang") else {
+        return;
+    };
+    match Lang::from_code(code) {
+        Some(lang) => set_lang(lang),
+        None => warn!("Unknown lang {code}, the messages are in English"),
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    *LANG.write().unwrap() = lang;
+}
+
+pub fn lang() -> Lang {
+    *LANG.read().unwrap()
+}
+
+/// The message `key` in the current language, see `translate`.
+pub fn tr(key: &str, args: &[&str]) -> String {
+    translate(lang(), key, args)
+}
+
+/// The message `key` in `lang`, with `{0}`, `{1}`, ... replaced with `args`. Falls back to English
+/// if `lang` doesn't have the message, and to `key` itself if English doesn't either.
+pub fn translate(lang: Lang, key: &str, args: &[&str]) -> String {
+    translate_with(&MESSAGES, lang, key, args)
+}
+
+fn translate_with(messages: &Messages, lang: Lang, key: &str, args: &[&str]) -> String {
+    let template = messages
+        .get(&lang)
+        .and_then(|messages| messages.get(key))
+        .or_else(|| messages[&Lang::EnUs].get(key))
+        .copied()
+        .unwrap_or(key);
+
+    args.iter()
+        .enumerate()
+        .fold(template.to_string(), |message, (i, arg)| {
+            message.replace(&format!("{{{i}}}"), arg)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch_language() {
+        assert_eq!(
+            translate(Lang::EnUs, "disconnect.server_full", &[]),
+            "The server is full!"
+        );
+        assert_eq!(
+            translate(Lang::FrFr, "disconnect.server_full", &[]),
+            "Le serveur est plein !"
+        );
+        assert_eq!(
+            translate(Lang::FrFr, "server.starting", &["1.21.4"]),
+            "Démarrage du serveur minecraft version 1.21.4"
+        );
+    }
+
+    #[test]
+    fn test_fallback_to_english() {
+        let messages = Messages::from([
+            (
+                Lang::EnUs,
+                HashMap::from([("greet", "Hello {0}"), ("leave", "Bye")]),
+            ),
+            (Lang::FrFr, HashMap::from([("greet", "Bonjour {0}")])),
+        ]);
+        assert_eq!(
+            translate_with(&messages, Lang::FrFr, "greet", &["Steve"]),
+            "Bonjour Steve"
+        );
+        assert_eq!(translate_with(&messages, Lang::FrFr, "leave", &[]), "Bye");
+        assert_eq!(
+            translate_with(&messages, Lang::FrFr, "unknown.key", &[]),
+            "unknown.key"
+        );
+    }
+
+    #[test]
+    fn test_every_key_in_english() {
+        for (key, _) in FR_FR {
+            assert!(MESSAGES[&Lang::EnUs].contains_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_every_key_in_french() {
+        for (key, _) in EN_US {
+            assert!(MESSAGES[&Lang::FrFr].contains_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_lang_codes() {
+        assert_eq!(Lang::from_code("fr_FR"), Some(Lang::FrFr));
+        assert_eq!(Lang::from_code("en-us"), Some(Lang::EnUs));
+        assert_eq!(Lang::from_code("xx"), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Captures the log records of the current thread, for the tests checking what is logged.
+use std::cell::RefCell;
+use std::sync::Once;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+thread_local! {
+    /// The records logged by the current thread since `start`, `None` if it isn't capturing.
+    static RECORDS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        RECORDS.with(|records| records.borrow().is_some())
+    }
+
+    fn log(&self, record: &Record) {
+        RECORDS.with(|records| {
+            if let Some(records) = records.borrow_mut().as_mut() {
+                records.push(format!("{} {}", record.level(), record.args()));
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Starts capturing what the current thread logs, as "LEVEL message".
+pub fn start() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_boxed_logger(Box::new(CaptureLogger)).expect("No other logger in the tests");
+        log::set_max_level(LevelFilter::Trace);
+    });
+    RECORDS.with(|records| *records.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stops capturing, and returns what the current thread logged since `start`.
+pub fn take() -> Vec<String> {
+    RECORDS.with(|records| records.borrow_mut().take().unwrap_or_default())
+}
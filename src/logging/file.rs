@@ -0,0 +1,81 @@
+//! The log file, `logs/latest.log`, archived as `logs/<date>-<n>.log.gz` when rolled.
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use once_cell::sync::Lazy;
+
+/// Name of the file the logs are written to.
+pub const LATEST_LOG: &str = "latest.log";
+
+static LOG_FILE: Lazy<Arc<Mutex<Option<LogFile>>>> = Lazy::new(|| Arc::new(Mutex::new(None)));
+
+/// Returns the log file of the server, `None` until the logging is initialized, or if it
+/// couldn't be opened.
+pub fn global() -> Arc<Mutex<Option<LogFile>>> {
+    LOG_FILE.clone()
+}
+
+#[derive(Debug)]
+pub struct LogFile {
+    dir: PathBuf,
+    file: File,
+}
+
+impl LogFile {
+    /// Opens the `latest.log` of `dir`, appending to it. Creates `dir` if needed.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LATEST_LOG))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+        })
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(LATEST_LOG)
+    }
+
+    /// Appends a line to the file.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.file, "{line}")
+    }
+
+    /// Makes sure the lines written so far are on disk, e.g. before exiting.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.sync_data()
+    }
+
+    /// Compresses the current file into a dated archive, and starts a fresh one.
+    /// Returns the path of the archive.
+    pub fn roll(&mut self) -> io::Result<PathBuf> {
+        self.file.flush()?;
+        let archive = self.next_archive();
+
+        let mut encoder = GzEncoder::new(File::create(&archive)?, Compression::default());
+        io::copy(&mut BufReader::new(File::open(self.path())?), &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+
+        File::create(self.path())?;
+        *self = Self::open(&self.dir)?;
+        Ok(archive)
+    }
+
+    /// The first free archive name of the day, like vanilla: `2024-12-03-1.log.gz`, then `-2`...
+    fn next_archive(&self) -> PathBuf {
+        let date = Local::now().format("%Y-%m-%d");
+        (1..)
+            .map(|n| self.dir.join(format!("{date}-{n}.log.gz")))
+            .find(|path| !path.exists())
+            .expect("There is always a free archive name")
+    }
+}
@@ -1,5 +1,69 @@
+#[cfg(test)]
+pub mod capture;
+pub mod file;
+
+use std::path::Path;
+use std::sync::Mutex;
+
 use env_logger::Builder;
-use log::LevelFilter;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::{consts, fs_manager};
+
+/// Logs to the terminal with env_logger, and to the log file when it is open.
+struct Logger {
+    terminal: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.terminal.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.terminal.log(record);
+
+        let log_file = file::global();
+        let Ok(mut log_file) = log_file.lock() else {
+            return;
+        };
+        if let Some(log_file) = log_file.as_mut() {
+            let line = format!(
+                "[{}] [{}] [{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            // Nowhere to report it, the logger is what fails.
+            let _ = log_file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        self.terminal.flush();
+        flush_file(&file::global());
+    }
+}
+
+/// Writes out the pending log records, to the terminal and to `log_file`. To call before exiting,
+/// as `process::exit` doesn't.
+pub fn flush(log_file: &Mutex<Option<file::LogFile>>) {
+    log::logger().flush();
+    flush_file(log_file);
+}
+
+fn flush_file(log_file: &Mutex<Option<file::LogFile>>) {
+    let Ok(mut log_file) = log_file.lock() else {
+        return;
+    };
+    if let Some(log_file) = log_file.as_mut() {
+        let _ = log_file.flush();
+    }
+}
 
 /// Initializes the logging for the whole application
 pub fn init(log_level: LevelFilter) {
@@ -7,20 +71,19 @@ pub fn init(log_level: LevelFilter) {
 
     // TODO: Customize logging format. Making the logging level the right color is time consuming.
 
-    //builder.format(|buf, record| {
-    //    writeln!(
-    //        buf,
-    //        "[{}] [{}] [{}] {}",
-    //        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-    //        record.level(),
-    //        record.target(),
-    //        record.args()
-    //    )
-    //});
-
-    // And add use::io::Write; for the above code.
-
     builder.filter_level(log_level);
 
-    builder.init();
+    let logger = Logger {
+        terminal: builder.build(),
+    };
+    log::set_max_level(logger.terminal.filter());
+    log::set_boxed_logger(Box::new(logger)).expect("The logger is initialized once");
+
+    if fs_manager::is_dry_run() {
+        return;
+    }
+    match file::LogFile::open(Path::new(consts::directory_paths::LOGS)) {
+        Ok(log_file) => *file::global().lock().unwrap() = Some(log_file),
+        Err(e) => log::error!("Failed to open the log file, logging to the terminal only: {e}"),
+    }
 }
@@ -5,23 +5,30 @@ mod config;
 mod consts;
 mod file_folder_parser;
 mod fs_manager;
+mod i18n;
 mod logging;
+mod metrics;
 mod net;
-use log::{error, info, warn};
-use net::packet;
+use log::{debug, error, info, warn};
 mod chunks_manager;
 mod encode_chunk;
 mod player;
+mod region_parser;
 mod seed_hasher;
+mod self_test;
+mod tick;
 mod time;
+mod world;
 
-use config::Gamemode;
 use consts::messages;
+use std::time::Instant;
+use tokio::sync::oneshot;
 
 #[tokio::main]
 async fn main() {
     args::init();
 
+    let startup = Instant::now();
     if let Err(e) = early_init().await {
         error!("Failed to start the server, error in early initialization: {e}. \nExiting...");
         gracefully_exit(-1);
@@ -32,12 +39,13 @@ async fn main() {
         gracefully_exit(-1);
     }
 
-    if let Err(e) = start().await {
+    if let Err(e) = start(startup).await {
         error!("Failed to start the server: {e}. \nExiting...");
         gracefully_exit(-1);
     }
 
-    info!("{}", *messages::SERVER_SHUTDOWN);
+    info!("{}", messages::server_shutdown());
+    logging::flush(&logging::file::global());
 }
 
 /// Logic that must executes as early as possibe
@@ -45,15 +53,11 @@ async fn early_init() -> Result<(), Box<dyn std::error::Error>> {
     // This must executes as early as possible
     logging::init(log::LevelFilter::Debug);
 
-    info!("{}", *messages::SERVER_STARTING);
+    info!("{}", messages::server_starting());
 
     // Adds custom behavior to CTRL + C signal
     init_ctrlc_handler()?;
 
-    // A testing function, only in debug mode
-    #[cfg(debug_assertions)]
-    test();
-
     // Listens for cli input commands
     commands::listen_console_commands().await;
     Ok(())
@@ -61,28 +65,34 @@ async fn early_init() -> Result<(), Box<dyn std::error::Error>> {
 
 /// Essential server initialization logic.
 fn init() -> Result<(), Box<dyn std::error::Error>> {
-    // Printing a greeting message
-    greet();
-
     // Makes sure server files are initialized and valid.
     fs_manager::init()?;
-    fs_manager::create_dirs();
+    fs_manager::create_dirs()?;
     fs_manager::create_other_files();
+    if fs_manager::is_dry_run() {
+        info!("Dry run finished, no file was created");
+        gracefully_exit(0);
+    }
+
+    fs_manager::migrate_uuids();
+
+    // The log and disconnect messages are in the `lang` of server.properties from now on.
+    i18n::init(&config::Settings::new());
+    commands::broadcast::init(&config::Settings::new());
+    init_panic_hook(&config::Settings::new());
 
-    // TODO: Not sure this has to be in main.rs
-    let gamemode1 = match config::Settings::new().gamemode {
-        Gamemode::Survival => "Survival",
-        Gamemode::Adventure => "Adventure",
-        Gamemode::Creative => "Creative",
-        Gamemode::Spectator => "Spectator",
-    };
-    info!("Default game type: {}", gamemode1.to_uppercase());
+    // Printing a greeting message
+    greet();
+
+    for warning in config::Settings::new().validate() {
+        warn!("server.properties: {warning}");
+    }
 
     Ok(())
 }
 
-/// Starts up the server.
-async fn start() -> Result<(), Box<dyn std::error::Error>> {
+/// Starts up the server. `startup` is when the server started initializing.
+async fn start(startup: Instant) -> Result<(), Box<dyn std::error::Error>> {
     info!(
         "Starting Minecraft server on {}:{}",
         match config::Settings::new().server_ip {
@@ -91,9 +101,38 @@ async fn start() -> Result<(), Box<dyn std::error::Error>> {
         },
         config::Settings::new().server_port
     );
-    info!("{}", *messages::SERVER_STARTED);
 
-    net::listen().await.map_err(|e| {
+    if net::status_only::is_enabled() {
+        info!("Status-only mode: logins are refused and the world isn't ticked");
+    } else {
+        // TODO: Give the seed to the chunk generators, once the chunks are generated on demand.
+        let level_dat = std::path::Path::new(consts::file_paths::LEVEL_DAT);
+        let seed = world::level::init_seed(level_dat, &config::Settings::new())?;
+        debug!("World seed: {seed}");
+        let time = world::time::WorldTime::from_level(&world::level::LevelData::load(level_dat)?);
+        if let Ok(mut world_time) = world::time::global().write() {
+            *world_time = time;
+        }
+
+        let scheduler =
+            tick::TickScheduler::from_settings(&config::Settings::new(), player::online_players());
+        let world_time = world::time::global();
+        scheduler.spawn(move || {
+            if let Ok(mut time) = world_time.write() {
+                time.tick();
+            }
+        });
+    }
+
+    // Only reports readiness once the server is actually accepting connections.
+    let (ready, bound) = oneshot::channel();
+    tokio::spawn(async move {
+        if bound.await.is_ok() {
+            info!("{}", messages::server_ready(startup.elapsed()));
+        }
+    });
+
+    net::listen(ready).await.map_err(|e| {
         error!("Failed to listen for packets: {e}");
         e
     })?;
@@ -111,51 +150,63 @@ fn init_ctrlc_handler() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// Prints the starting greetings
-fn greet() {
-    info!("{}", *messages::GREET);
+/// Logs where to report a crash, after the default panic message.
+fn init_panic_hook(settings: &config::Settings) {
+    let bug_report_link = settings.bug_report_link.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        error!("{}", messages::crash(bug_report_link.as_deref()));
+        logging::flush(&logging::file::global());
+    }));
 }
 
-#[cfg(debug_assertions)]
-/// A test fonction that'll only run in debug-mode. (cargo run) and not (cargo run --release)
-fn test() {
-    use packet::data_types::{string, varint};
-
-    info!("[ BEGIN test() ]");
-
-    // Do not remove this line, yet.
-    let _ = packet::Packet::new(&[]);
-
-    let s = &[6, 72, 69, 76, 76, 79, 33, 0xFF, 0xFF, 0xFF];
-    let string_s = string::read(s);
-    info!("{string_s:#?}");
-
-    let a = &s[..2];
-    info!("{a:#?}");
-
-    let a = &s[2..];
-    info!("{a:#?}");
-
-    info!("Hello, world from test()!");
-
-    warn!("----------------------------------------------------");
-
-    let empty_vec = Vec::default();
-    info!("empty_vec = {empty_vec:#?}");
-    let read = varint::read(&empty_vec);
-    info!("Read with empty_vec: {read:#?} (val, bytes_read)");
-
-    info!("[ END test()]");
+/// Prints the starting greetings, and the banner telling what runs.
+fn greet() {
+    info!("{}", messages::greet());
+    for line in messages::banner(&config::Settings::new()).lines() {
+        info!("{line}");
+    }
 }
 
 /// Gracefully exits the server with an exit code.
+/// The Ctrl+C handler and the `stop` command exit through it.
 pub fn gracefully_exit(code: i32) -> ! {
+    shutdown(code, &logging::file::global());
+
+    // Well, for now it's not "gracefully" exiting.
+    std::process::exit(code);
+}
+
+/// What is done before exiting with `code`: the last logs are written to `log_file`, which
+/// `process::exit` would otherwise leave to chance.
+fn shutdown(code: i32, log_file: &std::sync::Mutex<Option<logging::file::LogFile>>) {
     if code == 0 {
-        info!("{}", *messages::SERVER_SHUTDOWN);
+        info!("{}", messages::server_shutdown());
     } else {
         warn!("{}", messages::server_shutdown_code(code));
     }
+    logging::flush(log_file);
+}
 
-    // Well, for now it's not "gracefully" exiting.
-    std::process::exit(code);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_shutdown_flushes_logs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut log_file = logging::file::LogFile::open(temp_dir.path()).unwrap();
+        log_file.write_line("[INFO] Stopping").unwrap();
+        let path = log_file.path();
+        let log_file = Mutex::new(Some(log_file));
+
+        shutdown(-1, &log_file);
+
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "[INFO] Stopping\n"
+        );
+    }
 }
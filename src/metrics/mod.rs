@@ -0,0 +1,259 @@
+//! Counters describing the state of the server, shown by the `debug` command.
+//!
+//! Every subsystem updates the global `Metrics` with atomics, so that recording never waits on a
+//! lock. `snapshot` copies them into a plain `MetricsSnapshot`. The client versions are the
+//! exception, behind a mutex only taken once per connection.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+use crate::net::ConnectionState;
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
+/// Returns the metrics of the server.
+pub fn global() -> &'static Metrics {
+    &METRICS
+}
+
+/// Weight of the newest tick in the tick time averages, in percent.
+const TICK_AVERAGE_WEIGHT: u64 = 10;
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    connections: AtomicUsize,
+    connections_total: AtomicU64,
+    /// Open connections per state, indexed by `ConnectionState::index`.
+    connections_per_state: [AtomicUsize; ConnectionState::COUNT],
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    packets_handled: AtomicU64,
+    /// Status Requests answered, e.g. by the server lists.
+    status_requests: AtomicU64,
+    ticks: AtomicU64,
+    /// Average time spent in a tick, in microseconds.
+    tick_time_micros: AtomicU64,
+    /// Average time between the start of two ticks, in microseconds.
+    tick_interval_micros: AtomicU64,
+    /// Handshakes received per protocol version.
+    client_versions: Mutex<BTreeMap<i32, u64>>,
+}
+
+impl Metrics {
+    /// A connection was accepted, it starts in the Handshake state.
+    pub fn connection_opened(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+        self.connections_total.fetch_add(1, Ordering::Relaxed);
+        self.connections_per_state[ConnectionState::Handshake.index()]
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A connection in `state` was closed.
+    pub fn connection_closed(&self, state: ConnectionState) {
+        self.connections.fetch_sub(1, Ordering::Relaxed);
+        self.connections_per_state[state.index()].fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn state_changed(&self, from: ConnectionState, to: ConnectionState) {
+        self.connections_per_state[from.index()].fetch_sub(1, Ordering::Relaxed);
+        self.connections_per_state[to.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A handshake announcing `protocol_version` was received.
+    pub fn handshake_received(&self, protocol_version: i32) {
+        *self
+            .client_versions
+            .lock()
+            .unwrap()
+            .entry(protocol_version)
+            .or_default() += 1;
+    }
+
+    pub fn bytes_received(&self, count: usize) {
+        self.bytes_in.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn bytes_sent(&self, count: usize) {
+        self.bytes_out.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn packet_handled(&self) {
+        self.packets_handled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn status_request_answered(&self) {
+        self.status_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A tick took `duration`, and started `interval` after the previous one.
+    ///
+    /// Only the tick loop records ticks, so the averages don't need a compare-and-swap.
+    pub fn tick_recorded(&self, duration: Duration, interval: Duration) {
+        let average = |value: &AtomicU64, sample: Duration| {
+            let sample = sample.as_micros() as u64;
+            let previous = value.load(Ordering::Relaxed);
+            let updated = if previous == 0 {
+                sample
+            } else {
+                (previous * (100 - TICK_AVERAGE_WEIGHT) + sample * TICK_AVERAGE_WEIGHT) / 100
+            };
+            value.store(updated, Ordering::Relaxed);
+        };
+
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+        average(&self.tick_time_micros, duration);
+        average(&self.tick_interval_micros, interval);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let tick_interval = self.tick_interval_micros.load(Ordering::Relaxed);
+
+        MetricsSnapshot {
+            connections: self.connections.load(Ordering::Relaxed),
+            connections_total: self.connections_total.load(Ordering::Relaxed),
+            connections_per_state: ConnectionState::ALL
+                .map(|state| {
+                    let count = self.connections_per_state[state.index()].load(Ordering::Relaxed);
+                    (state, count)
+                })
+                .to_vec(),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            packets_handled: self.packets_handled.load(Ordering::Relaxed),
+            status_requests: self.status_requests.load(Ordering::Relaxed),
+            ticks: self.ticks.load(Ordering::Relaxed),
+            tps: match tick_interval {
+                0 => 0.0,
+                micros => 1_000_000.0 / micros as f64,
+            },
+            mspt: self.tick_time_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+            memory_bytes: memory_estimate(),
+            client_versions: self
+                .client_versions
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(&version, &count)| (version, count))
+                .collect(),
+        }
+    }
+}
+
+/// A copy of the metrics at some point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSnapshot {
+    pub connections: usize,
+    pub connections_total: u64,
+    pub connections_per_state: Vec<(ConnectionState, usize)>,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub packets_handled: u64,
+    pub status_requests: u64,
+    pub ticks: u64,
+    pub tps: f64,
+    /// Milliseconds per tick.
+    pub mspt: f64,
+    /// Resident memory of the process, when the platform tells it.
+    pub memory_bytes: Option<u64>,
+    /// Handshakes received per protocol version, by increasing version.
+    pub client_versions: Vec<(i32, u64)>,
+}
+
+impl fmt::Display for MetricsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Connections: {} open, {} since start",
+            self.connections, self.connections_total
+        )?;
+        for (state, count) in &self.connections_per_state {
+            writeln!(f, "  {state:?}: {count}")?;
+        }
+        writeln!(
+            f,
+            "Network: {} bytes in, {} bytes out, {} packets handled",
+            self.bytes_in, self.bytes_out, self.packets_handled
+        )?;
+        writeln!(f, "Status requests: {}", self.status_requests)?;
+        writeln!(
+            f,
+            "Ticks: {} ({:.1} TPS, {:.2} MSPT)",
+            self.ticks, self.tps, self.mspt
+        )?;
+        writeln!(f, "Client versions:")?;
+        for (version, count) in &self.client_versions {
+            writeln!(f, "  Protocol {version}: {count}")?;
+        }
+        match self.memory_bytes {
+            Some(bytes) => write!(f, "Memory: {} MiB", bytes / (1024 * 1024)),
+            None => write!(f, "Memory: unknown"),
+        }
+    }
+}
+
+/// The resident memory of the process, read from `/proc` on Linux.
+fn memory_estimate() -> Option<u64> {
+    /// Assumed, as the standard library can't query it.
+    const PAGE_SIZE: u64 = 4096;
+
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(resident_pages * PAGE_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_counters() {
+        let metrics = Metrics::default();
+
+        metrics.connection_opened();
+        metrics.connection_opened();
+        metrics.state_changed(ConnectionState::Handshake, ConnectionState::Status);
+        metrics.connection_closed(ConnectionState::Status);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.connections, 1);
+        assert_eq!(snapshot.connections_total, 2);
+        assert_eq!(
+            snapshot.connections_per_state[ConnectionState::Handshake.index()],
+            (ConnectionState::Handshake, 1)
+        );
+        assert_eq!(
+            snapshot.connections_per_state[ConnectionState::Status.index()],
+            (ConnectionState::Status, 0)
+        );
+    }
+
+    #[test]
+    fn test_client_versions() {
+        let metrics = Metrics::default();
+
+        metrics.handshake_received(769);
+        metrics.handshake_received(767);
+        metrics.handshake_received(769);
+
+        assert_eq!(metrics.snapshot().client_versions, vec![(767, 1), (769, 2)]);
+    }
+
+    #[test]
+    fn test_tick_averages() {
+        let metrics = Metrics::default();
+
+        metrics.tick_recorded(Duration::from_millis(10), Duration::from_millis(50));
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.ticks, 1);
+        assert_eq!(snapshot.mspt, 10.0);
+        assert_eq!(snapshot.tps, 20.0);
+
+        metrics.tick_recorded(Duration::from_millis(20), Duration::from_millis(50));
+        assert_eq!(metrics.snapshot().mspt, 11.0);
+    }
+}
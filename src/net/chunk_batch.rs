@@ -0,0 +1,176 @@
+//! Sends the chunks in batches, paced by the client.
+//!
+//! Since 1.20.2, the Chunk Data packets are sent between a Chunk Batch Start and a Chunk Batch
+//! Finished. The client answers each batch with a Chunk Batch Received telling how many chunks it
+//! can process per tick, which sets the size of the next batches. Like vanilla, a new batch waits
+//! for the previous ones to be acknowledged.
+use std::collections::VecDeque;
+
+use super::packet::packet_types::{
+    ChunkBatchFinished, ChunkBatchReceived, ChunkBatchStart, EncodablePacket,
+};
+use super::packet::{Packet, PacketError};
+use crate::chunks_manager::pos::ChunkPos;
+
+/// The chunks per tick before the client tells its rate.
+const INITIAL_CHUNKS_PER_TICK: f32 = 9.0;
+/// The bounds of the rate the client can ask for.
+const MIN_CHUNKS_PER_TICK: f32 = 0.01;
+const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+/// How many batches can wait for an acknowledgement once the client answered the first one.
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+
+/// The chunks waiting to be sent to a player, and the pacing of their batches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSender {
+    pending: VecDeque<ChunkPos>,
+    desired_chunks_per_tick: f32,
+    /// The chunks that can be sent, growing each tick. Fractional for the slow clients.
+    batch_quota: f32,
+    unacknowledged_batches: u32,
+    /// Only one batch until the client tells its rate.
+    max_unacknowledged_batches: u32,
+}
+
+impl ChunkSender {
+    /// Sends `chunks`, in this order. See `ViewDistances::chunks_in_view` for the initial ones.
+    // TODO: Create it when a player joins, once the Play state exists.
+    pub fn new(chunks: impl IntoIterator<Item = ChunkPos>) -> Self {
+        Self {
+            pending: chunks.into_iter().collect(),
+            desired_chunks_per_tick: INITIAL_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            max_unacknowledged_batches: 1,
+        }
+    }
+
+    /// The chunks not sent yet.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Called each tick. Returns the chunks of the next batch, or `None` if nothing can be sent
+    /// this tick: no chunk is waiting, the quota is too low, or the client hasn't acknowledged
+    /// enough batches.
+    pub fn next_batch(&mut self) -> Option<Vec<ChunkPos>> {
+        self.batch_quota = (self.batch_quota + self.desired_chunks_per_tick)
+            .min(self.desired_chunks_per_tick.max(1.0));
+        if self.pending.is_empty()
+            || self.batch_quota < 1.0
+            || self.unacknowledged_batches >= self.max_unacknowledged_batches
+        {
+            return None;
+        }
+
+        let size = (self.batch_quota as usize).min(self.pending.len());
+        self.batch_quota -= size as f32;
+        self.unacknowledged_batches += 1;
+        Some(self.pending.drain(..size).collect())
+    }
+
+    /// Updates the pacing with the client's answer to a batch.
+    pub fn on_batch_received(&mut self, received: ChunkBatchReceived) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.desired_chunks_per_tick = if received.chunks_per_tick.is_nan() {
+            MIN_CHUNKS_PER_TICK
+        } else {
+            received
+                .chunks_per_tick
+                .clamp(MIN_CHUNKS_PER_TICK, MAX_CHUNKS_PER_TICK)
+        };
+        if self.unacknowledged_batches == 0 {
+            self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES;
+        }
+    }
+}
+
+/// The packets of a batch: Chunk Batch Start, the Chunk Data of each chunk built by `chunk_data`,
+/// then Chunk Batch Finished with the number of chunks.
+pub fn batch_packets(
+    chunks: &[ChunkPos],
+    mut chunk_data: impl FnMut(ChunkPos) -> Result<Packet, PacketError>,
+) -> Result<Vec<Packet>, PacketError> {
+    let mut packets = Vec::with_capacity(chunks.len() + 2);
+    packets.push(ChunkBatchStart.build()?);
+    for &chunk in chunks {
+        packets.push(chunk_data(chunk)?);
+    }
+    packets.push(
+        ChunkBatchFinished {
+            batch_size: chunks.len() as i32,
+        }
+        .build()?,
+    );
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::data_types::varint;
+    use crate::net::packet::PacketBuilder;
+
+    fn chunks(count: i32) -> Vec<ChunkPos> {
+        (0..count).map(|x| ChunkPos::new(x, 0)).collect()
+    }
+
+    #[test]
+    fn test_batch_packets() {
+        assert_eq!(
+            ChunkBatchStart.build().unwrap().get_full_packet(),
+            [1, 0x0C]
+        );
+        assert_eq!(
+            ChunkBatchFinished { batch_size: 300 }
+                .build()
+                .unwrap()
+                .get_full_packet(),
+            [3, 0x0B, 0xAC, 0x02]
+        );
+
+        let batch = chunks(5);
+        let packets = batch_packets(&batch, |chunk| {
+            PacketBuilder::new().append_varint(chunk.x).build(0x28)
+        })
+        .unwrap();
+
+        assert_eq!(packets.len(), 7);
+        assert_eq!(packets[0].get_id().get_value(), 0x0C);
+        let chunk_data = packets.iter().filter(|p| p.get_id().get_value() == 0x28);
+        assert_eq!(chunk_data.count(), 5);
+        let finished = packets.last().unwrap();
+        assert_eq!(finished.get_id().get_value(), 0x0B);
+        assert_eq!(varint::read(finished.get_payload()).unwrap().0, 5);
+    }
+
+    #[test]
+    fn test_batches_wait_for_acknowledgement() {
+        let mut sender = ChunkSender::new(chunks(100));
+
+        let first = sender.next_batch().unwrap();
+        assert_eq!(first, chunks(9));
+        // Waits for the client's answer.
+        assert_eq!(sender.next_batch(), None);
+
+        sender.on_batch_received(ChunkBatchReceived {
+            chunks_per_tick: 20.0,
+        });
+        assert_eq!(sender.next_batch().unwrap().len(), 20);
+        // Up to 10 batches in flight now.
+        assert_eq!(sender.next_batch().unwrap().len(), 20);
+        assert_eq!(sender.pending(), 100 - 9 - 40);
+
+        // A slow client gets a chunk every other tick.
+        sender.on_batch_received(ChunkBatchReceived {
+            chunks_per_tick: 0.5,
+        });
+        sender.on_batch_received(ChunkBatchReceived {
+            chunks_per_tick: 0.5,
+        });
+        let sizes: Vec<usize> = (0..4)
+            .map(|_| sender.next_batch().map_or(0, |batch| batch.len()))
+            .collect();
+        assert_eq!(sizes.iter().sum::<usize>(), 2);
+    }
+}
@@ -0,0 +1,133 @@
+//! Every reason the server disconnects a client for, and the message the client shows.
+use std::fmt;
+
+use super::packet::chat::TextComponent;
+use super::secure_profile::SecureProfileError;
+use crate::consts::minecraft::VERSION;
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    ServerFull,
+    NotWhitelisted,
+    /// The client has an older protocol version than the server.
+    OutdatedClient,
+    /// The client has a newer protocol version than the server.
+    OutdatedServer,
+    SecureProfile(SecureProfileError),
+    /// The maintenance mode is on, with its message.
+    Maintenance(String),
+    /// The server is in status-only mode, with its message.
+    StatusOnly(String),
+    /// A login plugin handler refused the client, with its message.
+    LoginPlugin(String),
+    /// The server failed to handle a packet, with the `bug-report-link` if any.
+    InternalError(Option<String>),
+    /// The client sent a packet that isn't valid in its state.
+    ProtocolError,
+    /// The client logged in, but the server can't configure it and let it play yet.
+    NotImplemented,
+}
+
+impl DisconnectReason {
+    /// The text component of the message.
+    pub fn to_component(&self) -> TextComponent {
+        TextComponent::text(self.to_string())
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ServerFull => write!(f, "{}", tr("disconnect.server_full", &[])),
+            Self::NotWhitelisted => write!(f, "{}", tr("disconnect.not_whitelisted", &[])),
+            Self::OutdatedClient => write!(f, "{}", tr("disconnect.outdated_client", &[VERSION])),
+            Self::OutdatedServer => write!(f, "{}", tr("disconnect.outdated_server", &[VERSION])),
+            Self::SecureProfile(e) => write!(f, "{e}"),
+            Self::Maintenance(message) | Self::StatusOnly(message) | Self::LoginPlugin(message) => {
+                write!(f, "{message}")
+            }
+            Self::ProtocolError => write!(f, "{}", tr("disconnect.protocol_error", &[])),
+            Self::NotImplemented => write!(f, "{}", tr("disconnect.not_implemented", &[])),
+            Self::InternalError(link) => {
+                write!(f, "{}", tr("disconnect.internal_error", &[]))?;
+                match link {
+                    Some(link) => write!(f, "\n{}", tr("bug_report.link", &[link])),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl From<SecureProfileError> for DisconnectReason {
+    fn from(e: SecureProfileError) -> Self {
+        Self::SecureProfile(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_components() {
+        let expected = [
+            (
+                DisconnectReason::ServerFull,
+                r#"{"text":"The server is full!"}"#,
+            ),
+            (
+                DisconnectReason::NotWhitelisted,
+                r#"{"text":"You are not white-listed on this server!"}"#,
+            ),
+            (
+                DisconnectReason::OutdatedClient,
+                r#"{"text":"Outdated client! Please use 1.21.4"}"#,
+            ),
+            (
+                DisconnectReason::OutdatedServer,
+                r#"{"text":"Outdated server! I'm still on 1.21.4"}"#,
+            ),
+            (
+                SecureProfileError::MissingPublicKey.into(),
+                r#"{"text":"Missing profile public key.\nThis server requires secure profiles."}"#,
+            ),
+            (
+                DisconnectReason::Maintenance("Back soon".to_string()),
+                r#"{"text":"Back soon"}"#,
+            ),
+            (
+                DisconnectReason::StatusOnly("Booting".to_string()),
+                r#"{"text":"Booting"}"#,
+            ),
+            (
+                DisconnectReason::ProtocolError,
+                r#"{"text":"Network Protocol Error"}"#,
+            ),
+            (
+                DisconnectReason::NotImplemented,
+                r#"{"text":"Joining the game isn't implemented yet."}"#,
+            ),
+        ];
+        for (reason, json) in expected {
+            assert_eq!(reason.to_component().to_string(), json);
+        }
+    }
+
+    #[test]
+    fn test_internal_error_link() {
+        let reason =
+            DisconnectReason::InternalError(Some("https://example.com/issues".to_string()));
+        assert_eq!(
+            reason.to_component().to_string(),
+            r#"{"text":"An internal server error occurred.\nPlease report it at https://example.com/issues"}"#
+        );
+
+        let reason = DisconnectReason::InternalError(None);
+        assert_eq!(
+            reason.to_component().to_string(),
+            r#"{"text":"An internal server error occurred."}"#
+        );
+    }
+}
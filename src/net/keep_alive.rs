@@ -0,0 +1,89 @@
+//! Keep alives, sent regularly to check that a player is still there.
+//!
+//! They're sent by a task of the connection, never by the tick loop: a slow tick doesn't make
+//! the clients time out.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::packet::packet_types::{EncodablePacket, KeepAlive};
+use super::{Connection, NetError};
+
+/// Vanilla's interval between two keep alives.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Sends a keep alive to `connection` every `interval`, until writing fails. Like vanilla, the
+/// IDs are the current time in milliseconds.
+// TODO: Spawn it for each connection once the Play state exists, and check the answers.
+pub(super) async fn run(connection: &Connection, interval: Duration) -> NetError {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick is immediate, nothing to check yet.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_millis() as i64);
+        let keep_alive = KeepAlive { id };
+        let result = match keep_alive.build() {
+            Ok(packet) => connection.write(&[packet]).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            return e;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::packet_types::{Handshake, NextState};
+    use crate::net::transport::FrameDecoder;
+    use crate::net::ByteCounter;
+    use crate::tick::TickScheduler;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[tokio::test]
+    async fn test_slow_tick_does_not_delay_keep_alives() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let handshake = Handshake {
+            protocol_version: 769,
+            server_address: "localhost".to_string(),
+            server_port: 25565,
+            next_state: NextState::Login,
+        };
+        let connection = Connection::new(
+            socket,
+            &handshake,
+            FrameDecoder::new(),
+            ByteCounter::default(),
+        );
+
+        // Every tick is stuck for 300ms.
+        let scheduler = TickScheduler::new(Arc::new(AtomicUsize::new(1)), None);
+        let ticks = scheduler.spawn(|| std::thread::sleep(Duration::from_millis(300)));
+        tokio::spawn(async move { run(&connection, Duration::from_millis(20)).await });
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            // Length, packet ID, then the ID of the keep alive.
+            let mut keep_alive = [0; 10];
+            let read = client.read_exact(&mut keep_alive);
+            tokio::time::timeout(Duration::from_millis(200), read)
+                .await
+                .expect("A keep alive should be sent while the world ticks")
+                .unwrap();
+            assert_eq!(keep_alive[..2], [9, 0x27]);
+        }
+        assert!(start.elapsed() < Duration::from_millis(300));
+
+        ticks.stop().unwrap();
+    }
+}
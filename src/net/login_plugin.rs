@@ -0,0 +1,185 @@
+//! Login plugin messages: custom exchanges on a channel during the Login state, before Login
+//! Success. Used by proxies and mods, e.g. to forward the player information.
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use super::packet::packet_types::{LoginPluginRequest, LoginPluginResponse};
+
+/// Handles the messages of a channel. A request is sent to every client logging in, and the
+/// login continues once the client answered.
+pub trait LoginPluginHandler: Debug + Send + Sync {
+    /// The channel of the messages, e.g. "velocity:player_info".
+    fn channel(&self) -> &str;
+
+    /// The data of the request sent to the client.
+    fn request_data(&self) -> Vec<u8>;
+
+    /// Handles the response of the client, `None` if it didn't understand the channel.
+    /// Returns the reason of the disconnection if the client must not log in.
+    fn handle_response(&self, data: Option<&[u8]>) -> Result<(), String>;
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LoginPluginError {
+    #[error("Got a Login Plugin Response to an unknown message: {0}")]
+    UnknownMessageId(i32),
+
+    #[error("Login plugin channel {channel} rejected the client: {reason}")]
+    Rejected { channel: String, reason: String },
+}
+
+static HANDLERS: Lazy<RwLock<Vec<Arc<dyn LoginPluginHandler>>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Registers a handler. The handlers are read when the server starts listening.
+pub fn register(handler: Arc<dyn LoginPluginHandler>) {
+    HANDLERS.write().unwrap().push(handler);
+}
+
+/// The registered handlers.
+pub fn handlers() -> Vec<Arc<dyn LoginPluginHandler>> {
+    HANDLERS.read().unwrap().clone()
+}
+
+/// The login plugin messages of a connection. The requests are sent one at a time, each after
+/// the response to the previous one.
+#[derive(Debug, Default)]
+pub struct LoginPluginExchange {
+    /// Handlers waiting for a response, by message ID.
+    pending: HashMap<i32, Arc<dyn LoginPluginHandler>>,
+    /// The requests not sent yet.
+    unsent: VecDeque<LoginPluginRequest>,
+}
+
+impl LoginPluginExchange {
+    /// Starts an exchange with `handlers`.
+    pub fn start(handlers: Vec<Arc<dyn LoginPluginHandler>>) -> Self {
+        let mut exchange = Self::default();
+        for (message_id, handler) in (0..).zip(handlers) {
+            exchange.unsent.push_back(LoginPluginRequest {
+                message_id,
+                channel: handler.channel().to_string(),
+                data: handler.request_data(),
+            });
+            exchange.pending.insert(message_id, handler);
+        }
+        exchange
+    }
+
+    /// The next request to send to the client, `None` once they were all sent.
+    pub fn next_request(&mut self) -> Option<LoginPluginRequest> {
+        self.unsent.pop_front()
+    }
+
+    /// Gives `response` to the handler of its request.
+    pub fn handle(&mut self, response: &LoginPluginResponse) -> Result<(), LoginPluginError> {
+        let handler = self
+            .pending
+            .remove(&response.message_id)
+            .ok_or(LoginPluginError::UnknownMessageId(response.message_id))?;
+
+        handler
+            .handle_response(response.data.as_deref())
+            .map_err(|reason| LoginPluginError::Rejected {
+                channel: handler.channel().to_string(),
+                reason,
+            })
+    }
+
+    /// Whether every request got its response, Login Success can then be sent.
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::packet_types::{DecodablePacket, EncodablePacket};
+    use std::sync::Mutex;
+
+    /// Expects the client to send back the request data reversed.
+    #[derive(Debug, Default)]
+    struct Reverse {
+        responses: Mutex<Vec<Option<Vec<u8>>>>,
+    }
+
+    impl LoginPluginHandler for Reverse {
+        fn channel(&self) -> &str {
+            "cactus:reverse"
+        }
+
+        fn request_data(&self) -> Vec<u8> {
+            vec![1, 2, 3]
+        }
+
+        fn handle_response(&self, data: Option<&[u8]>) -> Result<(), String> {
+            self.responses
+                .lock()
+                .unwrap()
+                .push(data.map(<[u8]>::to_vec));
+            match data {
+                Some([3, 2, 1]) => Ok(()),
+                _ => Err("Wrong answer".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_exchange_roundtrip() {
+        let handler = Arc::new(Reverse::default());
+        let mut exchange = LoginPluginExchange::start(vec![handler.clone()]);
+        let request = exchange.next_request().unwrap();
+        assert_eq!(exchange.next_request(), None);
+        assert!(!exchange.is_done());
+
+        // What the client receives
+        let packet = request.build().unwrap();
+        let request = LoginPluginRequest::parse(&packet).unwrap();
+        assert_eq!(request.channel, "cactus:reverse");
+
+        // What the server receives
+        let mut data = request.data.clone();
+        data.reverse();
+        let response = LoginPluginResponse {
+            message_id: request.message_id,
+            data: Some(data),
+        };
+        let packet = response.build().unwrap();
+        let response = LoginPluginResponse::parse(&packet).unwrap();
+
+        assert_eq!(exchange.handle(&response), Ok(()));
+        assert!(exchange.is_done());
+        assert_eq!(
+            *handler.responses.lock().unwrap(),
+            vec![Some(vec![3, 2, 1])]
+        );
+    }
+
+    #[test]
+    fn test_exchange_not_understood() {
+        let handler = Arc::new(Reverse::default());
+        let mut exchange = LoginPluginExchange::start(vec![handler.clone()]);
+
+        let request = exchange.next_request().unwrap();
+        let response = LoginPluginResponse::not_understood(request.message_id);
+        assert_eq!(
+            exchange.handle(&response),
+            Err(LoginPluginError::Rejected {
+                channel: "cactus:reverse".to_string(),
+                reason: "Wrong answer".to_string()
+            })
+        );
+        assert_eq!(*handler.responses.lock().unwrap(), vec![None]);
+
+        // Already answered
+        assert_eq!(
+            exchange.handle(&response),
+            Err(LoginPluginError::UnknownMessageId(0))
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Maintenance mode: new logins are refused with a message, while the status keeps answering.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+/// The login refusal message when none is given.
+pub const DEFAULT_MESSAGE: &str = "The server is under maintenance, come back later!";
+
+static MAINTENANCE: Lazy<Arc<RwLock<Maintenance>>> =
+    Lazy::new(|| Arc::new(RwLock::new(Maintenance::from_settings(&Settings::new()))));
+
+/// Returns the maintenance mode of the server.
+pub fn global() -> Arc<RwLock<Maintenance>> {
+    MAINTENANCE.clone()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Maintenance {
+    /// The message of the refused logins, `None` if the maintenance is off.
+    message: Option<String>,
+    /// Replaces the MOTD in the status during the maintenance.
+    motd: Option<String>,
+}
+
+impl Maintenance {
+    pub fn new(motd: Option<String>) -> Self {
+        Self {
+            message: None,
+            motd,
+        }
+    }
+
+    /// Reads the non-standard `maintenance-motd` property.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::new(settings.extra("maintenance-motd").map(str::to_string))
+    }
+
+    /// Starts the maintenance. Logins are refused with `message`, or `DEFAULT_MESSAGE`.
+    pub fn enable(&mut self, message: Option<String>) {
+        self.message = Some(message.unwrap_or_else(|| DEFAULT_MESSAGE.to_string()));
+    }
+
+    pub fn disable(&mut self) {
+        self.message = None;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.message.is_some()
+    }
+
+    /// The message refusing a login, if the maintenance is on.
+    pub fn login_refusal(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The MOTD to show instead of the normal one, if the maintenance is on.
+    pub fn motd(&self) -> Option<&str> {
+        self.message.as_ref().and(self.motd.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enable_disable() {
+        let mut maintenance = Maintenance::new(Some("Back soon".to_string()));
+        assert!(!maintenance.is_enabled());
+        assert_eq!(maintenance.login_refusal(), None);
+        assert_eq!(maintenance.motd(), None);
+
+        maintenance.enable(None);
+        assert_eq!(maintenance.login_refusal(), Some(DEFAULT_MESSAGE));
+        assert_eq!(maintenance.motd(), Some("Back soon"));
+
+        maintenance.enable(Some("Updating".to_string()));
+        assert_eq!(maintenance.login_refusal(), Some("Updating"));
+
+        maintenance.disable();
+        assert!(!maintenance.is_enabled());
+        assert_eq!(maintenance.motd(), None);
+    }
+}
@@ -1,21 +1,48 @@
 //! This module manages the TCP server and how/where the packets are managed/sent.
+pub mod chunk_batch;
+pub mod disconnect;
+pub mod keep_alive;
+pub mod login_plugin;
+pub mod maintenance;
 pub mod packet;
+pub mod secure_profile;
 pub mod slp;
-use crate::config;
+pub mod state_validator;
+pub mod status_only;
+pub mod timeout;
+pub mod transport;
+pub mod virtual_host;
+use crate::player::uuid::UuidResolver;
+use crate::region_parser::limits::NbtLimits;
+use crate::{config, consts, metrics, player};
 use bytes::BytesMut;
+use disconnect::DisconnectReason;
 use log::{debug, error, info, warn};
+use login_plugin::{LoginPluginError, LoginPluginExchange, LoginPluginHandler};
+use maintenance::Maintenance;
+use once_cell::sync::Lazy;
+use packet::packet_types::{
+    ConfigurationDisconnect, DecodablePacket, EncodablePacket, Handshake, LoginAcknowledged,
+    LoginDisconnect, LoginPluginResponse, LoginStart, LoginSuccess, NextState, SetCompression,
+};
 use packet::{Packet, PacketError, Response};
 use std::io;
+use std::net::{AddrParseError, IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use timeout::HandlerTimeout;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, oneshot, Mutex, OwnedSemaphorePermit, Semaphore};
+use transport::{FrameDecoder, FrameEncoder, TransportError};
+use virtual_host::VirtualHostRouter;
 
-/// Listening address
-/// TODO: Change this. Use config files.
-const ADDRESS: &str = "0.0.0.0";
+/// The address listened on when `server-ip` is empty: every interface.
+const ANY_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
 
 #[derive(Error, Debug)]
 pub enum NetError {
@@ -31,36 +58,314 @@ pub enum NetError {
     #[error("Failed to parse packet: {0}")]
     Parsing(#[from] PacketError),
 
+    #[error("Failed to decode the received bytes: {0}")]
+    Transport(#[from] TransportError),
+
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
 
     #[error("Unknown packet id: {0}")]
     UnknownPacketId(String),
+
+    #[error("Handler of packet {0:#04X} (state {1:?}) timed out after {2:?}")]
+    HandlerTimeout(i32, ConnectionState, Duration),
+
+    #[error("Timed out after {0:?} without receiving a packet")]
+    ReadTimeout(Duration),
+
+    #[error("Packet {0:#04X} can't be sent in state {1:?}")]
+    UnexpectedPacket(i32, ConnectionState),
+
+    #[error("Invalid server-ip {0:?}: {1}. Use an IP address, or leave it empty to listen on every interface")]
+    InvalidServerIp(String, AddrParseError),
+
+    #[error("Failed to bind to {0}: {1}")]
+    Bind(String, io::Error),
+}
+
+impl NetError {
+    /// Classifies an error writing to the socket: the peer going away is a `ConnectionClosed`,
+    /// anything else a `Writing`.
+    fn from_write(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected => Self::ConnectionClosed(format!("{e} while writing")),
+            _ => Self::Writing(e.to_string()),
+        }
+    }
+
+    /// Whether the error only means that the client left or stopped answering, a normal end of
+    /// connection.
+    pub fn is_disconnect(&self) -> bool {
+        matches!(self, Self::ConnectionClosed(_) | Self::ReadTimeout(_))
+    }
+
+    /// Whether the error is the server's fault rather than the client's, a bug to report.
+    pub fn is_internal(&self) -> bool {
+        matches!(self, Self::Io(_) | Self::HandlerTimeout(..))
+    }
+}
+
+/// How many broadcast packets can wait for a slow connection before it starts missing them.
+const BROADCAST_CAPACITY: usize = 256;
+
+/// Packets that must be sent to every player.
+static BROADCAST: Lazy<broadcast::Sender<Arc<Packet>>> =
+    Lazy::new(|| broadcast::channel(BROADCAST_CAPACITY).0);
+
+/// Sends `packet` to every connected player. Returns how many players it was sent to.
+pub fn broadcast(packet: Packet) -> usize {
+    // Sending fails when there are no subscribers, which just means nobody is online.
+    BROADCAST.send(Arc::new(packet)).unwrap_or(0)
+}
+
+/// Subscribes to the packets sent with `broadcast`. Each player connection has to subscribe.
+pub fn subscribe_broadcast() -> broadcast::Receiver<Arc<Packet>> {
+    BROADCAST.subscribe()
+}
+
+/// Default of the non-standard `max-pending-connections` property.
+const DEFAULT_MAX_PENDING_CONNECTIONS: usize = 256;
+
+/// Default of the non-standard `accept-backoff-ms` property.
+const DEFAULT_ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How long a client can stay silent before it's logged in, like vanilla.
+const LOGIN_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the listener handles the connections.
+#[derive(Debug, Clone, Default)]
+struct ListenOptions {
+    /// Time limit of each packet handler.
+    timeout: Option<HandlerTimeout>,
+    /// How many connections can be in the handshake, status or login states at the same time.
+    /// No limit if `None`.
+    max_pending_connections: Option<usize>,
+    /// How long to wait for each packet before the login, so that the stalled connections give
+    /// back their pending slot. No limit if `None`.
+    login_read_timeout: Option<Duration>,
+    virtual_hosts: Arc<VirtualHostRouter>,
+    maintenance: Arc<std::sync::RwLock<Maintenance>>,
+    /// The message refusing every login in status-only mode, `None` otherwise.
+    status_only: Option<String>,
+    /// How long to wait before accepting again after an accept error.
+    accept_backoff: Duration,
+    /// Given to the clients disconnected by an internal error.
+    bug_report_link: Option<String>,
+    /// Whether the addresses of the clients can be logged.
+    log_ips: bool,
+    /// The packets with at least this many bytes are compressed once logged in. `None` disables
+    /// the compression.
+    compression_threshold: Option<usize>,
+    /// How the UUIDs of the players logging in are found, from `online-mode`.
+    uuid_resolver: UuidResolver,
+    /// Answered to the legacy pings, which bypass the status cache.
+    motd: Option<String>,
+    max_players: u32,
+    /// Asked before Login Success, see `login_plugin`.
+    login_plugins: Vec<Arc<dyn LoginPluginHandler>>,
+    /// Where the data of the players logging in is loaded from. Not loaded if `None`.
+    player_data: Option<PathBuf>,
+    /// Enforced on the player data.
+    nbt_limits: NbtLimits,
+}
+
+impl ListenOptions {
+    /// Reads the options from the settings and the virtual hosts file.
+    /// `max-pending-connections` is non-standard, and 0 means no limit. So is
+    /// `accept-backoff-ms`.
+    fn from_settings(settings: &config::Settings) -> Self {
+        let path = Path::new(consts::file_paths::VIRTUAL_HOSTS);
+        let virtual_hosts = VirtualHostRouter::load(path).unwrap_or_else(|e| {
+            error!(
+                "Failed to read {}, ignoring virtual hosts: {e}",
+                path.display()
+            );
+            VirtualHostRouter::default()
+        });
+
+        let max_pending_connections = settings
+            .extra("max-pending-connections")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_PENDING_CONNECTIONS);
+        let accept_backoff = settings
+            .extra("accept-backoff-ms")
+            .and_then(|value| value.parse::<u64>().ok())
+            .map_or(DEFAULT_ACCEPT_BACKOFF, Duration::from_millis);
+
+        Self {
+            timeout: HandlerTimeout::from_settings(settings),
+            max_pending_connections: Some(max_pending_connections).filter(|&max| max > 0),
+            login_read_timeout: Some(LOGIN_READ_TIMEOUT),
+            virtual_hosts: Arc::new(virtual_hosts),
+            maintenance: maintenance::global(),
+            status_only: status_only::login_refusal(),
+            accept_backoff,
+            bug_report_link: settings.bug_report_link.clone(),
+            log_ips: settings.log_ips,
+            compression_threshold: usize::try_from(settings.network_compression_threshold).ok(),
+            uuid_resolver: UuidResolver::from_settings(settings),
+            motd: settings.motd.clone(),
+            max_players: settings.max_players,
+            login_plugins: login_plugin::handlers(),
+            player_data: Some(PathBuf::from(consts::directory_paths::PLAYER_DATA)),
+            nbt_limits: NbtLimits::from_settings(settings),
+        }
+    }
+
+    /// The MOTD replacing the one of the settings for a client that connected to
+    /// `server_address`: the maintenance one, or the one of the virtual host.
+    fn status_motd(&self, server_address: &str) -> Option<String> {
+        if let Some(motd) = self.maintenance.read().unwrap().motd() {
+            return Some(motd.to_string());
+        }
+        self.virtual_hosts
+            .route(server_address)
+            .and_then(|host| host.motd.clone())
+    }
 }
 
 /// Listens for every incoming TCP connection.
-pub async fn listen() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// `ready` receives the bound address once the server accepts connections. It is dropped without
+/// a value if binding fails.
+pub async fn listen(ready: oneshot::Sender<SocketAddr>) -> Result<(), Box<dyn std::error::Error>> {
     let config = config::Settings::new();
-    let server_address = format!("{}:{}", ADDRESS, config.server_port);
-    let listener = TcpListener::bind(server_address).await?;
+    let server_address = bind_address(&config)?;
+    let options = ListenOptions::from_settings(&config);
+    listen_on(&server_address.to_string(), ready, options).await
+}
+
+/// The address to listen on: `server-ip`, every interface if it's empty, and `server-port`.
+pub fn bind_address(settings: &config::Settings) -> Result<SocketAddr, NetError> {
+    let ip = match settings.server_ip.as_deref() {
+        Some(ip) => ip
+            .parse::<IpAddr>()
+            .map_err(|e| NetError::InvalidServerIp(ip.to_string(), e))?,
+        None => ANY_ADDRESS,
+    };
+    Ok(SocketAddr::new(ip, settings.server_port))
+}
+
+async fn listen_on(
+    address: &str,
+    ready: oneshot::Sender<SocketAddr>,
+    options: ListenOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| NetError::Bind(address.to_string(), e))?;
+    // Nobody waiting for the signal is not an error.
+    let _ = ready.send(listener.local_addr()?);
+
+    let pending = options
+        .max_pending_connections
+        .map(|max| Arc::new(Semaphore::new(max)));
 
     loop {
-        let (socket, addr) = listener.accept().await?;
+        let (socket, addr) = accept_with_backoff(&listener, options.accept_backoff).await?;
+        let addr = client_address(addr, options.log_ips);
+
+        let permit = match &pending {
+            Some(pending) => match pending.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    warn!("Too many pending connections, rejecting the connection from {addr}");
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let options = options.clone();
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(socket).await {
+            if let Err(e) = handle_connection(socket, &options, permit).await {
                 warn!("Error handling connection from {addr}: {e}");
             }
         });
     }
 }
 
+/// The address of a client as logged: withheld unless `log_ips`, like vanilla.
+fn client_address(addr: SocketAddr, log_ips: bool) -> String {
+    if log_ips {
+        addr.to_string()
+    } else {
+        "<ip address withheld>".to_string()
+    }
+}
+
+/// Where the connections come from: a `TcpListener`, or a mock in the tests.
+trait Acceptor {
+    type Stream;
+
+    async fn accept(&self) -> io::Result<(Self::Stream, SocketAddr)>;
+}
+
+impl Acceptor for TcpListener {
+    type Stream = TcpStream;
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        TcpListener::accept(self).await
+    }
+}
+
+/// Accepts the next connection. An error, e.g. running out of file descriptors, usually lasts
+/// until some connections close: it is logged and accepting is retried after `backoff`. Only the
+/// errors meaning the listener itself is unusable are returned.
+async fn accept_with_backoff<A: Acceptor>(
+    acceptor: &A,
+    backoff: Duration,
+) -> io::Result<(A::Stream, SocketAddr)> {
+    loop {
+        match acceptor.accept().await {
+            Ok(accepted) => return Ok(accepted),
+            Err(e) if matches!(e.kind(), io::ErrorKind::InvalidInput) => return Err(e),
+            Err(e) => {
+                error!("Failed to accept a connection, retrying in {backoff:?}: {e}");
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+}
+
 /// State of each connection. (e.g.: handshake, play, ...)
-#[derive(Debug, Clone, Copy)]
-enum ConnectionState {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
     Handshake,
     Status,
     Login,
     Transfer,
+    /// After the login, until the client has the registries and enters the game.
+    Configuration,
+}
+
+impl ConnectionState {
+    pub const COUNT: usize = 5;
+    pub const ALL: [Self; Self::COUNT] = [
+        Self::Handshake,
+        Self::Status,
+        Self::Login,
+        Self::Transfer,
+        Self::Configuration,
+    ];
+
+    /// Position of the state in `ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<NextState> for ConnectionState {
+    fn from(next_state: NextState) -> Self {
+        match next_state {
+            NextState::Status => Self::Status,
+            NextState::Login => Self::Login,
+            NextState::Transfer => Self::Transfer,
+        }
+    }
 }
 
 impl Default for ConnectionState {
@@ -69,17 +374,85 @@ impl Default for ConnectionState {
     }
 }
 
+/// The bytes read and written on a connection, as sent on the wire. They are added to the global
+/// metrics too.
+#[derive(Debug, Default)]
+struct ByteCounter {
+    read: AtomicU64,
+    written: AtomicU64,
+}
+
+impl ByteCounter {
+    fn add_read(&self, count: usize) {
+        self.read.fetch_add(count as u64, Ordering::Relaxed);
+        metrics::global().bytes_received(count);
+    }
+
+    fn add_written(&self, count: usize) {
+        self.written.fetch_add(count as u64, Ordering::Relaxed);
+        metrics::global().bytes_sent(count);
+    }
+
+    fn get_read(&self) -> u64 {
+        self.read.load(Ordering::Relaxed)
+    }
+
+    fn get_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+}
+
 /// Object representing a TCP connection.
 struct Connection {
     state: Arc<Mutex<ConnectionState>>,
+    /// The protocol version of the client, from its handshake.
+    protocol_version: i32,
+    /// The address and port the client used to connect, from its handshake.
+    server_address: String,
+    server_port: u16,
     socket: Arc<Mutex<TcpStream>>,
+    decoder: Mutex<FrameDecoder>,
+    encoder: Mutex<FrameEncoder>,
+    bytes: ByteCounter,
+    /// Whether Login Start was answered with Login Success, which Login Acknowledged must follow.
+    login_success_sent: AtomicBool,
+    /// The slot of the connection among the pending ones, see `handle_connection`.
+    pending: std::sync::Mutex<Option<OwnedSemaphorePermit>>,
+    /// Whether the player is counted in `player::online_players`, until the connection is dropped.
+    online: AtomicBool,
+    /// The login waiting for the responses to its login plugin requests, if any.
+    plugin_login: std::sync::Mutex<Option<PluginLogin>>,
+}
+
+/// A login waiting for the responses to its login plugin requests.
+struct PluginLogin {
+    exchange: LoginPluginExchange,
+    /// Sent once every request got its response.
+    login_success: Packet,
 }
 
 impl Connection {
-    fn new(socket: TcpStream) -> Self {
+    /// Creates the connection of a client that sent `handshake`. `decoder` holds the bytes
+    /// received after the handshake, and `bytes` already counts what was read before.
+    fn new(
+        socket: TcpStream,
+        handshake: &Handshake,
+        decoder: FrameDecoder,
+        bytes: ByteCounter,
+    ) -> Self {
         Self {
             state: Arc::new(Mutex::new(ConnectionState::default())),
+            protocol_version: handshake.protocol_version,
+            server_address: handshake.server_address.clone(),
+            server_port: handshake.server_port,
             socket: Arc::new(Mutex::new(socket)),
+            decoder: Mutex::new(decoder),
+            encoder: Mutex::new(FrameEncoder::new()),
+            bytes,
+            login_success_sent: AtomicBool::new(false),
+            pending: std::sync::Mutex::new(None),
+            online: AtomicBool::new(false),
+            plugin_login: std::sync::Mutex::new(None),
         }
     }
 
@@ -90,74 +463,451 @@ impl Connection {
 
     /// Change the state of the current Connection.
     async fn set_state(&self, new_state: ConnectionState) {
-        *self.state.lock().await = new_state
+        let mut state = self.state.lock().await;
+        metrics::global().state_changed(*state, new_state);
+        *state = new_state;
+
+        // Logged in, the connection isn't pending anymore and the player is online.
+        if new_state == ConnectionState::Configuration {
+            self.pending.lock().unwrap().take();
+            if !self.online.swap(true, Ordering::Relaxed) {
+                player::online_players().fetch_add(1, Ordering::Relaxed);
+            }
+        }
     }
 
-    /// Writes either a &[u8] to the socket.
-    ///
-    /// This function can take in `Packet`.
-    async fn write<T: AsRef<[u8]>>(&self, data: T) -> Result<(), NetError> {
-        let mut socket = self.socket.lock().await;
-        Ok(socket.write_all(data.as_ref()).await?)
+    /// Keeps `pending` until the connection is logged in.
+    fn hold_pending(&self, pending: Option<OwnedSemaphorePermit>) {
+        *self.pending.lock().unwrap() = pending;
+    }
+
+    /// Writes `packets` to the socket in a single write, compressed and encrypted as negotiated.
+    async fn write(&self, packets: &[Packet]) -> Result<(), NetError> {
+        let mut encoder = self.encoder.lock().await;
+        let mut data = Vec::new();
+        for packet in packets {
+            data.extend(encoder.encode(packet)?);
+        }
+        write_bytes(&mut *self.socket.lock().await, &data, &self.bytes).await
+    }
+
+    /// Waits for a packet, then returns it along with the ones received at the same time.
+    async fn read(&self) -> Result<Vec<Packet>, NetError> {
+        let mut decoder = self.decoder.lock().await;
+        let packet = read_packet(&mut *self.socket.lock().await, &mut decoder, &self.bytes).await?;
+
+        let mut packets = vec![packet];
+        while let Some(packet) = decoder.decode()? {
+            packets.push(packet);
+        }
+        Ok(packets)
+    }
+
+    /// Sends Set Compression, then switches both directions to the compressed format.
+    async fn enable_compression(&self, threshold: usize) -> Result<(), NetError> {
+        let set_compression = SetCompression {
+            threshold: threshold as i32,
+        }
+        .build()?;
+        // Locked until the switch, so that no packet goes between.
+        let mut encoder = self.encoder.lock().await;
+        let data = encoder.encode(&set_compression)?;
+        write_bytes(&mut *self.socket.lock().await, &data, &self.bytes).await?;
+        encoder.set_compression(Some(threshold));
+        self.decoder.lock().await.set_compression(Some(threshold));
+        Ok(())
+    }
+
+    /// The threshold of the compressed format, `None` if the packets aren't compressed.
+    async fn compression_threshold(&self) -> Option<usize> {
+        self.encoder.lock().await.compression_threshold()
+    }
+
+    /// Forces the compression threshold of both directions, whatever
+    /// `network-compression-threshold`: `Some(0)` compresses every packet, `None` disables the
+    /// compression.
+    #[cfg(test)]
+    async fn force_compression(&self, threshold: Option<usize>) {
+        self.encoder.lock().await.set_compression(threshold);
+        self.decoder.lock().await.set_compression(threshold);
     }
 
-    async fn read(&self) -> Result<Packet, NetError> {
-        let mut buffer = BytesMut::with_capacity(512);
+    /// Tries to close the connection with the Minecraft client
+    async fn close(&self) -> Result<(), NetError> {
         let mut socket = self.socket.lock().await;
+        socket.shutdown().await.map_err(NetError::from_write)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if *self.online.get_mut() {
+            player::online_players().fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns the next packet of `decoder`, reading `socket` until it is whole. Counts the bytes
+/// read in `bytes`.
+async fn read_packet(
+    socket: &mut TcpStream,
+    decoder: &mut FrameDecoder,
+    bytes: &ByteCounter,
+) -> Result<Packet, NetError> {
+    let mut buffer = BytesMut::with_capacity(512);
+    loop {
+        if let Some(packet) = decoder.decode()? {
+            return Ok(packet);
+        }
+
+        // Once the length is known, the rest of the frame is read at once.
+        if let Some(missing) = decoder.missing().filter(|&missing| missing > 0) {
+            let rest = read_exact_into(socket, missing).await?;
+            bytes.add_read(rest.len());
+            decoder.extend(&rest);
+            continue;
+        }
 
+        buffer.clear();
         let read: usize = socket.read_buf(&mut buffer).await?;
 
         if read == 0 {
             info!("Connection closed gracefully with (read 0 bytes)");
             return Err(NetError::ConnectionClosed("read 0 bytes".to_string()));
         }
+        bytes.add_read(read);
+        decoder.extend(&buffer);
+    }
+}
 
-        Ok(Packet::new(&buffer)?)
+/// Waits for `read`, for at most `timeout` if any.
+async fn read_within<T>(
+    timeout: Option<Duration>,
+    read: impl std::future::Future<Output = Result<T, NetError>>,
+) -> Result<T, NetError> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, read)
+            .await
+            .map_err(|_| NetError::ReadTimeout(timeout))?,
+        None => read.await,
     }
+}
 
-    /// Tries to close the connection with the Minecraft client
-    async fn close(&self) -> Result<(), std::io::Error> {
-        self.socket.lock().await.shutdown().await
+/// Reads exactly `n` bytes from `reader`, whatever the number of reads they arrive in.
+/// Fails with `NetError::ConnectionClosed` if the peer closes the connection before.
+async fn read_exact_into<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    n: usize,
+) -> Result<BytesMut, NetError> {
+    let mut buffer = BytesMut::with_capacity(n);
+    while buffer.len() < n {
+        // `take` so that the bytes after the `n` stay in the socket.
+        let remaining = (n - buffer.len()) as u64;
+        if (&mut *reader).take(remaining).read_buf(&mut buffer).await? == 0 {
+            return Err(NetError::ConnectionClosed(format!(
+                "read {} of {n} bytes",
+                buffer.len()
+            )));
+        }
     }
+    Ok(buffer)
+}
+
+/// Writes `data` to `socket`, counting the bytes written in `bytes`.
+async fn write_bytes<W: AsyncWrite + Unpin>(
+    socket: &mut W,
+    data: &[u8],
+    bytes: &ByteCounter,
+) -> Result<(), NetError> {
+    socket.write_all(data).await.map_err(NetError::from_write)?;
+    bytes.add_written(data.len());
+    Ok(())
 }
 
-/// Handles each connection. Receives every packet.
-async fn handle_connection(socket: TcpStream) -> Result<(), NetError> {
+/// Handles each connection. Receives every packet, each handled within the timeout of `options`.
+///
+/// Connections that only ask for the status (server list ping) are answered directly from the
+/// socket, without creating a `Connection`. The virtual host is selected from the handshake.
+///
+/// `pending` is the slot of the connection among the pending ones, it is released when the
+/// connection is logged in or closes.
+async fn handle_connection(
+    mut socket: TcpStream,
+    options: &ListenOptions,
+    mut pending: Option<OwnedSemaphorePermit>,
+) -> Result<(), NetError> {
     debug!("Handling new connection: {socket:?}");
+    metrics::global().connection_opened();
+    let bytes = ByteCounter::default();
+    let mut decoder = FrameDecoder::new();
+
+    let read_timeout = options.login_read_timeout;
+    let legacy_ping = read_within(read_timeout, async {
+        Ok(slp::legacy::peek_legacy_ping(&socket).await)
+    });
+    let legacy_ping = match legacy_ping.await {
+        Ok(false) => None,
+        Ok(true) => Some(serve_legacy_ping(&mut socket, options, &bytes).await),
+        // Silent until the timeout.
+        Err(e) => Some(Err(e)),
+    };
+    if let Some(result) = legacy_ping {
+        metrics::global().connection_closed(ConnectionState::Handshake);
+        drop(pending);
+        return ignore_disconnect(result);
+    }
 
-    let connection = Connection::new(socket);
+    let handshake = read_handshake(&mut socket, &mut decoder, &bytes, options.log_ips);
+    let handshake = read_within(read_timeout, handshake).await;
+    let (state, result, bytes) = match handshake {
+        Err(e) => (ConnectionState::Handshake, Err(e), bytes),
+        Ok(handshake) if handshake.next_state == NextState::Status => {
+            metrics::global().state_changed(ConnectionState::Handshake, ConnectionState::Status);
+            let motd = options.status_motd(&handshake.server_address);
+            let motd = motd.as_deref();
+            let result = serve_status(&mut socket, &mut decoder, options, motd, &bytes).await;
+            (ConnectionState::Status, result, bytes)
+        }
+        Ok(handshake) if options.status_only.is_some() => {
+            let state = handshake.next_state.into();
+            metrics::global().state_changed(ConnectionState::Handshake, state);
+            let message = options.status_only.clone().unwrap_or_default();
+            let reason = DisconnectReason::StatusOnly(message);
+            let result = refuse_status_only(&mut socket, reason, &bytes).await;
+            (state, result, bytes)
+        }
+        Ok(handshake) => {
+            let mut connection = Connection::new(socket, &handshake, decoder, bytes);
+            connection.hold_pending(pending.take());
+            connection.set_state(handshake.next_state.into()).await;
 
+            let result = handle_packets(&connection, options).await;
+            let bytes = std::mem::take(&mut connection.bytes);
+            (connection.get_state().await, result, bytes)
+        }
+    };
+    debug!(
+        "Connection closed after reading {} bytes and writing {} bytes",
+        bytes.get_read(),
+        bytes.get_written()
+    );
+    metrics::global().connection_closed(state);
+    drop(pending);
+    ignore_disconnect(result)
+}
+
+/// The outcome of a connection: the client leaving, even mid-response, isn't an error.
+fn ignore_disconnect(result: Result<(), NetError>) -> Result<(), NetError> {
+    match result {
+        Err(e) if e.is_disconnect() => {
+            debug!("Client disconnected: {e}");
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Reads the first packet of a connection, which must be a handshake.
+/// The packets following it stay in `decoder`. The address the client used is logged if
+/// `log_ips`.
+async fn read_handshake(
+    socket: &mut TcpStream,
+    decoder: &mut FrameDecoder,
+    bytes: &ByteCounter,
+    log_ips: bool,
+) -> Result<Handshake, NetError> {
+    let packet = read_packet(socket, decoder, bytes).await?;
+    let handshake = Handshake::parse(&packet)?;
+
+    let summary = handshake.summary(log_ips);
+    debug!("Handshake: {summary}");
+    metrics::global().packet_handled();
+    metrics::global().handshake_received(summary.protocol_version);
+    Ok(handshake)
+}
+
+/// Answers the Status Request and the Ping Request of a status-only connection, then closes it.
+/// `motd` replaces the one of the settings, e.g. for a virtual host.
+async fn serve_status(
+    socket: &mut TcpStream,
+    decoder: &mut FrameDecoder,
+    options: &ListenOptions,
+    motd: Option<&str>,
+    bytes: &ByteCounter,
+) -> Result<(), NetError> {
+    let timeout = options.timeout;
+    // Never compressed nor encrypted, the status comes before the login.
+    let mut encoder = FrameEncoder::new();
     loop {
-        // Read the socket and wait for a packet
-        let packet: Packet = connection.read().await?;
+        let read = read_packet(socket, decoder, bytes);
+        let packet: Packet = read_within(options.login_read_timeout, read).await?;
+        debug!("{packet:?} / Conn. state: {:?}", ConnectionState::Status);
+
+        let packet_id = packet.get_id().get_value();
+        if packet_id == 0x00 {
+            // Status Request: the cached bytes are written as is.
+            write_bytes(socket, &slp::status_response_bytes(motd), bytes).await?;
+            metrics::global().packet_handled();
+            metrics::global().status_request_answered();
+            continue;
+        }
+        let handler = dispatch::status(packet, motd);
+        let response =
+            timeout::run_handler(timeout, packet_id, ConnectionState::Status, handler).await?;
+        metrics::global().packet_handled();
+        let Some(response) = response else {
+            continue;
+        };
+
+        if let Some(data) = encoder.encode_response(&response)? {
+            write_bytes(socket, &data, bytes).await?;
+        }
+        if response.does_close_conn() {
+            socket.shutdown().await?;
+            return Ok(());
+        }
+    }
+}
+
+/// Answers the legacy ping of a pre-1.7 client or a monitor, then closes the connection. What it
+/// sent isn't read, the status being the same whatever the version of the ping.
+async fn serve_legacy_ping(
+    socket: &mut TcpStream,
+    options: &ListenOptions,
+    bytes: &ByteCounter,
+) -> Result<(), NetError> {
+    debug!("Legacy server list ping");
+    let maintenance_motd = options
+        .maintenance
+        .read()
+        .unwrap()
+        .motd()
+        .map(str::to_string);
+    let motd = maintenance_motd.or_else(|| options.motd.clone());
+    let status =
+        slp::legacy::LegacyStatus::new(motd.as_deref().unwrap_or_default(), options.max_players);
+    write_bytes(socket, &status.encode(), bytes).await?;
+    metrics::global().packet_handled();
+    socket.shutdown().await.map_err(NetError::from_write)
+}
 
-        let response: Response = handle_packet(&connection, packet).await?;
+/// Disconnects a client trying to log in to a status-only server with `reason`, without waiting
+/// for its Login Start.
+async fn refuse_status_only(
+    socket: &mut TcpStream,
+    reason: DisconnectReason,
+    bytes: &ByteCounter,
+) -> Result<(), NetError> {
+    let disconnect = LoginDisconnect {
+        reason: reason.to_component(),
+    }
+    .build()?;
+    write_bytes(socket, disconnect.get_full_packet(), bytes).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+/// Reads and answers the packets of a connection until it closes.
+///
+/// A client can send several packets at once, e.g. the handshake and the Status Request. They
+/// are handled in order and their responses are written together.
+async fn handle_packets(connection: &Connection, options: &ListenOptions) -> Result<(), NetError> {
+    loop {
+        // Read the socket and wait for packets. Only the logged in clients can stay silent.
+        let state = connection.get_state().await;
+        let read_timeout = options
+            .login_read_timeout
+            .filter(|_| state != ConnectionState::Configuration);
+        let packets = read_within(read_timeout, connection.read()).await?;
 
-        if let Some(packet) = response.get_packet() {
-            // TODO: Make sure that sent packets are big endians (data types).
-            connection.write(packet).await?;
+        let mut responses = Vec::new();
+        let mut close = false;
+        for packet in packets {
+            let packet_id = packet.get_id().get_value();
+            let state = connection.get_state().await;
+            if let Err(e) = state_validator::validate(state, packet_id) {
+                warn!("{e}, closing the connection");
+                if matches!(state, ConnectionState::Login | ConnectionState::Transfer) {
+                    disconnect_on_error(connection, DisconnectReason::ProtocolError).await;
+                }
+                return Err(e);
+            }
+            let handler = handle_packet(connection, packet, options);
+            let response = match timeout::run_handler(options.timeout, packet_id, state, handler)
+                .await
+            {
+                Err(e)
+                    if e.is_internal()
+                        && matches!(state, ConnectionState::Login | ConnectionState::Transfer) =>
+                {
+                    let reason = DisconnectReason::InternalError(options.bug_report_link.clone());
+                    disconnect_on_error(connection, reason).await;
+                    return Err(e);
+                }
+                response => response?,
+            };
+            metrics::global().packet_handled();
+            let Some(response) = response else {
+                continue;
+            };
 
-            if response.does_close_conn() {
-                warn!("Sent a packet that will close the connection");
-                connection.close().await?;
+            close = response.does_close_conn();
+            if let Some(packet) = response.take_packet() {
+                // TODO: Make sure that sent packets are big endians (data types).
+                responses.push(packet);
+            } else {
+                // Temp warn
+                warn!("Got response None. Not sending any packet to the MC client");
+                close = false;
+            }
+            if close {
+                // The packets after it are not answered.
+                break;
             }
-        } else {
-            // Temp warn
-            warn!("Got response None. Not sending any packet to the MC client");
         }
+
+        if !responses.is_empty() {
+            connection.write(&responses).await?;
+        }
+        if close {
+            warn!("Sent a packet that will close the connection");
+            connection.close().await?;
+        }
+    }
+}
+
+/// Tells a client logging in why the connection is about to close. The connection being already
+/// in error, failing to do so is only logged.
+async fn disconnect_on_error(connection: &Connection, reason: DisconnectReason) {
+    let disconnect = LoginDisconnect {
+        reason: reason.to_component(),
+    };
+    let result = match disconnect.build() {
+        Ok(packet) => connection.write(&[packet]).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = result {
+        debug!("Failed to send the disconnect reason: {e}");
     }
 }
 
 /// This function returns an appropriate response given the input `buffer` packet data.
-async fn handle_packet(conn: &Connection, packet: Packet) -> Result<Response, NetError> {
+async fn handle_packet(
+    conn: &Connection,
+    packet: Packet,
+    options: &ListenOptions,
+) -> Result<Response, NetError> {
     debug!("{packet:?} / Conn. state: {:?}", conn.get_state().await);
 
     // Dispatch packet depending on the current State.
     match conn.get_state().await {
-        ConnectionState::Handshake => dispatch::handshake(conn).await,
-        ConnectionState::Status => dispatch::status(packet).await,
-        ConnectionState::Login => dispatch::login(conn, packet).await,
-        ConnectionState::Transfer => dispatch::transfer(conn, packet).await,
+        ConnectionState::Handshake => dispatch::handshake(conn, packet).await,
+        ConnectionState::Status => dispatch::status(packet, None).await,
+        // A transfer is a login, from another server.
+        ConnectionState::Login | ConnectionState::Transfer => {
+            dispatch::login(conn, packet, options).await
+        }
+        ConnectionState::Configuration => dispatch::configuration(conn, packet).await,
     }
 }
 
@@ -165,18 +915,20 @@ mod dispatch {
     use super::*;
     use packet::Response;
 
-    pub async fn handshake(conn: &Connection) -> Result<Response, NetError> {
-        // Set state to Status
-        conn.set_state(ConnectionState::Status).await;
+    pub async fn handshake(conn: &Connection, packet: Packet) -> Result<Response, NetError> {
+        let handshake = Handshake::parse(&packet)?;
+        conn.set_state(handshake.next_state.into()).await;
 
         Ok(Response::new(None))
     }
 
-    pub async fn status(packet: Packet) -> Result<Response, NetError> {
+    pub async fn status(packet: Packet, motd: Option<&str>) -> Result<Response, NetError> {
         match packet.get_id().get_value() {
             0x00 => {
-                // Got Status Request
-                let status_resp_packet = slp::status_response()?;
+                // Got Status Request, built on a blocking thread when the status cache is stale.
+                let motd = motd.map(str::to_string);
+                let status_resp_packet =
+                    timeout::blocking(move || slp::status_response(motd.as_deref())).await??;
                 let response = Response::new(Some(status_resp_packet));
 
                 Ok(response)
@@ -202,11 +954,1228 @@ mod dispatch {
         }
     }
 
-    pub async fn login(conn: &Connection, packet: Packet) -> Result<Response, NetError> {
-        todo!()
+    pub async fn login(
+        conn: &Connection,
+        packet: Packet,
+        options: &ListenOptions,
+    ) -> Result<Response, NetError> {
+        match packet.get_id().get_value() {
+            0x00 => {
+                // Got Login Start
+                let login_start = LoginStart::parse(&packet)?;
+                info!("{} is logging in", login_start.name);
+
+                let protocol_version = consts::minecraft::PROTOCOL_VERSION as i32;
+                if conn.protocol_version != protocol_version {
+                    info!(
+                        "Refused the login of {}: protocol version {}",
+                        login_start.name, conn.protocol_version
+                    );
+                    return refuse_login(if conn.protocol_version < protocol_version {
+                        DisconnectReason::OutdatedClient
+                    } else {
+                        DisconnectReason::OutdatedServer
+                    });
+                }
+
+                let maintenance = options
+                    .maintenance
+                    .read()
+                    .unwrap()
+                    .login_refusal()
+                    .map(str::to_string);
+                if let Some(message) = maintenance {
+                    info!("Refused the login of {}: maintenance", login_start.name);
+                    return refuse_login(DisconnectReason::Maintenance(message));
+                }
+
+                if let Some(threshold) = options.compression_threshold {
+                    conn.enable_compression(threshold).await?;
+                }
+
+                // TODO: Check the chat signing key with `secure_profile::check` when Player Session
+                // is received, once the Play state exists. The key isn't sent at login since 1.19.3.
+                // TODO: Authenticate the online players (encryption, session server), their UUID
+                // is trusted for now.
+                let uuid = match options.uuid_resolver {
+                    UuidResolver::Online => login_start.uuid,
+                    UuidResolver::Offline => {
+                        let uuid = player::uuid::offline_uuid(&login_start.name);
+                        u128::from_str_radix(&uuid, 16).expect("offline UUIDs are hexadecimal")
+                    }
+                };
+                if let Some(directory) = options.player_data.clone() {
+                    let limits = options.nbt_limits;
+                    let player_data = timeout::blocking(move || {
+                        player::data::load(&directory, &format!("{uuid:032x}"), &limits)
+                    })
+                    .await?;
+                    // TODO: Keep the data for the Play state (position, inventory, ...).
+                    if player_data.is_some() {
+                        debug!("Loaded the player data of {}", login_start.name);
+                    }
+                }
+
+                let login_success = LoginSuccess::from_values(uuid, login_start.name).build()?;
+
+                // Login Success waits for the responses to the login plugin requests.
+                let mut exchange = LoginPluginExchange::start(options.login_plugins.clone());
+                let Some(request) = exchange.next_request() else {
+                    return send_login_success(conn, login_success);
+                };
+                *conn.plugin_login.lock().unwrap() = Some(PluginLogin {
+                    exchange,
+                    login_success,
+                });
+                Ok(Response::new(Some(request.build()?)))
+            }
+            0x02 => {
+                // Got Login Plugin Response
+                let response = LoginPluginResponse::parse(&packet)?;
+                let mut plugin_login = conn.plugin_login.lock().unwrap();
+                let Some(login) = plugin_login.as_mut() else {
+                    warn!("Got a Login Plugin Response without request, closing the connection");
+                    return refuse_login(DisconnectReason::ProtocolError);
+                };
+
+                if let Err(e) = login.exchange.handle(&response) {
+                    info!("Refused a login: {e}");
+                    return refuse_login(match e {
+                        LoginPluginError::Rejected { reason, .. } => {
+                            DisconnectReason::LoginPlugin(reason)
+                        }
+                        // Like vanilla, a response to no request is a protocol error.
+                        LoginPluginError::UnknownMessageId(_) => DisconnectReason::ProtocolError,
+                    });
+                }
+
+                if let Some(request) = login.exchange.next_request() {
+                    return Ok(Response::new(Some(request.build()?)));
+                }
+                let login = plugin_login.take().expect("the login is waiting");
+                send_login_success(conn, login.login_success)
+            }
+            0x03 => {
+                // Got Login Acknowledged
+                LoginAcknowledged::parse(&packet)?;
+                // Would skip the checks of Login Start.
+                if !conn.login_success_sent.load(Ordering::Relaxed) {
+                    warn!("Got Login Acknowledged before Login Success, closing the connection");
+                    return refuse_login(DisconnectReason::ProtocolError);
+                }
+                conn.set_state(ConnectionState::Configuration).await;
+
+                // TODO: Configure the client (registries, known packs) instead.
+                refuse_configuration(DisconnectReason::NotImplemented)
+            }
+            _ => {
+                warn!("Unknown packet ID, State: Login");
+                Err(NetError::UnknownPacketId(format!(
+                    "unknown packet ID, State: Login, PacketId: {}",
+                    packet.get_id().get_value()
+                )))
+            }
+        }
+    }
+
+    /// Answers the login with `login_success`, which Login Acknowledged must then follow.
+    fn send_login_success(conn: &Connection, login_success: Packet) -> Result<Response, NetError> {
+        conn.login_success_sent.store(true, Ordering::Relaxed);
+        Ok(Response::new(Some(login_success)))
+    }
+
+    /// Disconnects a client logging in with `reason`.
+    fn refuse_login(reason: DisconnectReason) -> Result<Response, NetError> {
+        let disconnect = LoginDisconnect {
+            reason: reason.to_component(),
+        }
+        .build()?;
+        Ok(Response::new(Some(disconnect)).close_conn())
+    }
+
+    /// Not reached yet: the client is disconnected as soon as it enters the Configuration state.
+    pub async fn configuration(_conn: &Connection, packet: Packet) -> Result<Response, NetError> {
+        debug!(
+            "Got packet {} in the Configuration state",
+            packet.get_id().get_value()
+        );
+        refuse_configuration(DisconnectReason::NotImplemented)
+    }
+
+    /// Disconnects a client being configured with `reason`.
+    fn refuse_configuration(reason: DisconnectReason) -> Result<Response, NetError> {
+        let disconnect = ConfigurationDisconnect {
+            reason: reason.to_component(),
+        }
+        .build()?;
+        Ok(Response::new(Some(disconnect)).close_conn())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packet::data_types::{string, Encodable};
+    use packet::PacketBuilder;
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+    use virtual_host::VirtualHost;
+
+    /// Delivers its chunks one read at a time, then reads 0 bytes like a closed socket.
+    struct ChunkedReader {
+        chunks: VecDeque<Vec<u8>>,
+    }
+
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if let Some(mut chunk) = self.chunks.pop_front() {
+                let count = chunk.len().min(buf.remaining());
+                buf.put_slice(&chunk[..count]);
+                chunk.drain(..count);
+                if !chunk.is_empty() {
+                    self.chunks.push_front(chunk);
+                }
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_exact_into() {
+        let chunks = [vec![1, 2], vec![3], vec![4, 5, 6, 7], vec![8]];
+        let mut reader = ChunkedReader {
+            chunks: chunks.into(),
+        };
+
+        let read = read_exact_into(&mut reader, 5).await.unwrap();
+        assert_eq!(&read[..], &[1, 2, 3, 4, 5]);
+        // The bytes after the 5 aren't consumed.
+        let read = read_exact_into(&mut reader, 3).await.unwrap();
+        assert_eq!(&read[..], &[6, 7, 8]);
+
+        let closed = read_exact_into(&mut reader, 1).await;
+        assert!(matches!(closed, Err(NetError::ConnectionClosed(_))));
+    }
+
+    /// Fails every write as if the client was gone.
+    struct BrokenPipeWriter;
+
+    impl AsyncWrite for BrokenPipeWriter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_broken_pipe() {
+        let bytes = ByteCounter::default();
+        let result = write_bytes(&mut BrokenPipeWriter, &[1, 2, 3], &bytes).await;
+
+        assert!(matches!(result, Err(NetError::ConnectionClosed(_))));
+        assert_eq!(bytes.get_written(), 0);
+        assert!(ignore_disconnect(result).is_ok());
+    }
+
+    #[test]
+    fn test_write_error_classification() {
+        let reset = NetError::from_write(io::ErrorKind::ConnectionReset.into());
+        assert!(reset.is_disconnect());
+
+        let other = NetError::from_write(io::ErrorKind::PermissionDenied.into());
+        assert!(matches!(other, NetError::Writing(_)));
+        assert!(ignore_disconnect(Err(other)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ready_after_bind() {
+        let (ready, bound) = oneshot::channel();
+        tokio::spawn(async move {
+            listen_on("127.0.0.1:0", ready, ListenOptions::default())
+                .await
+                .map_err(|e| e.to_string())
+        });
+
+        let address = bound.await.expect("The listener should be bound");
+        assert!(TcpStream::connect(address).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bind_server_ip() {
+        let settings = |server_ip: &str| {
+            config::Settings::with(&[("server-ip", server_ip), ("server-port", "0")])
+        };
+
+        assert_eq!(
+            bind_address(&settings("")).unwrap(),
+            "0.0.0.0:0".parse().unwrap()
+        );
+        assert!(matches!(
+            bind_address(&settings("127.0.0.300")),
+            Err(NetError::InvalidServerIp(ip, _)) if ip == "127.0.0.300"
+        ));
+
+        let address = bind_address(&settings("127.0.0.1")).unwrap();
+        let (ready, bound) = oneshot::channel();
+        tokio::spawn(async move {
+            listen_on(&address.to_string(), ready, ListenOptions::default())
+                .await
+                .map_err(|e| e.to_string())
+        });
+        let bound = bound.await.unwrap();
+        assert!(bound.ip().is_loopback());
+        assert!(TcpStream::connect(bound).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_when_bind_fails() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = taken.local_addr().unwrap().to_string();
+
+        let (ready, bound) = oneshot::channel();
+        let options = ListenOptions::default();
+        assert!(listen_on(&address, ready, options).await.is_err());
+        assert!(bound.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_connection_and_packet() {
+        let before = metrics::global().snapshot();
+
+        let (ready, bound) = oneshot::channel();
+        tokio::spawn(async move {
+            listen_on("127.0.0.1:0", ready, ListenOptions::default())
+                .await
+                .map_err(|e| e.to_string())
+        });
+        let mut client = TcpStream::connect(bound.await.unwrap()).await.unwrap();
+
+        // Handshake: protocol 769, "localhost", port 25565, next state Status
+        let mut handshake = vec![0x00, 0x81, 0x06, 9];
+        handshake.extend(b"localhost");
+        handshake.extend([0x63, 0xdd, 0x01]);
+        let mut frame = vec![handshake.len() as u8];
+        frame.extend(handshake);
+        client.write_all(&frame).await.unwrap();
+
+        // The counters are global, other tests may increase them too.
+        let mut after = metrics::global().snapshot();
+        for _ in 0..100 {
+            if after.packets_handled > before.packets_handled {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            after = metrics::global().snapshot();
+        }
+        assert!(after.connections_total > before.connections_total);
+        assert!(after.packets_handled > before.packets_handled);
+        assert!(after.bytes_in >= before.bytes_in + frame.len() as u64);
+    }
+
+    /// Starts a server with `options` and returns its address.
+    async fn start_server(options: ListenOptions) -> SocketAddr {
+        let (ready, bound) = oneshot::channel();
+        tokio::spawn(async move {
+            listen_on("127.0.0.1:0", ready, options)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        bound.await.unwrap()
+    }
+
+    /// Returns the frame of a handshake for `hostname` with `next_state`.
+    fn handshake_frame(hostname: &str, next_state: NextState) -> Vec<u8> {
+        // Handshake: protocol 769, `hostname`, port 25565, `next_state`
+        let mut handshake = vec![0x00, 0x81, 0x06, hostname.len() as u8];
+        handshake.extend(hostname.as_bytes());
+        handshake.extend([0x63, 0xdd, next_state as u8]);
+        let mut frame = vec![handshake.len() as u8];
+        frame.extend(handshake);
+        frame
+    }
+
+    /// Sends a handshake for `hostname` with `next_state`.
+    async fn send_handshake(client: &mut TcpStream, hostname: &str, next_state: NextState) {
+        let frame = handshake_frame(hostname, next_state);
+        client.write_all(&frame).await.unwrap();
+    }
+
+    /// Sends a handshake for `hostname` with next state Status, then a Status Request, and
+    /// returns the status response.
+    async fn request_status(client: &mut TcpStream, hostname: &str) -> Packet {
+        send_handshake(client, hostname, NextState::Status).await;
+
+        // Status Request
+        client.write_all(&[1, 0x00]).await.unwrap();
+        let mut buffer = vec![0; 32768];
+        let read = client.read(&mut buffer).await.unwrap();
+        Packet::new(&buffer[..read]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_status_only_connection_skips_connection() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let answered = metrics::global().snapshot().status_requests;
+
+        let status = request_status(&mut client, "localhost").await;
+        assert_eq!(status.get_id().get_value(), 0x00);
+        // Only `serve_status` answers them, without a `Connection`. The other tests can only add
+        // to the count.
+        assert!(metrics::global().snapshot().status_requests > answered);
+
+        // Ping Request, answered with the same payload before the server closes the connection
+        let ping = [9, 0x01, 0, 0, 0, 0, 0, 0, 0, 42];
+        client.write_all(&ping).await.unwrap();
+        let mut pong = Vec::new();
+        client.read_to_end(&mut pong).await.unwrap();
+        assert_eq!(pong, ping);
+    }
+
+    #[tokio::test]
+    async fn test_server_list_ping() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        let status = request_status(&mut client, "localhost").await;
+        let (json, _) = string::read(status.get_payload()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            json["version"]["protocol"],
+            consts::minecraft::PROTOCOL_VERSION
+        );
+
+        let nonce = -0x0123_4567_89AB_CDEF;
+        let ping = PacketBuilder::new().append_long(nonce).build(0x01).unwrap();
+        client.write_all(ping.get_full_packet()).await.unwrap();
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let pong = Packet::new(&answer).unwrap();
+        assert_eq!(pong.get_id().get_value(), 0x01);
+        let mut reader = packet::reader::PacketReader::new(pong.get_payload());
+        assert_eq!(reader.read_long().unwrap(), nonce);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_status_packets() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // Handshake, Status Request and Ping Request in a single write
+        let ping = [9, 0x01, 0, 0, 0, 0, 0, 0, 0, 42];
+        let mut frames = handshake_frame("localhost", NextState::Status);
+        frames.extend([1, 0x00]);
+        frames.extend(ping);
+        client.write_all(&frames).await.unwrap();
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let mut decoder = FrameDecoder::new();
+        decoder.extend(&answer);
+        let status = decoder.decode().unwrap().unwrap();
+        assert_eq!(status.get_id().get_value(), 0x00);
+        let pong = decoder.decode().unwrap().unwrap();
+        assert_eq!(pong.get_full_packet(), ping);
+    }
+
+    #[tokio::test]
+    async fn test_frames_split_across_reads() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // A handshake larger than a read, with a 2-byte length, then a Status Request and a Ping
+        // Request.
+        let hostname = "a".repeat(2000);
+        let handshake = PacketBuilder::new()
+            .append_varint(769)
+            .append_string(&hostname)
+            .append_ushort(25565)
+            .append_varint(NextState::Status as i32)
+            .build(0x00)
+            .unwrap();
+        let ping = [9, 0x01, 0, 0, 0, 0, 0, 0, 0, 42];
+        let mut frames = handshake.get_full_packet().to_vec();
+        frames.extend([1, 0x00]);
+        frames.extend(ping);
+
+        // Awkward sizes: the length VarInt of the handshake is split, and the last write holds
+        // the end of a packet and the start of the next one.
+        let mut rest = frames.as_slice();
+        for size in [1, 2, 700, 1300, 5, 3].into_iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, remaining) = rest.split_at(size.min(rest.len()));
+            client.write_all(chunk).await.unwrap();
+            client.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            rest = remaining;
+        }
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let mut decoder = FrameDecoder::new();
+        decoder.extend(&answer);
+        let status = decoder.decode().unwrap().unwrap();
+        assert_eq!(status.get_id().get_value(), 0x00);
+        let pong = decoder.decode().unwrap().unwrap();
+        assert_eq!(pong.get_full_packet(), ping);
+    }
+
+    #[tokio::test]
+    async fn test_status_virtual_host_motd() {
+        let router = VirtualHostRouter::new(vec![VirtualHost {
+            hostname: "creative.example.com".to_string(),
+            motd: Some("Creative server".to_string()),
+        }]);
+        let address = start_server(ListenOptions {
+            virtual_hosts: Arc::new(router),
+            ..Default::default()
+        })
+        .await;
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let status = request_status(&mut client, "creative.example.com").await;
+        let (json, _) = string::read(status.get_payload()).unwrap();
+        assert!(json.contains("\"text\":\"Creative server\""));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let status = request_status(&mut client, "unknown.example.com").await;
+        let (json, _) = string::read(status.get_payload()).unwrap();
+        assert!(!json.contains("Creative server"));
+    }
+
+    /// Whether the server closes `client` without answering.
+    async fn is_rejected(client: &mut TcpStream) -> bool {
+        let mut buffer = [0; 1];
+        let read = tokio::time::timeout(Duration::from_millis(100), client.read(&mut buffer));
+        matches!(read.await, Ok(Ok(0)) | Ok(Err(_)))
+    }
+
+    #[tokio::test]
+    async fn test_max_pending_connections() {
+        let options = ListenOptions {
+            max_pending_connections: Some(2),
+            ..Default::default()
+        };
+        let (ready, bound) = oneshot::channel();
+        tokio::spawn(async move {
+            listen_on("127.0.0.1:0", ready, options)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        let address = bound.await.unwrap();
+
+        // They never send their handshake, so they stay pending.
+        let first = TcpStream::connect(address).await.unwrap();
+        let mut second = TcpStream::connect(address).await.unwrap();
+        let mut third = TcpStream::connect(address).await.unwrap();
+        assert!(is_rejected(&mut third).await);
+        assert!(!is_rejected(&mut second).await);
+
+        drop(first);
+        let mut accepted = false;
+        for _ in 0..20 {
+            let mut fourth = TcpStream::connect(address).await.unwrap();
+            if !is_rejected(&mut fourth).await {
+                accepted = true;
+                break;
+            }
+        }
+        assert!(accepted, "A connection should be accepted once one closes");
+    }
+
+    #[tokio::test]
+    async fn test_login_read_timeout() {
+        let address = start_server(ListenOptions {
+            max_pending_connections: Some(1),
+            login_read_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        })
+        .await;
+
+        // A silent client holds the only slot, until it times out.
+        let mut silent = TcpStream::connect(address).await.unwrap();
+        let mut second = TcpStream::connect(address).await.unwrap();
+        assert!(is_rejected(&mut second).await);
+        let mut buffer = [0; 1];
+        let closed = tokio::time::timeout(Duration::from_secs(2), silent.read(&mut buffer));
+        assert!(matches!(closed.await, Ok(Ok(0)) | Ok(Err(_))));
+
+        // Also after the handshake.
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        let closed = tokio::time::timeout(Duration::from_secs(2), client.read(&mut buffer));
+        assert!(matches!(closed.await, Ok(Ok(0)) | Ok(Err(_))));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let status = request_status(&mut client, "localhost").await;
+        assert_eq!(status.get_id().get_value(), 0x00);
+    }
+
+    /// Fails with the errors of `errors`, in order, then accepts a connection.
+    struct FailingAcceptor {
+        errors: std::sync::Mutex<Vec<io::Error>>,
+    }
+
+    impl Acceptor for FailingAcceptor {
+        type Stream = ();
+
+        async fn accept(&self) -> io::Result<((), SocketAddr)> {
+            match self.errors.lock().unwrap().pop() {
+                Some(e) => Err(e),
+                None => Ok(((), "127.0.0.1:25565".parse().unwrap())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_client_address() {
+        let addr: SocketAddr = "203.0.113.7:51234".parse().unwrap();
+        assert_eq!(client_address(addr, true), "203.0.113.7:51234");
+        assert_eq!(client_address(addr, false), "<ip address withheld>");
+    }
+
+    #[tokio::test]
+    async fn test_accept_error_is_retried() {
+        // EMFILE, too many open files.
+        let acceptor = FailingAcceptor {
+            errors: std::sync::Mutex::new(vec![
+                io::Error::from_raw_os_error(24),
+                io::Error::from(io::ErrorKind::ConnectionAborted),
+            ]),
+        };
+        let (_, addr) = accept_with_backoff(&acceptor, Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(addr.port(), 25565);
+        assert!(acceptor.errors.lock().unwrap().is_empty());
+
+        let acceptor = FailingAcceptor {
+            errors: std::sync::Mutex::new(vec![io::Error::from(io::ErrorKind::InvalidInput)]),
+        };
+        let error = accept_with_backoff(&acceptor, Duration::from_millis(1))
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    /// Logs in with `login_start`, returns what the server answered.
+    async fn login(address: SocketAddr, login_start: LoginStart) -> Vec<u8> {
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        client
+            .write_all(login_start.build().unwrap().get_full_packet())
+            .await
+            .unwrap();
+
+        let mut answer = Vec::new();
+        // A refused client is disconnected, an accepted one waits for the rest of the login.
+        let read = client.read_to_end(&mut answer);
+        let _ = tokio::time::timeout(Duration::from_millis(200), read).await;
+        answer
     }
 
-    pub async fn transfer(conn: &Connection, packet: Packet) -> Result<Response, NetError> {
-        todo!()
+    #[tokio::test]
+    async fn test_login_default_settings() {
+        // Secure profiles are enforced by default, and vanilla clients don't send their key at
+        // login.
+        let settings = config::Settings::with(&[]);
+        assert!(secure_profile::is_enforced(&settings));
+        let address = start_server(ListenOptions::from_settings(&settings)).await;
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+
+        // Set Compression, threshold 256, then Login Success: too small to be compressed.
+        let answer = login(address, login_start).await;
+        assert_eq!(answer[..4], [3, 0x03, 0x80, 0x02]);
+        assert_eq!(answer[4..7], [25, 0, 0x02]);
+    }
+
+    /// Accepts the clients sending back the data of the request.
+    #[derive(Debug)]
+    struct Echo;
+
+    impl LoginPluginHandler for Echo {
+        fn channel(&self) -> &str {
+            "cactus:echo"
+        }
+
+        fn request_data(&self) -> Vec<u8> {
+            b"secret".to_vec()
+        }
+
+        fn handle_response(&self, data: Option<&[u8]>) -> Result<(), String> {
+            match data {
+                Some(b"secret") => Ok(()),
+                _ => Err("Echo the secret to log in".to_string()),
+            }
+        }
+    }
+
+    /// Reads the next uncompressed packet sent to `client`.
+    async fn read_frame(client: &mut TcpStream) -> Packet {
+        let mut frame = vec![client.read_u8().await.unwrap()];
+        let length = frame[0] as usize;
+        frame.resize(1 + length, 0);
+        client.read_exact(&mut frame[1..]).await.unwrap();
+        Packet::new(frame).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_login_plugin() {
+        use packet::packet_types::LoginPluginRequest;
+
+        let address = start_server(ListenOptions {
+            login_plugins: vec![Arc::new(Echo), Arc::new(Echo)],
+            ..Default::default()
+        })
+        .await;
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        }
+        .build()
+        .unwrap();
+
+        // Each request is sent once the previous one got its response, then Login Success.
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+        for message_id in 0..2 {
+            let request = LoginPluginRequest::parse(&read_frame(&mut client).await).unwrap();
+            assert_eq!(request.message_id, message_id);
+            assert_eq!(request.channel, "cactus:echo");
+            let response = LoginPluginResponse {
+                message_id,
+                data: Some(request.data),
+            };
+            client
+                .write_all(response.build().unwrap().get_full_packet())
+                .await
+                .unwrap();
+        }
+        let login_success = read_frame(&mut client).await;
+        assert_eq!(login_success.get_id().get_value(), 0x02);
+
+        // A client not understanding the channel is refused by the handler.
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+        let request = LoginPluginRequest::parse(&read_frame(&mut client).await).unwrap();
+        let response = LoginPluginResponse::not_understood(request.message_id);
+        client
+            .write_all(response.build().unwrap().get_full_packet())
+            .await
+            .unwrap();
+        let disconnect = read_frame(&mut client).await;
+        assert_eq!(disconnect.get_id().get_value(), 0x00);
+        let (reason, _) = string::read(disconnect.get_payload()).unwrap();
+        assert!(reason.contains("Echo the secret to log in"));
+
+        // A response to no request is a protocol error.
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        let response = LoginPluginResponse::not_understood(7);
+        client
+            .write_all(response.build().unwrap().get_full_packet())
+            .await
+            .unwrap();
+        let disconnect = read_frame(&mut client).await;
+        assert_eq!(disconnect.get_id().get_value(), 0x00);
+    }
+
+    /// Held by the tests logging players in, which change the global count of online players.
+    static ONLINE_PLAYERS_LOCK: Mutex<()> = Mutex::const_new(());
+
+    #[tokio::test]
+    async fn test_login_success() {
+        let _online_players = ONLINE_PLAYERS_LOCK.lock().await;
+        let online_players = player::online_players().load(Ordering::Relaxed);
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            // Also the offline UUID of Steve, whatever the mode.
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let login_start = login_start.build().unwrap();
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+
+        let mut login_success = [0; 25];
+        client.read_exact(&mut login_success).await.unwrap();
+        let mut expected = vec![24, 0x02];
+        expected.extend(0x5627dd98e6be3c21b8a8e92344183641u128.to_be_bytes());
+        expected.extend([5, b'S', b't', b'e', b'v', b'e', 0]);
+        assert_eq!(login_success[..], expected);
+
+        // Login Acknowledged: the client can't be configured yet.
+        client.write_all(&[1, 0x03]).await.unwrap();
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let disconnect = Packet::new(&answer).unwrap();
+        assert_eq!(disconnect.get_id().get_value(), 0x02);
+        let reason = DisconnectReason::NotImplemented.to_component();
+        assert_eq!(
+            disconnect.get_payload(),
+            reason.to_nbt().unwrap().get_bytes()
+        );
+
+        // The player leaves once the server drops the connection.
+        drop(client);
+        while player::online_players().load(Ordering::Relaxed) != online_players {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_counts_online_players() {
+        let _online_players = ONLINE_PLAYERS_LOCK.lock().await;
+        let status_online_players = || {
+            let status = slp::status_response(None).unwrap();
+            let (json, _) = string::read(status.get_payload()).unwrap();
+            let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            json["players"]["online"].as_u64().unwrap()
+        };
+        let before = status_online_players();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let handshake =
+            Handshake::parse(&Packet::new(handshake_frame("localhost", NextState::Login)).unwrap())
+                .unwrap();
+        let connection = Connection::new(
+            socket,
+            &handshake,
+            FrameDecoder::new(),
+            ByteCounter::default(),
+        );
+        connection.set_state(ConnectionState::Login).await;
+        assert_eq!(status_online_players(), before);
+        connection.set_state(ConnectionState::Configuration).await;
+        assert_eq!(status_online_players(), before + 1);
+
+        drop(connection);
+        assert_eq!(status_online_players(), before);
+    }
+
+    #[tokio::test]
+    async fn test_configuration_releases_pending() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let handshake =
+            Handshake::parse(&Packet::new(handshake_frame("localhost", NextState::Login)).unwrap())
+                .unwrap();
+        let connection = Connection::new(
+            socket,
+            &handshake,
+            FrameDecoder::new(),
+            ByteCounter::default(),
+        );
+
+        let semaphore = Arc::new(Semaphore::new(1));
+        connection.hold_pending(Some(semaphore.clone().try_acquire_owned().unwrap()));
+        connection.set_state(ConnectionState::Login).await;
+        assert_eq!(semaphore.available_permits(), 0);
+        connection.set_state(ConnectionState::Configuration).await;
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connection_records_handshake() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        send_handshake(&mut client, "play.example.com", NextState::Login).await;
+        let bytes = ByteCounter::default();
+        let mut decoder = FrameDecoder::new();
+        let handshake = read_handshake(&mut socket, &mut decoder, &bytes, true)
+            .await
+            .unwrap();
+        let connection = Connection::new(socket, &handshake, decoder, bytes);
+
+        assert_eq!(connection.protocol_version, 769);
+        assert_eq!(connection.server_address, "play.example.com");
+        assert_eq!(connection.server_port, 25565);
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_connection_packets() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let handshake = Handshake {
+            protocol_version: 769,
+            server_address: "localhost".to_string(),
+            server_port: 25565,
+            next_state: NextState::Status,
+        };
+        // Still in the Handshake state, it has to handle the handshake itself.
+        let connection = Connection::new(
+            socket,
+            &handshake,
+            FrameDecoder::new(),
+            ByteCounter::default(),
+        );
+
+        // Handshake and Status Request in a single write
+        let mut frames = handshake_frame("localhost", NextState::Status);
+        frames.extend([1, 0x00]);
+        client.write_all(&frames).await.unwrap();
+
+        let options = ListenOptions::default();
+        let handled = handle_packets(&connection, &options);
+        let _ = tokio::time::timeout(Duration::from_millis(200), handled).await;
+        assert_eq!(connection.get_state().await, ConnectionState::Status);
+
+        let mut buffer = vec![0; 32768];
+        let read = client.read(&mut buffer).await.unwrap();
+        let status = Packet::new(&buffer[..read]).unwrap();
+        assert_eq!(status.get_id().get_value(), 0x00);
+    }
+
+    #[tokio::test]
+    async fn test_status_only() {
+        let address = start_server(ListenOptions {
+            status_only: Some("Booting".to_string()),
+            ..Default::default()
+        })
+        .await;
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let disconnect = Packet::new(&answer).unwrap();
+        assert_eq!(disconnect.get_id().get_value(), 0x00);
+        let (reason, _) = string::read(disconnect.get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Booting"}"#);
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let status = request_status(&mut client, "localhost").await;
+        assert_eq!(status.get_id().get_value(), 0x00);
+    }
+
+    #[tokio::test]
+    async fn test_login_outdated_client() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // Handshake with protocol 767 (1.21), then Login Start
+        let mut frame = handshake_frame("localhost", NextState::Login);
+        frame[2..4].copy_from_slice(&[0xFF, 0x05]);
+        client.write_all(&frame).await.unwrap();
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let login_start = login_start.build().unwrap();
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let (reason, _) = string::read(Packet::new(&answer).unwrap().get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Outdated client! Please use 1.21.4"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_out_of_state_packet() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // Handshake, then a Play packet (Chat Message) during the login.
+        client
+            .write_all(&handshake_frame("localhost", NextState::Login))
+            .await
+            .unwrap();
+        let chat = PacketBuilder::new()
+            .append_string("Hi")
+            .build(0x07)
+            .unwrap();
+        client.write_all(chat.get_full_packet()).await.unwrap();
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let disconnect = Packet::new(&answer).unwrap();
+        assert_eq!(disconnect.get_id().get_value(), 0x00);
+        let (reason, _) = string::read(disconnect.get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Network Protocol Error"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_login_uuid_resolver() {
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 1,
+        };
+        for (uuid_resolver, uuid) in [
+            (UuidResolver::Offline, 0x5627dd98e6be3c21b8a8e92344183641),
+            (UuidResolver::Online, 1u128),
+        ] {
+            let address = start_server(ListenOptions {
+                uuid_resolver,
+                ..Default::default()
+            })
+            .await;
+            let answer = login(address, login_start.clone()).await;
+            let login_success = Packet::new(&answer).unwrap();
+            assert_eq!(login_success.get_id().get_value(), 0x02);
+            assert_eq!(login_success.get_payload()[..16], uuid.to_be_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_logs_in() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        send_handshake(&mut client, "localhost", NextState::Transfer).await;
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let login_start = login_start.build().unwrap();
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+
+        // Login Success, like a login.
+        let mut login_success = [0; 25];
+        client.read_exact(&mut login_success).await.unwrap();
+        assert_eq!(login_success[..2], [24, 0x02]);
+    }
+
+    #[tokio::test]
+    async fn test_early_login_acknowledged() {
+        let address = start_server(ListenOptions::default()).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // Handshake, then Login Acknowledged without Login Start.
+        client
+            .write_all(&handshake_frame("localhost", NextState::Login))
+            .await
+            .unwrap();
+        client.write_all(&[1, 0x03]).await.unwrap();
+
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+        let disconnect = Packet::new(&answer).unwrap();
+        assert_eq!(disconnect.get_id().get_value(), 0x00);
+        let (reason, _) = string::read(disconnect.get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Network Protocol Error"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_forced_compression() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let handshake =
+            Handshake::parse(&Packet::new(handshake_frame("localhost", NextState::Login)).unwrap())
+                .unwrap();
+        let connection = Connection::new(
+            socket,
+            &handshake,
+            FrameDecoder::new(),
+            ByteCounter::default(),
+        );
+        let tiny = PacketBuilder::new()
+            .append_bytes(b"hi")
+            .build(0x05)
+            .unwrap();
+
+        // Threshold 0: even a 3-byte packet is compressed.
+        connection.force_compression(Some(0)).await;
+        assert_eq!(connection.compression_threshold().await, Some(0));
+        connection.write(std::slice::from_ref(&tiny)).await.unwrap();
+        let mut client_io = transport::ConnectionIo::new();
+        client_io.enable_compression(0);
+        let mut wire = Vec::new();
+        let received = loop {
+            let mut buffer = [0; 64];
+            let read = client.read(&mut buffer).await.unwrap();
+            wire.extend_from_slice(&buffer[..read]);
+            let packets = client_io.decode(&buffer[..read]).unwrap();
+            if !packets.is_empty() {
+                break packets;
+            }
+        };
+        // Packet Length, then Data Length 3 rather than 0: the packet was compressed.
+        assert_eq!(wire[1], 3);
+        assert_eq!(received[0].get_full_packet(), tiny.get_full_packet());
+
+        // And the packets of the client are decompressed.
+        let wire = client_io.encode(&tiny).unwrap();
+        client.write_all(&wire).await.unwrap();
+        let read = connection.read().await.unwrap();
+        assert_eq!(read[0].get_full_packet(), tiny.get_full_packet());
+
+        // Back to the uncompressed format.
+        connection.force_compression(None).await;
+        connection.write(std::slice::from_ref(&tiny)).await.unwrap();
+        let mut buffer = [0; 4];
+        client.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(buffer, tiny.get_full_packet());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_ping() {
+        let address = start_server(ListenOptions {
+            motd: Some("A Cactus server".to_string()),
+            max_players: 42,
+            ..Default::default()
+        })
+        .await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        // The ping of a 1.6 client starts with 0xFE 0x01 0xFA.
+        client.write_all(&[0xFE, 0x01, 0xFA]).await.unwrap();
+        let mut answer = Vec::new();
+        client.read_to_end(&mut answer).await.unwrap();
+
+        assert_eq!(answer[0], 0xFF);
+        let units: Vec<u16> = answer[3..]
+            .chunks(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        assert_eq!(
+            usize::from(u16::from_be_bytes([answer[1], answer[2]])),
+            units.len()
+        );
+        let response = String::from_utf16(&units).unwrap();
+        assert!(response.starts_with("§1\u{0}127\u{0}1.21.4\u{0}A Cactus server\u{0}"));
+        assert!(response.ends_with("\u{0}42"));
+    }
+
+    #[tokio::test]
+    async fn test_login_enables_compression() {
+        let address = start_server(ListenOptions {
+            compression_threshold: Some(256),
+            ..Default::default()
+        })
+        .await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+
+        send_handshake(&mut client, "localhost", NextState::Login).await;
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let login_start = login_start.build().unwrap();
+        client
+            .write_all(login_start.get_full_packet())
+            .await
+            .unwrap();
+
+        // Set Compression, threshold 256, in the uncompressed format.
+        let mut set_compression = [0; 4];
+        client.read_exact(&mut set_compression).await.unwrap();
+        assert_eq!(set_compression, [3, 0x03, 0x80, 0x02]);
+    }
+
+    #[tokio::test]
+    async fn test_byte_counter() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let before = metrics::global().snapshot();
+
+        let bytes = ByteCounter::default();
+        client.write_all(&[1, 0x00]).await.unwrap();
+        let packet = read_packet(&mut socket, &mut FrameDecoder::new(), &bytes)
+            .await
+            .unwrap();
+        assert_eq!(packet.get_id().get_value(), 0x00);
+        assert_eq!(bytes.get_read(), 2);
+
+        let pong = [2, 0x01, 42];
+        write_bytes(&mut socket, &pong, &bytes).await.unwrap();
+        assert_eq!(bytes.get_written(), 3);
+
+        // Also in the global metrics, that other tests may increase too.
+        let after = metrics::global().snapshot();
+        assert!(after.bytes_in >= before.bytes_in + 2);
+        assert!(after.bytes_out >= before.bytes_out + 3);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance() {
+        let maintenance = Arc::new(std::sync::RwLock::new(Maintenance::new(Some(
+            "Under maintenance".to_string(),
+        ))));
+        maintenance
+            .write()
+            .unwrap()
+            .enable(Some("Back soon".to_string()));
+        let address = start_server(ListenOptions {
+            maintenance,
+            ..Default::default()
+        })
+        .await;
+
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let answer = login(address, login_start).await;
+        let disconnect = Packet::new(&answer).unwrap();
+        let (reason, _) = string::read(disconnect.get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Back soon"}"#);
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let status = request_status(&mut client, "localhost").await;
+        let (json, _) = string::read(status.get_payload()).unwrap();
+        assert!(json.contains("\"text\":\"Under maintenance\""));
     }
 }
@@ -0,0 +1,338 @@
+//! JSON text components, the formatted text of the MOTD, the disconnect reasons and the chat.
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::data_types::{string, CodecError, DataType, Encodable, ErrorReason, Nbt};
+
+/// The colors of the legacy `§` codes, `0` to `f`.
+const LEGACY_COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+/// A piece of text, with its children in `extra` inheriting its style. Unset styles are omitted
+/// from the JSON, so the client uses the parent's (or the default) one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextComponent {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<TextComponent>,
+}
+
+impl TextComponent {
+    /// Unstyled text.
+    pub fn text<T: Into<String>>(text: T) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A color name like `red`, or `#RRGGBB`.
+    pub fn color<T: Into<String>>(mut self, color: T) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    /// Appends a child, shown after this text and its previous children.
+    pub fn extra(mut self, child: TextComponent) -> Self {
+        self.extra.push(child);
+        self
+    }
+
+    /// Converts text formatted with the legacy codes, prefixed with `§` or `&`: `0` to `f` for
+    /// the colors, `k` to `o` for the styles and `r` to reset. Like vanilla, a color resets the
+    /// styles. Unknown codes are kept as text.
+    pub fn from_legacy(legacy: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut current = Self::default();
+        let mut chars = legacy.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let code = match chars.peek() {
+                Some(&code) if c == '§' || c == '&' => code.to_ascii_lowercase(),
+                _ => {
+                    current.text.push(c);
+                    continue;
+                }
+            };
+
+            let style = current.styled_like();
+            let mut next = match code {
+                '0'..='9' | 'a'..='f' => {
+                    let index = code.to_digit(16).unwrap() as usize;
+                    Self::default().color(LEGACY_COLORS[index])
+                }
+                'k' => Self {
+                    obfuscated: Some(true),
+                    ..style
+                },
+                'l' => style.bold(true),
+                'm' => Self {
+                    strikethrough: Some(true),
+                    ..style
+                },
+                'n' => Self {
+                    underlined: Some(true),
+                    ..style
+                },
+                'o' => style.italic(true),
+                'r' => Self::default(),
+                _ => {
+                    current.text.push(c);
+                    continue;
+                }
+            };
+            chars.next();
+
+            std::mem::swap(&mut current, &mut next);
+            if !next.text.is_empty() {
+                parts.push(next);
+            }
+        }
+        if !current.text.is_empty() || parts.is_empty() {
+            parts.push(current);
+        }
+
+        if parts.len() == 1 {
+            return parts.pop().unwrap();
+        }
+        Self {
+            extra: parts,
+            ..Default::default()
+        }
+    }
+
+    /// A component without text nor children, with the same style.
+    fn styled_like(&self) -> Self {
+        Self {
+            text: String::new(),
+            extra: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// The JSON sent to the client.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("A text component is always serializable")
+    }
+
+    /// The NBT sent to the client since 1.20.3 outside of the login, e.g. in the Configuration
+    /// Disconnect. Same fields as the JSON, the booleans being Bytes.
+    pub fn to_nbt(&self) -> Result<Nbt, CodecError> {
+        let mut blob = nbt::Blob::new();
+        for (name, value) in self.nbt_fields() {
+            blob.insert(name, value).map_err(|_| {
+                CodecError::Encoding(DataType::Nbt, ErrorReason::InvalidFormat("unwritable NBT"))
+            })?;
+        }
+        Nbt::from_value(blob)
+    }
+
+    fn nbt_fields(&self) -> HashMap<String, nbt::Value> {
+        let mut fields = HashMap::new();
+        fields.insert("text".to_string(), nbt::Value::String(self.text.clone()));
+        if let Some(color) = &self.color {
+            fields.insert("color".to_string(), nbt::Value::String(color.clone()));
+        }
+        let styles = [
+            ("bold", self.bold),
+            ("italic", self.italic),
+            ("underlined", self.underlined),
+            ("strikethrough", self.strikethrough),
+            ("obfuscated", self.obfuscated),
+        ];
+        for (name, style) in styles {
+            if let Some(style) = style {
+                fields.insert(name.to_string(), nbt::Value::Byte(style as i8));
+            }
+        }
+        if !self.extra.is_empty() {
+            let extra = self
+                .extra
+                .iter()
+                .map(|child| nbt::Value::Compound(child.nbt_fields()));
+            fields.insert("extra".to_string(), nbt::Value::List(extra.collect()));
+        }
+        fields
+    }
+}
+
+impl fmt::Display for TextComponent {
+    /// Writes the JSON.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
+
+/// The component sent as a string of its JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedTextComponent {
+    component: TextComponent,
+    bytes: Vec<u8>,
+}
+
+impl Encodable for EncodedTextComponent {
+    type ValueInput = TextComponent;
+    type ValueOutput = TextComponent;
+
+    /// A JSON string is read as unstyled text.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (json, size) = string::read(bytes)?;
+        let invalid = || {
+            CodecError::Decoding(
+                DataType::TextComponent,
+                ErrorReason::InvalidFormat("invalid JSON text component"),
+            )
+        };
+        let component = match serde_json::from_str(&json).map_err(|_| invalid())? {
+            serde_json::Value::String(text) => TextComponent::text(text),
+            value => serde_json::from_value(value).map_err(|_| invalid())?,
+        };
+        Ok(Self {
+            component,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(component: TextComponent) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: string::write(&component.to_json())?,
+            component,
+        })
+    }
+
+    fn get_value(&self) -> TextComponent {
+        self.component.clone()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        assert_eq!(TextComponent::text("Bye").to_json(), r#"{"text":"Bye"}"#);
+
+        let component = TextComponent::text("Cactus")
+            .color("green")
+            .bold(true)
+            .extra(TextComponent::text(" server").italic(true));
+        assert_eq!(
+            component.to_json(),
+            r#"{"text":"Cactus","color":"green","bold":true,"extra":[{"text":" server","italic":true}]}"#
+        );
+    }
+
+    #[test]
+    fn test_to_nbt() {
+        let nbt = TextComponent::text("Bye").to_nbt().unwrap();
+        // A nameless Compound holding the String `text`.
+        let mut expected = vec![0x0a, 0x08, 0x00, 0x04];
+        expected.extend(b"text\x00\x03Bye\x00");
+        assert_eq!(nbt.get_bytes(), expected);
+
+        let component = TextComponent::text("Cactus")
+            .bold(true)
+            .extra(TextComponent::text(" server"));
+        let blob = component.to_nbt().unwrap().get_value();
+        assert_eq!(blob["bold"], nbt::Value::Byte(1));
+        assert!(matches!(&blob["extra"], nbt::Value::List(extra) if extra.len() == 1));
+    }
+
+    #[test]
+    fn test_from_legacy() {
+        assert_eq!(
+            TextComponent::from_legacy("&aHello").to_json(),
+            r#"{"text":"Hello","color":"green"}"#
+        );
+        assert_eq!(
+            TextComponent::from_legacy("§c§lRed bold§r plain & free").to_json(),
+            r#"{"text":"","extra":[{"text":"Red bold","color":"red","bold":true},{"text":" plain & free"}]}"#
+        );
+        // A color resets the styles, a style keeps the color.
+        assert_eq!(
+            TextComponent::from_legacy("&lA&9B&oC").to_json(),
+            r#"{"text":"","extra":[{"text":"A","bold":true},{"text":"B","color":"blue"},{"text":"C","color":"blue","italic":true}]}"#
+        );
+        assert_eq!(
+            TextComponent::from_legacy("&zNo code&"),
+            TextComponent::text("&zNo code&")
+        );
+        assert_eq!(TextComponent::from_legacy(""), TextComponent::text(""));
+    }
+
+    #[test]
+    fn test_encodable() {
+        let component = TextComponent::from_legacy("&6Gold");
+        let encoded = EncodedTextComponent::from_value(component.clone()).unwrap();
+        assert_eq!(
+            encoded.get_bytes(),
+            string::write(r#"{"text":"Gold","color":"gold"}"#).unwrap()
+        );
+
+        let decoded = EncodedTextComponent::from_bytes(encoded.get_bytes()).unwrap();
+        assert_eq!(decoded.get_value(), component);
+
+        let plain = string::write(r#""Hi""#).unwrap();
+        assert_eq!(
+            EncodedTextComponent::from_bytes(&plain)
+                .unwrap()
+                .get_value(),
+            TextComponent::text("Hi")
+        );
+
+        let invalid = string::write("{text").unwrap();
+        assert!(matches!(
+            EncodedTextComponent::from_bytes(&invalid),
+            Err(CodecError::Decoding(
+                DataType::TextComponent,
+                ErrorReason::InvalidFormat(_)
+            ))
+        ));
+    }
+}
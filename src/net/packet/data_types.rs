@@ -1,5 +1,8 @@
 use thiserror::Error;
 
+use crate::region_parser::limits::NbtLimits;
+use crate::region_parser::RegionError;
+
 /// Implementation of the LEB128 variable-length code compression algorithm.
 /// Pseudo-code of this algorithm taken from https://wiki.vg/Protocol#VarInt_and_VarLong
 /// A VarInt may not be longer than 5 bytes.
@@ -160,6 +163,825 @@ pub enum CodecError {
     BlankString,
     #[error("String length error: string is too long")]
     InvalidEncoding,
+
+    #[error("{0:?} encoding error: {1}")]
+    Encoding(DataType, ErrorReason),
+    #[error("{0:?} decoding error: {1}")]
+    Decoding(DataType, ErrorReason),
+}
+
+/// The data types implementing `Encodable`, to know which one failed in a `CodecError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    VarInt,
+    Boolean,
+    Uuid,
+    UnsignedShort,
+    Int,
+    Long,
+    Float,
+    Double,
+    Position,
+    Angle,
+    Identifier,
+    PrefixedArray,
+    Optional,
+    Nbt,
+    TextComponent,
+}
+
+/// Why a data type couldn't be encoded or decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorReason {
+    /// The value is below the range of the type, or there aren't enough bytes to read it.
+    ValueTooSmall,
+    /// The value is above the range of the type.
+    ValueTooLarge,
+    /// The value doesn't follow the syntax of the type, for the reason given.
+    InvalidFormat(&'static str),
+}
+
+impl std::fmt::Display for ErrorReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ValueTooSmall => write!(f, "value too small"),
+            Self::ValueTooLarge => write!(f, "value too large"),
+            Self::InvalidFormat(reason) => write!(f, "invalid format, {reason}"),
+        }
+    }
+}
+
+/// A data type of the protocol, stored encoded. It is built either from its value (to write it in
+/// a packet) or from its bytes (to read it from a packet).
+pub trait Encodable: Sized {
+    /// What `from_value` takes.
+    type ValueInput;
+    /// What `get_value` returns.
+    type ValueOutput;
+
+    /// Reads the data type **beginning from the first byte of the data**. The following bytes are
+    /// ignored.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError>;
+
+    /// Encodes `value`.
+    fn from_value(value: Self::ValueInput) -> Result<Self, CodecError>;
+
+    /// The decoded value.
+    fn get_value(&self) -> Self::ValueOutput;
+
+    /// The encoded bytes.
+    fn get_bytes(&self) -> &[u8];
+
+    /// The number of encoded bytes.
+    fn len(&self) -> usize {
+        self.get_bytes().len()
+    }
+
+    /// Whether there is no encoded byte, e.g. an empty `ByteArray`.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the data type at the beginning of `bytes`, then moves `bytes` after it.
+    fn consume_from_bytes(bytes: &mut &[u8]) -> Result<Self, CodecError> {
+        let value = Self::from_bytes(bytes)?;
+        *bytes = &bytes[value.len()..];
+        Ok(value)
+    }
+}
+
+/// A VarInt, see `varint`. Unlike `varint::read`, a VarInt cut by the end of the bytes is an
+/// error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarInt {
+    value: i32,
+    bytes: Vec<u8>,
+}
+
+impl Encodable for VarInt {
+    type ValueInput = i32;
+    type ValueOutput = i32;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (value, size) = varint::read(bytes)?;
+        if bytes[size - 1] & 0x80 != 0 {
+            return Err(CodecError::Decoding(
+                DataType::VarInt,
+                ErrorReason::ValueTooSmall,
+            ));
+        }
+        Ok(Self {
+            value,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(value: i32) -> Result<Self, CodecError> {
+        Ok(Self {
+            value,
+            bytes: varint::write(value),
+        })
+    }
+
+    fn get_value(&self) -> i32 {
+        self.value
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A boolean, `0x01` for true and `0x00` for false.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boolean {
+    bytes: [u8; 1],
+}
+
+impl Encodable for Boolean {
+    type ValueInput = bool;
+    type ValueOutput = bool;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        match first_bytes(bytes, DataType::Boolean)? {
+            bytes @ ([0] | [1]) => Ok(Self { bytes }),
+            _ => Err(CodecError::Decoding(
+                DataType::Boolean,
+                ErrorReason::InvalidFormat("a boolean is either 0 or 1"),
+            )),
+        }
+    }
+
+    fn from_value(value: bool) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: [value as u8],
+        })
+    }
+
+    fn get_value(&self) -> bool {
+        self.bytes[0] == 1
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A UUID, as an unsigned 128-bit integer, big-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uuid {
+    bytes: [u8; 16],
+}
+
+impl Encodable for Uuid {
+    type ValueInput = u128;
+    type ValueOutput = u128;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Uuid)?,
+        })
+    }
+
+    fn from_value(value: u128) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> u128 {
+        u128::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// An unsigned 16-bit integer, big-endian. Used for the port in the handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsignedShort {
+    bytes: [u8; 2],
+}
+
+impl Encodable for UnsignedShort {
+    type ValueInput = u16;
+    type ValueOutput = u16;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        match bytes {
+            [first, second, ..] => Ok(Self {
+                bytes: [*first, *second],
+            }),
+            _ => Err(CodecError::Decoding(
+                DataType::UnsignedShort,
+                ErrorReason::ValueTooSmall,
+            )),
+        }
+    }
+
+    fn from_value(value: u16) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> u16 {
+        u16::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// The first `N` bytes of `bytes`, for the fixed-width data types.
+fn first_bytes<const N: usize>(bytes: &[u8], data_type: DataType) -> Result<[u8; N], CodecError> {
+    bytes
+        .first_chunk::<N>()
+        .copied()
+        .ok_or(CodecError::Decoding(data_type, ErrorReason::ValueTooSmall))
+}
+
+/// A signed 32-bit integer, big-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Int {
+    bytes: [u8; 4],
+}
+
+impl Encodable for Int {
+    type ValueInput = i32;
+    type ValueOutput = i32;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Int)?,
+        })
+    }
+
+    fn from_value(value: i32) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> i32 {
+        i32::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A signed 64-bit integer, big-endian. Used for the ping payloads and the keep alive IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Long {
+    bytes: [u8; 8],
+}
+
+impl Encodable for Long {
+    type ValueInput = i64;
+    type ValueOutput = i64;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Long)?,
+        })
+    }
+
+    fn from_value(value: i64) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> i64 {
+        i64::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A single-precision IEEE 754 float, big-endian. The bits are kept as is, NaN payloads included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Float {
+    bytes: [u8; 4],
+}
+
+impl Encodable for Float {
+    type ValueInput = f32;
+    type ValueOutput = f32;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Float)?,
+        })
+    }
+
+    fn from_value(value: f32) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> f32 {
+        f32::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A double-precision IEEE 754 float, big-endian. The bits are kept as is, NaN payloads included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Double {
+    bytes: [u8; 8],
+}
+
+impl Encodable for Double {
+    type ValueInput = f64;
+    type ValueOutput = f64;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Double)?,
+        })
+    }
+
+    fn from_value(value: f64) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: value.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> f64 {
+        f64::from_be_bytes(self.bytes)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A block position, packed in a big-endian 64-bit integer: X on 26 bits, Z on 26 bits, then Y on
+/// 12 bits, each in two's complement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    x: i32,
+    y: i32,
+    z: i32,
+    bytes: [u8; 8],
+}
+
+impl Position {
+    /// The range of X and Z, 26 bits.
+    const HORIZONTAL: std::ops::RangeInclusive<i32> = -(1 << 25)..=(1 << 25) - 1;
+    /// The range of Y, 12 bits.
+    const VERTICAL: std::ops::RangeInclusive<i32> = -(1 << 11)..=(1 << 11) - 1;
+
+    pub fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn get_y(&self) -> i32 {
+        self.y
+    }
+
+    pub fn get_z(&self) -> i32 {
+        self.z
+    }
+}
+
+impl Encodable for Position {
+    /// `(x, y, z)`
+    type ValueInput = (i32, i32, i32);
+    /// `(x, y, z)`
+    type ValueOutput = (i32, i32, i32);
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let bytes = first_bytes(bytes, DataType::Position)?;
+        let packed = i64::from_be_bytes(bytes);
+        // The arithmetic shifts sign-extend each component.
+        Ok(Self {
+            x: (packed >> 38) as i32,
+            y: (packed << 52 >> 52) as i32,
+            z: (packed << 26 >> 38) as i32,
+            bytes,
+        })
+    }
+
+    /// Fails with `ValueTooLarge` if a coordinate doesn't fit in its bits, whatever its sign.
+    fn from_value((x, y, z): (i32, i32, i32)) -> Result<Self, CodecError> {
+        if !Self::HORIZONTAL.contains(&x)
+            || !Self::HORIZONTAL.contains(&z)
+            || !Self::VERTICAL.contains(&y)
+        {
+            return Err(CodecError::Encoding(
+                DataType::Position,
+                ErrorReason::ValueTooLarge,
+            ));
+        }
+
+        let packed =
+            ((x as i64 & 0x3FF_FFFF) << 38) | ((z as i64 & 0x3FF_FFFF) << 12) | (y as i64 & 0xFFF);
+        Ok(Self {
+            x,
+            y,
+            z,
+            bytes: packed.to_be_bytes(),
+        })
+    }
+
+    fn get_value(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A rotation, in steps of 1/256 of a full turn. Used for the yaw and pitch of the entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Angle {
+    bytes: [u8; 1],
+}
+
+impl Angle {
+    /// The angle of a byte of the protocol.
+    pub fn from_byte(byte: u8) -> Self {
+        Self { bytes: [byte] }
+    }
+}
+
+impl Encodable for Angle {
+    /// In degrees.
+    type ValueInput = f32;
+    /// In degrees, in `[0, 360)`.
+    type ValueOutput = f32;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: first_bytes(bytes, DataType::Angle)?,
+        })
+    }
+
+    /// Rounds to the nearest step, wrapping around: -90° is 270°, 360° is 0°. Fails with
+    /// `ValueTooLarge` if `value` isn't finite.
+    fn from_value(value: f32) -> Result<Self, CodecError> {
+        if !value.is_finite() {
+            return Err(CodecError::Encoding(
+                DataType::Angle,
+                ErrorReason::ValueTooLarge,
+            ));
+        }
+        let steps = (value as f64 / 360.0 * 256.0).round();
+        Ok(Self::from_byte(steps.rem_euclid(256.0) as u8))
+    }
+
+    fn get_value(&self) -> f32 {
+        self.bytes[0] as f32 * 360.0 / 256.0
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A namespaced key, like `minecraft:stone`, sent as a string. The namespace defaults to
+/// `minecraft`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifier {
+    /// `namespace:path`
+    value: String,
+    bytes: Vec<u8>,
+}
+
+impl Identifier {
+    /// Adds the default namespace to `value` if it has none, and checks that the namespace matches
+    /// `[a-z0-9._-]+` and the path `[a-z0-9/._-]+`.
+    fn normalize(value: &str) -> Result<String, ErrorReason> {
+        let (namespace, path) = value.split_once(':').unwrap_or(("minecraft", value));
+
+        let is_valid = |part: &str, extra: &[char]| {
+            !part.is_empty()
+                && part.chars().all(|c| {
+                    c.is_ascii_lowercase()
+                        || c.is_ascii_digit()
+                        || "._-".contains(c)
+                        || extra.contains(&c)
+                })
+        };
+        if !is_valid(namespace, &[]) {
+            return Err(ErrorReason::InvalidFormat(
+                "the namespace must match [a-z0-9._-]+",
+            ));
+        }
+        if !is_valid(path, &['/']) {
+            return Err(ErrorReason::InvalidFormat(
+                "the path must match [a-z0-9/._-]+",
+            ));
+        }
+        Ok(format!("{namespace}:{path}"))
+    }
+}
+
+impl Encodable for Identifier {
+    type ValueInput = String;
+    /// `namespace:path`
+    type ValueOutput = String;
+
+    /// Fails with `InvalidFormat` if the identifier is malformed.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (read, size) = string::read(bytes)?;
+        let value = Self::normalize(&read)
+            .map_err(|reason| CodecError::Decoding(DataType::Identifier, reason))?;
+        Ok(Self {
+            value,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    /// Fails with `InvalidFormat` if the identifier is malformed.
+    fn from_value(value: String) -> Result<Self, CodecError> {
+        let value = Self::normalize(&value)
+            .map_err(|reason| CodecError::Encoding(DataType::Identifier, reason))?;
+        Ok(Self {
+            bytes: string::write(&value)?,
+            value,
+        })
+    }
+
+    fn get_value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A string, prefixed with its size in bytes as a VarInt. See `string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringProtocol {
+    value: String,
+    bytes: Vec<u8>,
+}
+
+impl Encodable for StringProtocol {
+    type ValueInput = String;
+    type ValueOutput = String;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (value, size) = string::read(bytes)?;
+        Ok(Self {
+            value,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(value: String) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: string::write(&value)?,
+            value,
+        })
+    }
+
+    fn get_value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Elements of the same type, prefixed with their number as a VarInt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixedArray<T: Encodable> {
+    items: Vec<T>,
+    bytes: Vec<u8>,
+}
+
+impl<T: Encodable> PrefixedArray<T> {
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T: Encodable> Encodable for PrefixedArray<T> {
+    type ValueInput = Vec<T>;
+    type ValueOutput = Vec<T::ValueOutput>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (count, count_size) = varint::read(bytes)?;
+        let count = usize::try_from(count).map_err(|_| {
+            CodecError::Decoding(DataType::PrefixedArray, ErrorReason::ValueTooSmall)
+        })?;
+
+        let mut rest = &bytes[count_size..];
+        // The count comes from the client: it doesn't size the allocation.
+        let mut items = Vec::with_capacity(count.min(rest.len()));
+        for _ in 0..count {
+            items.push(T::consume_from_bytes(&mut rest)?);
+        }
+
+        let size = bytes.len() - rest.len();
+        Ok(Self {
+            items,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(items: Vec<T>) -> Result<Self, CodecError> {
+        let count = i32::try_from(items.len()).map_err(|_| {
+            CodecError::Encoding(DataType::PrefixedArray, ErrorReason::ValueTooLarge)
+        })?;
+
+        let mut bytes = varint::write(count);
+        for item in &items {
+            bytes.extend_from_slice(item.get_bytes());
+        }
+        Ok(Self { items, bytes })
+    }
+
+    fn get_value(&self) -> Vec<T::ValueOutput> {
+        self.items.iter().map(Encodable::get_value).collect()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A value that may be absent, prefixed with a Boolean telling whether it's there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optional<T: Encodable> {
+    value: Option<T>,
+    bytes: Vec<u8>,
+}
+
+impl<T: Encodable> Optional<T> {
+    pub fn inner(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+}
+
+impl<T: Encodable> Encodable for Optional<T> {
+    type ValueInput = Option<T>;
+    type ValueOutput = Option<T::ValueOutput>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let value = match bytes.first() {
+            Some(0x00) => None,
+            Some(0x01) => Some(T::from_bytes(&bytes[1..])?),
+            Some(_) => {
+                return Err(CodecError::Decoding(
+                    DataType::Optional,
+                    ErrorReason::InvalidFormat("the presence flag must be 0 or 1"),
+                ))
+            }
+            None => {
+                return Err(CodecError::Decoding(
+                    DataType::Optional,
+                    ErrorReason::ValueTooSmall,
+                ))
+            }
+        };
+
+        let size = 1 + value.as_ref().map_or(0, Encodable::len);
+        Ok(Self {
+            value,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(value: Option<T>) -> Result<Self, CodecError> {
+        let bytes = match &value {
+            Some(inner) => [&[0x01], inner.get_bytes()].concat(),
+            None => vec![0x00],
+        };
+        Ok(Self { value, bytes })
+    }
+
+    fn get_value(&self) -> Option<T::ValueOutput> {
+        self.value.as_ref().map(Encodable::get_value)
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// An NBT root Compound, sent without its name like in every packet since 1.20.2. A lone TAG_End
+/// (`0x00`) root means "no NBT" and is decoded as an empty Compound.
+///
+/// Decoding fails with `ValueTooLarge` if the NBT exceeds the default limits, and with
+/// `InvalidFormat` if it's malformed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nbt {
+    blob: nbt::Blob,
+    bytes: Vec<u8>,
+}
+
+impl Nbt {
+    /// The TAG_End root, sent instead of a Compound when there's no NBT.
+    const END_ROOT: u8 = 0x00;
+
+    fn decoding_error(error: RegionError) -> CodecError {
+        let reason = match error {
+            RegionError::NbtTooLarge(..) | RegionError::NbtTooDeep(_) => ErrorReason::ValueTooLarge,
+            RegionError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                ErrorReason::ValueTooSmall
+            }
+            _ => ErrorReason::InvalidFormat("malformed NBT"),
+        };
+        CodecError::Decoding(DataType::Nbt, reason)
+    }
+}
+
+impl Encodable for Nbt {
+    type ValueInput = nbt::Blob;
+    type ValueOutput = nbt::Blob;
+
+    /// The NBT is checked against the default `NbtLimits` before being parsed, as it may come from
+    /// a client.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.first() == Some(&Self::END_ROOT) {
+            return Ok(Self {
+                blob: nbt::Blob::new(),
+                bytes: vec![Self::END_ROOT],
+            });
+        }
+
+        let size = NbtLimits::default()
+            .check_network(bytes)
+            .map_err(Self::decoding_error)?;
+
+        // The parser expects a name: an empty one.
+        let named = [&[bytes[0], 0, 0], &bytes[1..size]].concat();
+        let blob = nbt::Blob::from_reader(&mut named.as_slice())
+            .map_err(|e| Self::decoding_error(e.into()))?;
+        Ok(Self {
+            blob,
+            bytes: bytes[..size].to_vec(),
+        })
+    }
+
+    fn from_value(blob: nbt::Blob) -> Result<Self, CodecError> {
+        let mut named = Vec::new();
+        blob.to_writer(&mut named).map_err(|_| {
+            CodecError::Encoding(DataType::Nbt, ErrorReason::InvalidFormat("unwritable NBT"))
+        })?;
+
+        // Removes the name: its length (2 bytes) and the name itself.
+        let name_length = u16::from_be_bytes([named[1], named[2]]) as usize;
+        named.drain(1..3 + name_length);
+        Ok(Self { blob, bytes: named })
+    }
+
+    fn get_value(&self) -> nbt::Blob {
+        self.blob.clone()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Bytes whose length is known from the context, usually the rest of the packet. It has no length
+/// prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteArray {
+    bytes: Vec<u8>,
+}
+
+impl Encodable for ByteArray {
+    type ValueInput = Vec<u8>;
+    type ValueOutput = Vec<u8>;
+
+    /// Takes every byte.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    fn from_value(value: Vec<u8>) -> Result<Self, CodecError> {
+        Ok(Self { bytes: value })
+    }
+
+    fn get_value(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
 }
 
 /// Implementation of the String(https://wiki.vg/Protocol#Type:String).
@@ -741,4 +1563,508 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_unsigned_short_roundtrip() {
+        for value in [0, 1, 255, 256, 25565, u16::MAX] {
+            let encoded = UnsignedShort::from_value(value).unwrap();
+            assert_eq!(encoded.get_bytes(), value.to_be_bytes());
+            assert_eq!(encoded.len(), 2);
+
+            let decoded = UnsignedShort::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value(), value);
+        }
+    }
+
+    #[test]
+    fn test_unsigned_short_consume() {
+        let mut bytes: &[u8] = &[0x63, 0xDD, 0x01];
+        let port = UnsignedShort::consume_from_bytes(&mut bytes).unwrap();
+        assert_eq!(port.get_value(), 25565);
+        assert_eq!(bytes, &[0x01]);
+
+        let mut truncated: &[u8] = &[0x63];
+        assert_eq!(
+            UnsignedShort::consume_from_bytes(&mut truncated),
+            Err(CodecError::Decoding(
+                DataType::UnsignedShort,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+        assert_eq!(truncated, &[0x63]);
+    }
+
+    #[test]
+    fn test_long_roundtrip() {
+        for value in [0, 1, -1, 255, 256, 1_700_000_000_000, i64::MIN, i64::MAX] {
+            let encoded = Long::from_value(value).unwrap();
+            assert_eq!(encoded.get_bytes(), value.to_be_bytes());
+            assert_eq!(encoded.len(), 8);
+
+            let decoded = Long::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value(), value);
+        }
+
+        assert_eq!(
+            Long::from_bytes(&[0; 7]),
+            Err(CodecError::Decoding(
+                DataType::Long,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_int_roundtrip() {
+        for value in [0, 1, -1, 25565, i32::MIN, i32::MAX] {
+            let encoded = Int::from_value(value).unwrap();
+            assert_eq!(encoded.get_bytes(), value.to_be_bytes());
+
+            let decoded = Int::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value(), value);
+        }
+
+        assert_eq!(
+            Int::from_bytes(&[0; 3]),
+            Err(CodecError::Decoding(
+                DataType::Int,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_float_roundtrip() {
+        let values = [
+            0.0,
+            -0.0,
+            1.5,
+            -20.0,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::MIN_POSITIVE / 2.0,
+            f32::NAN,
+            f32::from_bits(0x7FC0_1234),
+        ];
+        for value in values {
+            let encoded = Float::from_value(value).unwrap();
+            assert_eq!(encoded.get_bytes(), value.to_be_bytes());
+
+            let decoded = Float::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value().to_bits(), value.to_bits());
+        }
+
+        assert_eq!(
+            Float::from_bytes(&[0; 3]),
+            Err(CodecError::Decoding(
+                DataType::Float,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_double_roundtrip() {
+        let values = [
+            0.0,
+            -0.0,
+            64.5,
+            -1e300,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN_POSITIVE / 2.0,
+            f64::NAN,
+            f64::from_bits(0x7FF8_0000_0000_1234),
+        ];
+        for value in values {
+            let encoded = Double::from_value(value).unwrap();
+            assert_eq!(encoded.get_bytes(), value.to_be_bytes());
+            assert_eq!(encoded.len(), 8);
+
+            let decoded = Double::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value().to_bits(), value.to_bits());
+        }
+
+        assert_eq!(
+            Double::from_bytes(&[0; 7]),
+            Err(CodecError::Decoding(
+                DataType::Double,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_position_roundtrip() {
+        let values = [
+            (0, 0, 0),
+            (1, 2, 3),
+            (-1, -1, -1),
+            (18357644, 831, -20882616),
+            (-33554432, -2048, -33554432),
+            (33554431, 2047, 33554431),
+            (-33554431, -64, 12),
+        ];
+        for value in values {
+            let encoded = Position::from_value(value).unwrap();
+            let decoded = Position::from_bytes(encoded.get_bytes()).unwrap();
+            assert_eq!(decoded.get_value(), value);
+            assert_eq!(decoded, encoded);
+        }
+
+        // The example of the protocol documentation.
+        let encoded = Position::from_value((18357644, 831, -20882616)).unwrap();
+        assert_eq!(encoded.get_bytes(), 0x4607_632C_15B4_833F_u64.to_be_bytes());
+
+        assert_eq!(
+            Position::from_bytes(&[0; 7]),
+            Err(CodecError::Decoding(
+                DataType::Position,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_position_out_of_range() {
+        for value in [
+            (-33554433, 0, 0),
+            (0, 0, -33554433),
+            (33554432, 0, 0),
+            (0, 2048, 0),
+            (0, -2049, 0),
+            (i32::MIN, i32::MIN, i32::MIN),
+        ] {
+            assert_eq!(
+                Position::from_value(value),
+                Err(CodecError::Encoding(
+                    DataType::Position,
+                    ErrorReason::ValueTooLarge
+                )),
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_angle() {
+        for (degrees, byte) in [
+            (0.0, 0),
+            (90.0, 64),
+            (180.0, 128),
+            (270.0, 192),
+            (-90.0, 192),
+            (360.0, 0),
+            (719.0, 255),
+            (1.0, 1),
+            (0.5, 0),
+        ] {
+            let angle = Angle::from_value(degrees).unwrap();
+            assert_eq!(angle.get_bytes(), [byte], "{degrees}°");
+        }
+
+        for byte in [0, 1, 64, 128, 255] {
+            let angle = Angle::from_byte(byte);
+            assert!((0.0..360.0).contains(&angle.get_value()));
+            assert_eq!(Angle::from_value(angle.get_value()).unwrap(), angle);
+            assert_eq!(Angle::from_bytes(&[byte]).unwrap(), angle);
+        }
+        assert_eq!(Angle::from_byte(64).get_value(), 90.0);
+
+        assert_eq!(
+            Angle::from_value(f32::NAN),
+            Err(CodecError::Encoding(
+                DataType::Angle,
+                ErrorReason::ValueTooLarge
+            ))
+        );
+        assert_eq!(
+            Angle::from_bytes(&[]),
+            Err(CodecError::Decoding(
+                DataType::Angle,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_identifier() {
+        for (value, normalized) in [
+            ("minecraft:stone", "minecraft:stone"),
+            ("stone", "minecraft:stone"),
+            (
+                "cactus:worldgen/biome/dry_1.2-b",
+                "cactus:worldgen/biome/dry_1.2-b",
+            ),
+        ] {
+            let identifier = Identifier::from_value(value.to_string()).unwrap();
+            assert_eq!(identifier.get_value(), normalized);
+            assert_eq!(identifier.get_bytes(), string::write(normalized).unwrap());
+
+            let mut bytes = identifier.get_bytes().to_vec();
+            bytes.push(0xFF);
+            let decoded = Identifier::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, identifier);
+        }
+
+        for invalid in [
+            "Minecraft:stone",
+            "minecraft:Stone",
+            ":stone",
+            "minecraft:",
+            "",
+            "my namespace:stone",
+            "mine/craft:stone",
+            "minecraft:stone:slab",
+        ] {
+            assert!(
+                matches!(
+                    Identifier::from_value(invalid.to_string()),
+                    Err(CodecError::Encoding(
+                        DataType::Identifier,
+                        ErrorReason::InvalidFormat(_)
+                    ))
+                ),
+                "{invalid}"
+            );
+        }
+
+        let bytes = string::write("minecraft:Stone").unwrap();
+        assert!(matches!(
+            Identifier::from_bytes(&bytes),
+            Err(CodecError::Decoding(
+                DataType::Identifier,
+                ErrorReason::InvalidFormat(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_prefixed_array_roundtrip() {
+        let values = ["textures", "", "Cactus 🌵"];
+        let strings = values
+            .iter()
+            .map(|value| StringProtocol::from_value(value.to_string()).unwrap())
+            .collect();
+        let array = PrefixedArray::<StringProtocol>::from_value(strings).unwrap();
+
+        let mut expected = vec![3];
+        for value in values {
+            expected.extend(string::write(value).unwrap());
+        }
+        assert_eq!(array.get_bytes(), expected);
+        assert_eq!(array.get_value(), values);
+
+        let mut bytes = array.get_bytes().to_vec();
+        bytes.push(0xFF);
+        let mut rest: &[u8] = &bytes;
+        let decoded = PrefixedArray::<StringProtocol>::consume_from_bytes(&mut rest).unwrap();
+        assert_eq!(decoded, array);
+        assert_eq!(decoded.items().len(), 3);
+        assert_eq!(rest, [0xFF]);
+
+        let empty = PrefixedArray::<Long>::from_value(Vec::new()).unwrap();
+        assert_eq!(empty.get_bytes(), [0]);
+    }
+
+    #[test]
+    fn test_prefixed_array_invalid() {
+        // 2 Longs announced, only one sent.
+        let mut bytes = vec![2];
+        bytes.extend(42i64.to_be_bytes());
+        assert_eq!(
+            PrefixedArray::<Long>::from_bytes(&bytes),
+            Err(CodecError::Decoding(
+                DataType::Long,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+
+        let negative = varint::write(-1);
+        assert_eq!(
+            PrefixedArray::<Long>::from_bytes(&negative),
+            Err(CodecError::Decoding(
+                DataType::PrefixedArray,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_optional() {
+        let present = Optional::from_value(Some(Int::from_value(-2).unwrap())).unwrap();
+        assert_eq!(present.get_bytes(), [0x01, 0xFF, 0xFF, 0xFF, 0xFE]);
+        assert_eq!(present.get_value(), Some(-2));
+
+        let absent = Optional::<Int>::from_value(None).unwrap();
+        assert_eq!(absent.get_bytes(), [0x00]);
+        assert_eq!(absent.get_value(), None);
+
+        let mut bytes: &[u8] = &[0x01, 0, 0, 0, 7, 0x00, 0x2A];
+        let first = Optional::<Int>::consume_from_bytes(&mut bytes).unwrap();
+        assert_eq!(first.get_value(), Some(7));
+        assert_eq!(first.inner(), Some(&Int::from_value(7).unwrap()));
+        assert_eq!(bytes, [0x00, 0x2A]);
+
+        let second = Optional::<Int>::consume_from_bytes(&mut bytes).unwrap();
+        assert_eq!(second, absent);
+        assert_eq!(bytes, [0x2A]);
+    }
+
+    #[test]
+    fn test_optional_invalid() {
+        assert!(matches!(
+            Optional::<Int>::from_bytes(&[0x2A]),
+            Err(CodecError::Decoding(
+                DataType::Optional,
+                ErrorReason::InvalidFormat(_)
+            ))
+        ));
+        assert_eq!(
+            Optional::<Int>::from_bytes(&[]),
+            Err(CodecError::Decoding(
+                DataType::Optional,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+        // Present, but truncated.
+        assert_eq!(
+            Optional::<Int>::from_bytes(&[0x01, 0]),
+            Err(CodecError::Decoding(
+                DataType::Int,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_nbt_roundtrip() {
+        let mut blob = nbt::Blob::new();
+        blob.insert("name", "Cactus").unwrap();
+        blob.insert("height", nbt::Value::Int(3)).unwrap();
+
+        let encoded = Nbt::from_value(blob.clone()).unwrap();
+        // Compound, no name, then the String "name" and the Int "height" in any order.
+        assert_eq!(encoded.get_bytes()[0], 0x0a);
+        assert!([0x03, 0x08].contains(&encoded.get_bytes()[1]));
+        assert_eq!(encoded.get_bytes().last(), Some(&0x00));
+        assert_eq!(encoded.len(), 1 + (3 + 4 + 8) + (3 + 6 + 4) + 1);
+
+        let mut bytes = encoded.get_bytes().to_vec();
+        bytes.push(0xFF);
+        let mut rest: &[u8] = &bytes;
+        let decoded = Nbt::consume_from_bytes(&mut rest).unwrap();
+        assert_eq!(decoded.get_value(), blob);
+        assert_eq!(rest, [0xFF]);
+
+        // Named roots aren't network NBT.
+        let named = Nbt::from_value(nbt::Blob::named("root")).unwrap();
+        assert_eq!(named.get_bytes(), [0x0a, 0x00]);
+    }
+
+    #[test]
+    fn test_nbt_end_root() {
+        let mut rest: &[u8] = &[0x00, 0xFF];
+        let decoded = Nbt::consume_from_bytes(&mut rest).unwrap();
+        assert_eq!(decoded.get_value(), nbt::Blob::new());
+        assert_eq!(decoded.get_bytes(), [0x00]);
+        assert_eq!(rest, [0xFF]);
+    }
+
+    #[test]
+    fn test_nbt_invalid() {
+        assert_eq!(
+            Nbt::from_bytes(&[0x0a, 0x01, 0x00]),
+            Err(CodecError::Decoding(
+                DataType::Nbt,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+        assert!(matches!(
+            Nbt::from_bytes(&[0x08, 0x00, 0x00]),
+            Err(CodecError::Decoding(
+                DataType::Nbt,
+                ErrorReason::InvalidFormat(_)
+            ))
+        ));
+
+        // Compounds nested deeper than the limit.
+        let mut deep = vec![0x0a];
+        for _ in 0..1000 {
+            deep.extend([0x0a, 0, 0]);
+        }
+        deep.extend(vec![0; 1001]);
+        assert_eq!(
+            Nbt::from_bytes(&deep),
+            Err(CodecError::Decoding(
+                DataType::Nbt,
+                ErrorReason::ValueTooLarge
+            ))
+        );
+    }
+
+    #[test]
+    fn test_var_int_boolean_uuid() {
+        let var_int = VarInt::from_value(-1).unwrap();
+        assert_eq!(var_int.get_bytes(), [0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert_eq!(
+            VarInt::from_bytes(&[0xdd, 0xc7, 0x01, 0x2a])
+                .unwrap()
+                .get_value(),
+            25565
+        );
+        // Cut in the middle.
+        assert_eq!(
+            VarInt::from_bytes(&[0xdd, 0xc7]),
+            Err(CodecError::Decoding(
+                DataType::VarInt,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+
+        assert!(Boolean::from_bytes(&[0x01]).unwrap().get_value());
+        assert!(!Boolean::from_value(false).unwrap().get_value());
+        assert!(matches!(
+            Boolean::from_bytes(&[0x02]),
+            Err(CodecError::Decoding(
+                DataType::Boolean,
+                ErrorReason::InvalidFormat(_)
+            ))
+        ));
+
+        let uuid = 0x5627dd98e6be3c21b8a8e92344183641;
+        let encoded = Uuid::from_value(uuid).unwrap();
+        assert_eq!(
+            Uuid::from_bytes(encoded.get_bytes()).unwrap().get_value(),
+            uuid
+        );
+        assert_eq!(
+            Uuid::from_bytes(&encoded.get_bytes()[..15]),
+            Err(CodecError::Decoding(
+                DataType::Uuid,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let empty = ByteArray::from_bytes(&[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+
+        let payload = ByteArray::from_value(vec![1, 2, 3]).unwrap();
+        assert!(!payload.is_empty());
+        assert_eq!(payload.len(), 3);
+
+        assert!(!UnsignedShort::from_value(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_byte_array_consume() {
+        let mut bytes: &[u8] = &[1, 2, 3];
+        let array = ByteArray::consume_from_bytes(&mut bytes).unwrap();
+        assert_eq!(array.get_value(), vec![1, 2, 3]);
+        assert!(bytes.is_empty());
+    }
 }
@@ -1,14 +1,17 @@
 //! This module abstracts away a Minecraft packet, so that it can be used in a simple and
 //! standardized way.
 
+pub mod chat;
 pub mod data_types;
+pub mod packet_types;
+pub mod reader;
 pub mod utils;
 
 use core::fmt;
 use std::{collections::VecDeque, fmt::Debug};
 
 use bytes::BytesMut;
-use data_types::varint;
+use data_types::{varint, Encodable};
 use log::warn;
 use thiserror::Error;
 
@@ -57,6 +60,21 @@ impl Packet {
         })
     }
 
+    /// Like `new`, but fails if the declared length isn't the number of bytes of the ID and the
+    /// payload. For the bytes received from a client, where a wrong length means a framing error
+    /// or a malicious client.
+    pub fn new_checked<T: AsRef<[u8]>>(data: T) -> Result<Self, PacketError> {
+        let packet = Self::new(data)?;
+        let actual = packet.id.len() + packet.payload.len();
+        if packet.length != actual {
+            return Err(PacketError::BuildPacket(format!(
+                "declared length {} but got {actual} bytes of ID and payload",
+                packet.length
+            )));
+        }
+        Ok(packet)
+    }
+
     /// This is the WHOLE packet.
     pub fn get_full_packet(&self) -> &[u8] {
         &self.data
@@ -259,6 +277,9 @@ pub enum PacketError {
 
     #[error("Failed to decode from the payload: {0}")]
     PayloadDecodeError(String),
+
+    #[error("Failed to decode from the payload: {0}")]
+    Codec(#[from] data_types::CodecError),
 }
 
 /// Represents the different actions that the PacketBuilder will do to construct the packet payload.
@@ -266,11 +287,26 @@ pub enum BuildAction {
     /// Appends raw bytes to the packet payload.
     AppendBytes(Vec<u8>),
 
-    /// Appends an integer as a VarInt to the packet payload.
-    AppendVarInt(i32),
+    /// A value that couldn't be encoded, `build` fails with it.
+    EncodingError(data_types::CodecError),
+
+    /// Appends a boolean as a byte to the packet payload.
+    AppendBool(bool),
+
+    /// Appends a big-endian i64 to the packet payload.
+    AppendLong(i64),
+
+    /// Appends a big-endian u16 to the packet payload.
+    AppendUnsignedShort(u16),
 
-    /// Appends a UTF-8 string to the packet payload.
-    AppendString(String),
+    /// Appends a UUID, as a big-endian u128, to the packet payload.
+    AppendUuid(u128),
+
+    /// Appends a big-endian f64 to the packet payload.
+    AppendDouble(f64),
+
+    /// Appends a big-endian f32 to the packet payload.
+    AppendFloat(f32),
 }
 
 /// A builder to build a packet.
@@ -294,14 +330,25 @@ impl PacketBuilder {
         for action in &self.actions {
             match action {
                 BuildAction::AppendBytes(bytes) => payload.extend_from_slice(bytes),
-                BuildAction::AppendVarInt(value) => {
-                    let varint = data_types::varint::write(*value);
-                    payload.extend_from_slice(&varint);
+                BuildAction::EncodingError(err) => {
+                    return Err(PacketError::BuildPacket(err.to_string()))
+                }
+                BuildAction::AppendBool(value) => {
+                    payload.extend_from_slice(encode::<data_types::Boolean>(*value)?.get_bytes())
+                }
+                BuildAction::AppendLong(value) => {
+                    payload.extend_from_slice(encode::<data_types::Long>(*value)?.get_bytes())
                 }
-                BuildAction::AppendString(string) => {
-                    let string_bytes = data_types::string::write(string)
-                        .map_err(|err| PacketError::BuildPacket(err.to_string()))?;
-                    payload.extend_from_slice(&string_bytes);
+                BuildAction::AppendUnsignedShort(value) => payload
+                    .extend_from_slice(encode::<data_types::UnsignedShort>(*value)?.get_bytes()),
+                BuildAction::AppendUuid(value) => {
+                    payload.extend_from_slice(encode::<data_types::Uuid>(*value)?.get_bytes())
+                }
+                BuildAction::AppendDouble(value) => {
+                    payload.extend_from_slice(encode::<data_types::Double>(*value)?.get_bytes())
+                }
+                BuildAction::AppendFloat(value) => {
+                    payload.extend_from_slice(encode::<data_types::Float>(*value)?.get_bytes())
                 }
             }
         }
@@ -329,18 +376,73 @@ impl PacketBuilder {
         self
     }
 
+    /// Appends the bytes of any data type to the back of the packet payload.
+    pub fn append<E: Encodable>(&mut self, value: E) -> &mut Self {
+        self.append_bytes(value.get_bytes())
+    }
+
+    /// Appends `value` encoded as `E`, or makes `build` fail if it can't be encoded.
+    fn append_value<E: Encodable>(&mut self, value: E::ValueInput) -> &mut Self {
+        match E::from_value(value) {
+            Ok(encoded) => self.append(encoded),
+            Err(err) => {
+                self.actions.push_back(BuildAction::EncodingError(err));
+                self
+            }
+        }
+    }
+
     /// Appends `value` as a VarInt to the back of the packet payload.
     pub fn append_varint(&mut self, value: i32) -> &mut Self {
-        self.actions.push_back(BuildAction::AppendVarInt(value));
-        self
+        self.append_value::<data_types::VarInt>(value)
     }
 
     /// Appends `string` as a String to the back of the packet payload.
     pub fn append_string<T: AsRef<str>>(&mut self, string: T) -> &mut Self {
+        self.append_value::<data_types::StringProtocol>(string.as_ref().to_string())
+    }
+
+    /// Appends `value` as a Boolean to the back of the packet payload.
+    pub fn append_bool(&mut self, value: bool) -> &mut Self {
+        self.actions.push_back(BuildAction::AppendBool(value));
+        self
+    }
+
+    /// Appends `value` as a Long to the back of the packet payload.
+    pub fn append_long(&mut self, value: i64) -> &mut Self {
+        self.actions.push_back(BuildAction::AppendLong(value));
+        self
+    }
+
+    /// Appends `value` as an Unsigned Short to the back of the packet payload.
+    pub fn append_ushort(&mut self, value: u16) -> &mut Self {
         self.actions
-            .push_back(BuildAction::AppendString(string.as_ref().to_string()));
+            .push_back(BuildAction::AppendUnsignedShort(value));
         self
     }
+
+    /// Appends `value` as a UUID to the back of the packet payload.
+    pub fn append_uuid(&mut self, value: u128) -> &mut Self {
+        self.actions.push_back(BuildAction::AppendUuid(value));
+        self
+    }
+
+    /// Appends `value` as a Double to the back of the packet payload.
+    pub fn append_double(&mut self, value: f64) -> &mut Self {
+        self.actions.push_back(BuildAction::AppendDouble(value));
+        self
+    }
+
+    /// Appends `value` as a Float to the back of the packet payload.
+    pub fn append_float(&mut self, value: f32) -> &mut Self {
+        self.actions.push_back(BuildAction::AppendFloat(value));
+        self
+    }
+}
+
+/// Encodes `value` as the data type `E`, for `PacketBuilder::build`.
+fn encode<E: Encodable>(value: E::ValueInput) -> Result<E, PacketError> {
+    E::from_value(value).map_err(|err| PacketError::BuildPacket(err.to_string()))
 }
 
 // TODO: I wonder if having "invalid" value, like a too short/long Length should propagate an error
@@ -422,6 +524,28 @@ mod tests {
         assert_eq!(packet.get_full_packet(), init_data);
     }
 
+    #[test]
+    fn test_packet_creation_checked() {
+        assert!(Packet::new_checked([4, 4, 1, 2, 3]).is_ok());
+        assert!(matches!(
+            Packet::new_checked([1, 4, 1, 2, 3]),
+            Err(PacketError::BuildPacket(_))
+        ));
+
+        let mut too_long = varint::write(2048);
+        too_long.extend([4, 1, 2, 3]);
+        assert!(matches!(
+            Packet::new_checked(too_long),
+            Err(PacketError::BuildPacket(_))
+        ));
+
+        // Multi-byte ID
+        let mut data = varint::write(3);
+        data.extend(varint::write(1000));
+        data.push(7);
+        assert_eq!(Packet::new_checked(data).unwrap().get_payload(), [7]);
+    }
+
     #[test]
     fn test_packet_creation_invalid_length_too_long() {
         // Length = 2048
@@ -489,4 +613,92 @@ mod tests {
         assert_eq!(packet.get_full_packet(), init_data);
         assert_eq!(packet.len(), init_data.len());
     }
+
+    #[test]
+    fn test_builder_typed_appends() {
+        let packet = PacketBuilder::new()
+            .append_bool(true)
+            .append_ushort(25565)
+            .append_long(-2)
+            .append_uuid(0x5627dd98e6be3c21b8a8e92344183641)
+            .append_float(1.5)
+            .append_double(-0.5)
+            .append_varint(300)
+            .build(0x2A)
+            .unwrap();
+
+        let mut expected = vec![0x01, 0x63, 0xDD];
+        expected.extend([0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]);
+        expected.extend([
+            0x56, 0x27, 0xDD, 0x98, 0xE6, 0xBE, 0x3C, 0x21, 0xB8, 0xA8, 0xE9, 0x23, 0x44, 0x18,
+            0x36, 0x41,
+        ]);
+        expected.extend([0x3F, 0xC0, 0x00, 0x00]);
+        expected.extend([0xBF, 0xE0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expected.extend([0xAC, 0x02]);
+
+        assert_eq!(packet.get_id().get_value(), 0x2A);
+        assert_eq!(packet.get_payload(), expected);
+        assert_eq!(packet.get_length(), 1 + expected.len());
+    }
+
+    /// A data type the builder doesn't know about: a 24-bit unsigned integer.
+    struct U24 {
+        bytes: [u8; 3],
+    }
+
+    impl Encodable for U24 {
+        type ValueInput = u32;
+        type ValueOutput = u32;
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self, data_types::CodecError> {
+            match bytes {
+                [a, b, c, ..] => Ok(Self {
+                    bytes: [*a, *b, *c],
+                }),
+                _ => Err(data_types::CodecError::DecodeVarIntEmpty),
+            }
+        }
+
+        fn from_value(value: u32) -> Result<Self, data_types::CodecError> {
+            let [_, a, b, c] = value.to_be_bytes();
+            Ok(Self { bytes: [a, b, c] })
+        }
+
+        fn get_value(&self) -> u32 {
+            u32::from_be_bytes([0, self.bytes[0], self.bytes[1], self.bytes[2]])
+        }
+
+        fn get_bytes(&self) -> &[u8] {
+            &self.bytes
+        }
+    }
+
+    #[test]
+    fn test_builder_append_encodable() {
+        let packet = PacketBuilder::new()
+            .append(U24::from_value(0x0A0B0C).unwrap())
+            .append(data_types::Position::from_value((1, 2, 3)).unwrap())
+            .append_string("hi")
+            .append_varint(-1)
+            .build(0x01)
+            .unwrap();
+
+        let mut expected = vec![0x0A, 0x0B, 0x0C];
+        expected.extend(
+            data_types::Position::from_value((1, 2, 3))
+                .unwrap()
+                .get_bytes(),
+        );
+        expected.extend([0x02, b'h', b'i']);
+        expected.extend([0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(packet.get_payload(), expected);
+
+        // The encoding errors surface when building.
+        let too_long = "a".repeat(40_000);
+        assert!(matches!(
+            PacketBuilder::new().append_string(too_long).build(0x01),
+            Err(PacketError::BuildPacket(_))
+        ));
+    }
 }
@@ -0,0 +1,874 @@
+//! Typed representations of the packets sent and received by the server.
+use super::reader::PacketReader;
+use super::{Packet, PacketBuilder, PacketError};
+use crate::net::packet::chat::TextComponent;
+use crate::net::packet::data_types::{string, varint, varlong, ByteArray, Encodable, Float};
+use crate::world::border::{WorldBorder, DEFAULT_WARNING_BLOCKS, DEFAULT_WARNING_TIME};
+
+/// A packet that the server can build and send to the client.
+pub trait EncodablePacket {
+    /// The ID of the packet in its connection state.
+    const PACKET_ID: i32;
+
+    /// Builds the `Packet` ready to be sent.
+    fn build(&self) -> Result<Packet, PacketError>;
+}
+
+/// A packet that the server can parse from what the client sent.
+pub trait DecodablePacket: Sized {
+    /// The ID of the packet in its connection state.
+    const PACKET_ID: i32;
+
+    /// Reads the packet fields from its payload.
+    fn parse(packet: &Packet) -> Result<Self, PacketError>;
+}
+
+/// The state the client wants to switch to after the handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextState {
+    Status = 1,
+    Login = 2,
+    Transfer = 3,
+}
+
+impl TryFrom<i32> for NextState {
+    type Error = PacketError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Status),
+            2 => Ok(Self::Login),
+            3 => Ok(Self::Transfer),
+            _ => Err(PacketError::PayloadDecodeError(format!(
+                "invalid next state: {value}"
+            ))),
+        }
+    }
+}
+
+/// Handshake (Handshaking, serverbound). The first packet of every connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handshake {
+    pub protocol_version: i32,
+    /// The hostname or IP the client used to connect.
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: NextState,
+}
+
+impl DecodablePacket for Handshake {
+    const PACKET_ID: i32 = 0x00;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        if packet.get_id().get_value() != Self::PACKET_ID {
+            return Err(PacketError::PayloadDecodeError(format!(
+                "expected a handshake, got packet {:#04X}",
+                packet.get_id().get_value()
+            )));
+        }
+        let mut reader = PacketReader::new(packet.get_payload());
+
+        Ok(Self {
+            protocol_version: reader.read_varint()?,
+            server_address: reader.read_string()?,
+            server_port: reader.read_unsigned_short()?,
+            next_state: NextState::try_from(reader.read_varint()?)?,
+        })
+    }
+}
+
+impl Handshake {
+    /// The fields to log in one line and to feed the metrics. The address is omitted unless
+    /// `log_ips`, since it can tell where the player is.
+    pub fn summary(&self, log_ips: bool) -> HandshakeSummary {
+        HandshakeSummary {
+            protocol_version: self.protocol_version,
+            server_address: Some(self.server_address.clone()).filter(|_| log_ips),
+            server_port: self.server_port,
+            next_state: self.next_state,
+        }
+    }
+}
+
+/// What a handshake tells, see `Handshake::summary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeSummary {
+    pub protocol_version: i32,
+    /// `None` if the IPs aren't logged.
+    pub server_address: Option<String>,
+    pub server_port: u16,
+    pub next_state: NextState,
+}
+
+impl std::fmt::Display for HandshakeSummary {
+    /// `protocol=769 address=localhost port=25565 next_state=Status`, without the address if it
+    /// is omitted.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "protocol={}", self.protocol_version)?;
+        if let Some(address) = &self.server_address {
+            write!(f, " address={address}")?;
+        }
+        write!(
+            f,
+            " port={} next_state={:?}",
+            self.server_port, self.next_state
+        )
+    }
+}
+
+/// Status Response (Status, clientbound). The server list entry as JSON, see
+/// `consts::protocol::status_response_json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusResponse {
+    pub json: String,
+}
+
+impl EncodablePacket for StatusResponse {
+    const PACKET_ID: i32 = 0x00;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_string(&self.json)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Ping Request (Status, serverbound). Any number, usually a timestamp, that the client uses to
+/// measure the latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PingRequest {
+    pub payload: i64,
+}
+
+impl DecodablePacket for PingRequest {
+    const PACKET_ID: i32 = 0x01;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        let mut reader = PacketReader::new(packet.get_payload());
+        let payload = reader.read_long()?;
+        if reader.remaining() != 0 {
+            return Err(PacketError::PayloadDecodeError(
+                "trailing bytes after the payload of the Ping Request".to_string(),
+            ));
+        }
+        Ok(Self { payload })
+    }
+}
+
+/// Pong Response (Status, clientbound). Echoes the payload of the Ping Request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PongResponse {
+    pub payload: i64,
+}
+
+impl From<PingRequest> for PongResponse {
+    fn from(ping: PingRequest) -> Self {
+        Self {
+            payload: ping.payload,
+        }
+    }
+}
+
+impl EncodablePacket for PongResponse {
+    const PACKET_ID: i32 = 0x01;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_long(self.payload)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Login Start (Login, serverbound). The first packet of the login. Since 1.19.3, the chat
+/// signing key is sent later, in Player Session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginStart {
+    pub name: String,
+    pub uuid: u128,
+}
+
+impl DecodablePacket for LoginStart {
+    const PACKET_ID: i32 = 0x00;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        let payload = packet.get_payload();
+
+        let (name, position) = string::read(payload)?;
+        let mut rest = &payload[position..];
+        let uuid = u128::from_be_bytes(take_array(&mut rest, "player UUID")?);
+
+        Ok(Self { name, uuid })
+    }
+}
+
+impl EncodablePacket for LoginStart {
+    const PACKET_ID: i32 = 0x00;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_string(&self.name)
+            .append_uuid(self.uuid)
+            .build(<Self as EncodablePacket>::PACKET_ID)
+    }
+}
+
+/// Disconnect (Login, clientbound). Refuses the login with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginDisconnect {
+    /// The message, see `net::disconnect::DisconnectReason`.
+    pub reason: TextComponent,
+}
+
+impl EncodablePacket for LoginDisconnect {
+    const PACKET_ID: i32 = 0x00;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_string(self.reason.to_json())
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// A property of a player profile, e.g. the skin (`textures`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileProperty {
+    pub name: String,
+    /// Base64.
+    pub value: String,
+    /// Mojang's signature of `value`, only on online servers.
+    pub signature: Option<String>,
+}
+
+/// Login Success (Login, clientbound). Ends the login, the client answers with Login
+/// Acknowledged and enters the Configuration state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginSuccess {
+    pub uuid: u128,
+    pub username: String,
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl LoginSuccess {
+    /// A profile without properties.
+    pub fn from_values(uuid: u128, username: String) -> Self {
+        Self {
+            uuid,
+            username,
+            properties: Vec::new(),
+        }
+    }
+}
+
+impl EncodablePacket for LoginSuccess {
+    const PACKET_ID: i32 = 0x02;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        let mut builder = PacketBuilder::new();
+        builder
+            .append_uuid(self.uuid)
+            .append_string(&self.username)
+            .append_varint(self.properties.len() as i32);
+        for property in &self.properties {
+            builder
+                .append_string(&property.name)
+                .append_string(&property.value)
+                .append_bool(property.signature.is_some());
+            if let Some(signature) = &property.signature {
+                builder.append_string(signature);
+            }
+        }
+        builder.build(Self::PACKET_ID)
+    }
+}
+
+/// Login Acknowledged (Login, serverbound). Answers Login Success, the connection is in the
+/// Configuration state from the next packet on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginAcknowledged;
+
+impl DecodablePacket for LoginAcknowledged {
+    const PACKET_ID: i32 = 0x03;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        if !packet.get_payload().is_empty() {
+            return Err(PacketError::PayloadDecodeError(
+                "Login Acknowledged has no payload".to_string(),
+            ));
+        }
+        Ok(Self)
+    }
+}
+
+/// Disconnect (Configuration, clientbound). Like the login one, with the message as NBT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationDisconnect {
+    pub reason: TextComponent,
+}
+
+impl EncodablePacket for ConfigurationDisconnect {
+    const PACKET_ID: i32 = 0x02;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_bytes(self.reason.to_nbt()?.get_bytes())
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Set Compression (Login, clientbound). The packets of both sides use the compressed format from
+/// the next one on, compressed from `threshold` bytes. Negative disables the compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetCompression {
+    pub threshold: i32,
+}
+
+impl EncodablePacket for SetCompression {
+    const PACKET_ID: i32 = 0x03;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_varint(self.threshold)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Reads `N` bytes and moves `bytes` after them. `field` names them in the error.
+fn take_array<const N: usize>(bytes: &mut &[u8], field: &str) -> Result<[u8; N], PacketError> {
+    let Some((array, rest)) = bytes.split_first_chunk::<N>() else {
+        return Err(PacketError::PayloadDecodeError(format!("missing {field}")));
+    };
+    *bytes = rest;
+    Ok(*array)
+}
+
+/// Login Plugin Request (Login, clientbound). A custom message on a channel, e.g. for proxies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginPluginRequest {
+    /// Chosen by the server, the response has the same one.
+    pub message_id: i32,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl EncodablePacket for LoginPluginRequest {
+    const PACKET_ID: i32 = 0x04;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_varint(self.message_id)
+            .append_string(&self.channel)
+            .append_bytes(&self.data)
+            .build(<Self as EncodablePacket>::PACKET_ID)
+    }
+}
+
+impl DecodablePacket for LoginPluginRequest {
+    const PACKET_ID: i32 = 0x04;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        let payload = packet.get_payload();
+
+        let (message_id, mut position) = varint::read(payload)?;
+        let (channel, read) = string::read(&payload[position..])?;
+        position += read;
+
+        Ok(Self {
+            message_id,
+            channel,
+            data: payload[position..].to_vec(),
+        })
+    }
+}
+
+/// Login Plugin Response (Login, serverbound). The answer to a `LoginPluginRequest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginPluginResponse {
+    pub message_id: i32,
+    /// `None` if the client didn't understand the request.
+    pub data: Option<Vec<u8>>,
+}
+
+impl LoginPluginResponse {
+    /// The response of a client that doesn't know the channel of the request.
+    pub fn not_understood(message_id: i32) -> Self {
+        Self {
+            message_id,
+            data: None,
+        }
+    }
+}
+
+impl EncodablePacket for LoginPluginResponse {
+    const PACKET_ID: i32 = 0x02;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        let mut builder = PacketBuilder::new();
+        builder.append_varint(self.message_id);
+        match &self.data {
+            Some(data) => builder.append_bool(true).append_bytes(data),
+            None => builder.append_bool(false),
+        };
+        builder.build(<Self as EncodablePacket>::PACKET_ID)
+    }
+}
+
+impl DecodablePacket for LoginPluginResponse {
+    const PACKET_ID: i32 = 0x02;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        let payload = packet.get_payload();
+
+        let (message_id, position) = varint::read(payload)?;
+        let successful = payload.get(position).copied();
+        let data = ByteArray::from_bytes(payload.get(position + 1..).unwrap_or_default())?;
+        let data = match successful {
+            Some(0) if data.is_empty() => None,
+            Some(0) => {
+                return Err(PacketError::PayloadDecodeError(
+                    "data in a not understood Login Plugin Response".to_string(),
+                ))
+            }
+            Some(1) => Some(data.get_value()),
+            _ => {
+                return Err(PacketError::PayloadDecodeError(
+                    "invalid Login Plugin Response success flag".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self { message_id, data })
+    }
+}
+
+/// Initialize World Border (Play, clientbound). Sent when a player joins the world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitializeWorldBorder {
+    pub x: f64,
+    pub z: f64,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// Number of real-time milliseconds until `new_diameter` is reached.
+    pub speed: i64,
+    pub portal_teleport_boundary: i32,
+    pub warning_blocks: i32,
+    pub warning_time: i32,
+}
+
+impl InitializeWorldBorder {
+    /// Describes a static border, with the vanilla warning distance and time.
+    pub fn new(border: &WorldBorder) -> Self {
+        let (x, z) = border.get_center();
+        Self {
+            x,
+            z,
+            old_diameter: border.get_diameter(),
+            new_diameter: border.get_diameter(),
+            speed: 0,
+            portal_teleport_boundary: border.get_max_world_size() as i32,
+            warning_blocks: DEFAULT_WARNING_BLOCKS,
+            warning_time: DEFAULT_WARNING_TIME,
+        }
+    }
+}
+
+impl EncodablePacket for InitializeWorldBorder {
+    const PACKET_ID: i32 = 0x26;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_double(self.x)
+            .append_double(self.z)
+            .append_double(self.old_diameter)
+            .append_double(self.new_diameter)
+            .append_bytes(varlong::write(self.speed))
+            .append_varint(self.portal_teleport_boundary)
+            .append_varint(self.warning_blocks)
+            .append_varint(self.warning_time)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// The events that can be sent with a Game Event packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEventKind {
+    NoRespawnBlockAvailable = 0,
+    BeginRaining = 1,
+    EndRaining = 2,
+    ChangeGameMode = 3,
+    WinGame = 4,
+    DemoEvent = 5,
+    ArrowHitPlayer = 6,
+    /// Value: the rain level, from 0 to 1.
+    RainLevelChange = 7,
+    /// Value: the thunder level, from 0 to 1.
+    ThunderLevelChange = 8,
+    PufferfishSting = 9,
+    ElderGuardianAppearance = 10,
+    EnableRespawnScreen = 11,
+    LimitedCrafting = 12,
+    StartWaitingForLevelChunks = 13,
+}
+
+/// Game Event (Play, clientbound). Used for changes in the game state, like the weather.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameEvent {
+    pub event: GameEventKind,
+    pub value: f32,
+}
+
+impl GameEvent {
+    pub fn new(event: GameEventKind, value: f32) -> Self {
+        Self { event, value }
+    }
+
+    /// Must be sent after the Login (play) packet and before the chunks, otherwise the client
+    /// stays on the "Loading terrain..." screen.
+    pub fn start_waiting_for_level_chunks() -> Self {
+        Self::new(GameEventKind::StartWaitingForLevelChunks, 0.0)
+    }
+}
+
+impl EncodablePacket for GameEvent {
+    const PACKET_ID: i32 = 0x23;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_bytes([self.event as u8])
+            .append_float(self.value)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Update Time (Play, clientbound). Synchronizes the time of the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateTime {
+    /// Total number of ticks since the world was created.
+    pub world_age: i64,
+    pub time_of_day: i64,
+    /// Whether the client should advance the time of the day on its own.
+    pub time_of_day_increasing: bool,
+}
+
+impl EncodablePacket for UpdateTime {
+    const PACKET_ID: i32 = 0x6B;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_long(self.world_age)
+            .append_long(self.time_of_day)
+            .append_bool(self.time_of_day_increasing)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Chunk Batch Start (Play, clientbound). Sent before the Chunk Data packets of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBatchStart;
+
+impl EncodablePacket for ChunkBatchStart {
+    const PACKET_ID: i32 = 0x0C;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new().build(Self::PACKET_ID)
+    }
+}
+
+/// Chunk Batch Finished (Play, clientbound). Ends a batch, the client then answers with a Chunk
+/// Batch Received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBatchFinished {
+    /// The number of chunks of the batch.
+    pub batch_size: i32,
+}
+
+impl EncodablePacket for ChunkBatchFinished {
+    const PACKET_ID: i32 = 0x0B;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_varint(self.batch_size)
+            .build(Self::PACKET_ID)
+    }
+}
+
+/// Chunk Batch Received (Play, serverbound). How fast the client processes the chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkBatchReceived {
+    pub chunks_per_tick: f32,
+}
+
+impl DecodablePacket for ChunkBatchReceived {
+    const PACKET_ID: i32 = 0x09;
+
+    fn parse(packet: &Packet) -> Result<Self, PacketError> {
+        let mut reader = PacketReader::new(packet.get_payload());
+        Ok(Self {
+            chunks_per_tick: reader.read::<Float>()?.get_value(),
+        })
+    }
+}
+
+/// Keep Alive (Play, clientbound). The client must answer with the same ID, or it's disconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAlive {
+    pub id: i64,
+}
+
+impl EncodablePacket for KeepAlive {
+    const PACKET_ID: i32 = 0x27;
+
+    fn build(&self) -> Result<Packet, PacketError> {
+        PacketBuilder::new()
+            .append_long(self.id)
+            .build(Self::PACKET_ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::data_types::{string, varint, CodecError, DataType, ErrorReason};
+
+    #[test]
+    fn test_initialize_world_border() {
+        let border = WorldBorder::from_max_world_size(29999984);
+        let packet = InitializeWorldBorder::new(&border).build().unwrap();
+
+        assert_eq!(packet.get_id().get_value(), 0x26);
+
+        let payload = packet.get_payload();
+        assert_eq!(&payload[0..8], &0.0f64.to_be_bytes());
+        assert_eq!(&payload[8..16], &0.0f64.to_be_bytes());
+        assert_eq!(&payload[16..24], &59999968.0f64.to_be_bytes());
+        assert_eq!(&payload[24..32], &59999968.0f64.to_be_bytes());
+        assert_eq!(payload[32], 0); // speed
+
+        let (boundary, read) = varint::read(&payload[33..]).unwrap();
+        assert_eq!(boundary, 29999984);
+        assert_eq!(&payload[33 + read..], &[5, 15]);
+    }
+
+    #[test]
+    fn test_game_event_start_waiting_for_chunks() {
+        let packet = GameEvent::start_waiting_for_level_chunks().build().unwrap();
+
+        assert_eq!(packet.get_id().get_value(), 0x23);
+        assert_eq!(packet.get_payload(), &[13, 0, 0, 0, 0]);
+        assert_eq!(packet.get_full_packet(), &[6, 0x23, 13, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_game_event_rain_level() {
+        let packet = GameEvent::new(GameEventKind::RainLevelChange, 0.5)
+            .build()
+            .unwrap();
+
+        // 0.5f32 is 0x3F000000
+        assert_eq!(packet.get_payload(), &[7, 0x3F, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_handshake_parse() {
+        let packet = Packet::new([
+            16, 0x00, 0x81, 0x06, 9, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', 0x63,
+            0xDD, 0x01,
+        ])
+        .unwrap();
+
+        let handshake = Handshake::parse(&packet).unwrap();
+        assert_eq!(handshake.protocol_version, 769);
+        assert_eq!(handshake.server_address, "localhost");
+        assert_eq!(handshake.server_port, 25565);
+        assert_eq!(handshake.next_state, NextState::Status);
+    }
+
+    #[test]
+    fn test_handshake_parse_invalid() {
+        // Missing the port and the next state
+        let truncated = Packet::new([4, 0x00, 0x81, 0x06, 0]).unwrap();
+        assert!(matches!(
+            Handshake::parse(&truncated),
+            Err(PacketError::Codec(CodecError::Decoding(
+                DataType::UnsignedShort,
+                ErrorReason::ValueTooSmall
+            )))
+        ));
+
+        // Only one byte of the port
+        let truncated = Packet::new([5, 0x00, 0x81, 0x06, 0, 0x63]).unwrap();
+        assert!(Handshake::parse(&truncated).is_err());
+
+        // Missing the next state
+        let truncated = Packet::new([6, 0x00, 0x81, 0x06, 0, 0x63, 0xDD]).unwrap();
+        assert!(matches!(
+            Handshake::parse(&truncated),
+            Err(PacketError::Codec(CodecError::DecodeVarIntEmpty))
+        ));
+
+        let bad_state = Packet::new([7, 0x00, 0x81, 0x06, 0, 0x63, 0xDD, 0x07]).unwrap();
+        assert!(Handshake::parse(&bad_state).is_err());
+    }
+
+    #[test]
+    fn test_status_packets() {
+        let json = r#"{"version":{"name":"1.21.4","protocol":769}}"#;
+        let status = StatusResponse {
+            json: json.to_string(),
+        }
+        .build()
+        .unwrap();
+        assert_eq!(status.get_id().get_value(), 0x00);
+        assert_eq!(string::read(status.get_payload()).unwrap().0, json);
+
+        // The ping of a vanilla client, a timestamp in milliseconds.
+        let ping = Packet::new([9, 0x01, 0, 0, 0x01, 0x94, 0x2D, 0x5F, 0x3E, 0xA0]).unwrap();
+        let ping = PingRequest::parse(&ping).unwrap();
+        assert_eq!(ping.payload, 1735928004256);
+
+        let pong = PongResponse::from(ping).build().unwrap();
+        assert_eq!(
+            pong.get_full_packet(),
+            [9, 0x01, 0, 0, 0x01, 0x94, 0x2D, 0x5F, 0x3E, 0xA0]
+        );
+        let mut reader = PacketReader::new(pong.get_payload());
+        assert_eq!(reader.read_long().unwrap(), ping.payload);
+
+        let truncated = Packet::new([5, 0x01, 0, 0, 0x01, 0x94]).unwrap();
+        assert!(PingRequest::parse(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_handshake_summary() {
+        let handshake = Handshake {
+            protocol_version: 769,
+            server_address: "play.example.com".to_string(),
+            server_port: 25565,
+            next_state: NextState::Login,
+        };
+
+        let summary = handshake.summary(true);
+        assert_eq!(summary.protocol_version, 769);
+        assert_eq!(summary.server_address.as_deref(), Some("play.example.com"));
+        assert_eq!(
+            summary.to_string(),
+            "protocol=769 address=play.example.com port=25565 next_state=Login"
+        );
+
+        let summary = handshake.summary(false);
+        assert_eq!(summary.server_address, None);
+        assert_eq!(
+            summary.to_string(),
+            "protocol=769 port=25565 next_state=Login"
+        );
+        assert!(!summary.to_string().contains("example"));
+    }
+
+    #[test]
+    fn test_login_plugin_request_roundtrip() {
+        let request = LoginPluginRequest {
+            message_id: 300,
+            channel: "cactus:hello".to_string(),
+            data: vec![1, 2, 3],
+        };
+        let packet = request.build().unwrap();
+
+        assert_eq!(packet.get_id().get_value(), 0x04);
+        assert_eq!(&packet.get_payload()[..3], &[0xAC, 0x02, 12]);
+        assert_eq!(LoginPluginRequest::parse(&packet).unwrap(), request);
+    }
+
+    #[test]
+    fn test_login_plugin_response_roundtrip() {
+        let response = LoginPluginResponse {
+            message_id: 7,
+            data: Some(vec![4, 5]),
+        };
+        let packet = response.build().unwrap();
+        assert_eq!(packet.get_payload(), &[7, 1, 4, 5]);
+        assert_eq!(LoginPluginResponse::parse(&packet).unwrap(), response);
+
+        let packet = LoginPluginResponse::not_understood(7).build().unwrap();
+        assert_eq!(packet.get_payload(), &[7, 0]);
+        assert_eq!(LoginPluginResponse::parse(&packet).unwrap().data, None);
+
+        // Not understood, but with data
+        let packet = PacketBuilder::new()
+            .append_bytes([7, 0, 4, 5])
+            .build(0x02)
+            .unwrap();
+        assert!(LoginPluginResponse::parse(&packet).is_err());
+    }
+
+    #[test]
+    fn test_login_start_roundtrip() {
+        let login_start = LoginStart {
+            name: "Steve".to_string(),
+            uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+        };
+        let packet = login_start.build().unwrap();
+        assert_eq!(packet.get_payload().len(), 6 + 16);
+        assert_eq!(LoginStart::parse(&packet).unwrap(), login_start);
+
+        // Missing the end of the UUID
+        let packet = Packet::new([9, 0x00, 5, b'S', b't', b'e', b'v', b'e', 0x56, 0x27]).unwrap();
+        assert!(LoginStart::parse(&packet).is_err());
+    }
+
+    #[test]
+    fn test_login_disconnect() {
+        let packet = LoginDisconnect {
+            reason: TextComponent::text("Bye"),
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(packet.get_id().get_value(), 0x00);
+        let (reason, _) = string::read(packet.get_payload()).unwrap();
+        assert_eq!(reason, r#"{"text":"Bye"}"#);
+    }
+
+    #[test]
+    fn test_login_success() {
+        let mut login_success =
+            LoginSuccess::from_values(0x5627dd98e6be3c21b8a8e92344183641, "Steve".to_string());
+        let packet = login_success.build().unwrap();
+        assert_eq!(packet.get_id().get_value(), 0x02);
+        let payload = packet.get_payload();
+        assert_eq!(
+            payload[..16],
+            0x5627dd98e6be3c21b8a8e92344183641u128.to_be_bytes()
+        );
+        assert_eq!(payload[16..], [5, b'S', b't', b'e', b'v', b'e', 0]);
+
+        login_success.properties.push(ProfileProperty {
+            name: "textures".to_string(),
+            value: "e30=".to_string(),
+            signature: None,
+        });
+        let packet = login_success.build().unwrap();
+        let properties = &packet.get_payload()[22..];
+        assert_eq!(properties[0], 1);
+        assert_eq!(properties[1..10], *b"\x08textures");
+        assert_eq!(properties[10..], [4, b'e', b'3', b'0', b'=', 0]);
+
+        assert!(LoginAcknowledged::parse(&Packet::new([1, 0x03]).unwrap()).is_ok());
+        assert!(LoginAcknowledged::parse(&Packet::new([2, 0x03, 0]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_update_time() {
+        let packet = UpdateTime {
+            world_age: 24001,
+            time_of_day: 1000,
+            time_of_day_increasing: true,
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(packet.get_id().get_value(), 0x6B);
+        assert_eq!(
+            packet.get_payload(),
+            &[0, 0, 0, 0, 0, 0, 0x5D, 0xC1, 0, 0, 0, 0, 0, 0, 0x03, 0xE8, 1]
+        );
+    }
+}
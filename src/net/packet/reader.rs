@@ -0,0 +1,103 @@
+//! Reads the fields of a payload one after the other.
+use super::data_types::{
+    Boolean, CodecError, Encodable, Long, StringProtocol, UnsignedShort, Uuid, VarInt,
+};
+
+/// A cursor over a payload. Each read moves it after the field, and fails without panicking if
+/// the payload ends before the field does.
+#[derive(Debug, Clone, Copy)]
+pub struct PacketReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(payload: &'a [u8]) -> Self {
+        Self { bytes: payload }
+    }
+
+    /// Reads any data type.
+    pub fn read<T: Encodable>(&mut self) -> Result<T, CodecError> {
+        T::consume_from_bytes(&mut self.bytes)
+    }
+
+    pub fn read_varint(&mut self) -> Result<i32, CodecError> {
+        Ok(self.read::<VarInt>()?.get_value())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, CodecError> {
+        Ok(self.read::<StringProtocol>()?.get_value())
+    }
+
+    pub fn read_uuid(&mut self) -> Result<u128, CodecError> {
+        Ok(self.read::<Uuid>()?.get_value())
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, CodecError> {
+        Ok(self.read::<Boolean>()?.get_value())
+    }
+
+    pub fn read_long(&mut self) -> Result<i64, CodecError> {
+        Ok(self.read::<Long>()?.get_value())
+    }
+
+    pub fn read_unsigned_short(&mut self) -> Result<u16, CodecError> {
+        Ok(self.read::<UnsignedShort>()?.get_value())
+    }
+
+    /// The number of bytes not read yet.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::data_types::{string, DataType, ErrorReason};
+
+    #[test]
+    fn test_sequential_reads() {
+        let mut payload = string::write("Steve").unwrap();
+        payload.extend(0x5627dd98e6be3c21b8a8e92344183641_u128.to_be_bytes());
+        payload.extend([0x01, 0xdd, 0xc7, 0x01]);
+
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(reader.read_string().unwrap(), "Steve");
+        assert_eq!(
+            reader.read_uuid().unwrap(),
+            0x5627dd98e6be3c21b8a8e92344183641
+        );
+        assert!(reader.read_bool().unwrap());
+        assert_eq!(reader.remaining(), 3);
+        assert_eq!(reader.read_varint().unwrap(), 25565);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn test_read_past_the_end() {
+        let mut reader = PacketReader::new(&[0x63]);
+        assert_eq!(
+            reader.read_unsigned_short(),
+            Err(CodecError::Decoding(
+                DataType::UnsignedShort,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+        // A failed read doesn't move the reader.
+        assert_eq!(reader.remaining(), 1);
+
+        assert_eq!(
+            reader.read_uuid(),
+            Err(CodecError::Decoding(
+                DataType::Uuid,
+                ErrorReason::ValueTooSmall
+            ))
+        );
+        assert!(reader.read_bool().is_err());
+        assert!(reader.read_string().is_err());
+
+        let mut reader = PacketReader::new(&[]);
+        assert!(reader.read_varint().is_err());
+        assert!(reader.read_string().is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! Secure profiles: with `enforce-secure-profile` in online mode, players must have a chat signing
+//! key signed by Mojang to log in.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+use crate::config::Settings;
+use crate::i18n::tr;
+
+/// The chat signing key of a player, signed by Mojang. Sent in Player Session, once in Play.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfilePublicKey {
+    /// When the key expires, in milliseconds since the Unix epoch.
+    pub expires_at: i64,
+    /// The encoded RSA public key.
+    pub key: Vec<u8>,
+    /// Mojang's signature of the key.
+    pub signature: Vec<u8>,
+}
+
+/// Why a player was refused. The messages are the vanilla ones.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecureProfileError {
+    #[error("{}", tr("disconnect.missing_public_key", &[]))]
+    MissingPublicKey,
+
+    #[error("{}", tr("disconnect.expired_public_key", &[]))]
+    ExpiredPublicKey,
+
+    #[error("{}", tr("disconnect.invalid_public_key_signature", &[]))]
+    InvalidSignature,
+}
+
+/// Whether secure profiles are required. They are only in online mode, because offline players
+/// don't have keys signed by Mojang.
+pub fn is_enforced(settings: &Settings) -> bool {
+    settings.enforce_secure_profile && settings.online_mode
+}
+
+/// Checks the key a player sent in Player Session, if `enforced`.
+// TODO: Verify the signature with Mojang's public key. Only its presence is checked for now.
+pub fn check(
+    enforced: bool,
+    public_key: Option<&ProfilePublicKey>,
+) -> Result<(), SecureProfileError> {
+    if !enforced {
+        return Ok(());
+    }
+    let public_key = public_key.ok_or(SecureProfileError::MissingPublicKey)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as i64);
+    if public_key.expires_at <= now {
+        return Err(SecureProfileError::ExpiredPublicKey);
+    }
+    if public_key.key.is_empty() || public_key.signature.is_empty() {
+        return Err(SecureProfileError::InvalidSignature);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(expires_at: i64) -> ProfilePublicKey {
+        ProfilePublicKey {
+            expires_at,
+            key: vec![1; 16],
+            signature: vec![2; 16],
+        }
+    }
+
+    #[test]
+    fn test_enforced() {
+        assert_eq!(check(true, None), Err(SecureProfileError::MissingPublicKey));
+        assert_eq!(check(true, Some(&key(i64::MAX))), Ok(()));
+        assert_eq!(
+            check(true, Some(&key(0))),
+            Err(SecureProfileError::ExpiredPublicKey)
+        );
+
+        let unsigned = ProfilePublicKey {
+            signature: Vec::new(),
+            ..key(i64::MAX)
+        };
+        assert_eq!(
+            check(true, Some(&unsigned)),
+            Err(SecureProfileError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_not_enforced() {
+        assert_eq!(check(false, None), Ok(()));
+        assert_eq!(check(false, Some(&key(0))), Ok(()));
+    }
+}
@@ -0,0 +1,144 @@
+//! Caches the Status Response JSON, so that server list pings don't read the settings and the
+//! server icon every time.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+/// Default of the non-standard `status-cache-ttl-ms` property.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5);
+
+static STATUS_CACHE: Lazy<StatusCache> =
+    Lazy::new(|| StatusCache::new(ttl_from_settings(&Settings::new())));
+
+/// Returns the cache used for the status requests.
+pub fn global() -> &'static StatusCache {
+    &STATUS_CACHE
+}
+
+/// Reads the non-standard `status-cache-ttl-ms` property. 0 disables the cache.
+pub fn ttl_from_settings(settings: &Settings) -> Duration {
+    settings
+        .extra("status-cache-ttl-ms")
+        .and_then(|millis| millis.parse::<u64>().ok())
+        .map_or(DEFAULT_TTL, Duration::from_millis)
+}
+
+/// A cached Status Response.
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub json: Arc<str>,
+    /// The whole Status Response packet with `json`, as sent on the wire.
+    pub packet: Arc<[u8]>,
+}
+
+struct CachedStatus {
+    entry: StatusEntry,
+    online_players: usize,
+    built_at: Instant,
+}
+
+/// The Status Response JSON of each MOTD (the main one and the ones of the virtual hosts).
+///
+/// A JSON is rebuilt once it's older than the TTL, or when the number of online players changed.
+pub struct StatusCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<Option<String>, CachedStatus>>,
+}
+
+impl StatusCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached JSON for `motd`, or the one returned by `build` if it's missing or
+    /// outdated.
+    pub fn get_or_build(
+        &self,
+        motd: Option<&str>,
+        online_players: usize,
+        build: impl FnOnce() -> String,
+    ) -> StatusEntry {
+        let mut entries = self.entries.lock().unwrap();
+        let key = motd.map(str::to_string);
+
+        if let Some(cached) = entries.get(&key) {
+            if cached.online_players == online_players && cached.built_at.elapsed() < self.ttl {
+                return cached.entry.clone();
+            }
+        }
+
+        let json = build();
+        let entry = StatusEntry {
+            packet: super::encode_status_response(&json).into(),
+            json: json.into(),
+        };
+        entries.insert(
+            key,
+            CachedStatus {
+                entry: entry.clone(),
+                online_players,
+                built_at: Instant::now(),
+            },
+        );
+        entry
+    }
+
+    /// Forgets every cached JSON, e.g. after the MOTD or the server icon changed.
+    pub fn invalidate(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_reuse_within_ttl() {
+        let cache = StatusCache::new(Duration::from_secs(60));
+        let builds = Cell::new(0);
+        let build = || {
+            builds.set(builds.get() + 1);
+            "{}".to_string()
+        };
+
+        cache.get_or_build(None, 0, build);
+        cache.get_or_build(None, 0, build);
+        assert_eq!(builds.get(), 1);
+
+        // Each MOTD has its own JSON
+        cache.get_or_build(Some("Creative server"), 0, build);
+        assert_eq!(builds.get(), 2);
+    }
+
+    #[test]
+    fn test_rebuild_when_outdated() {
+        let cache = StatusCache::new(Duration::from_secs(60));
+        let builds = Cell::new(0);
+        let build = || {
+            builds.set(builds.get() + 1);
+            "{}".to_string()
+        };
+
+        cache.get_or_build(None, 0, build);
+        cache.get_or_build(None, 1, build);
+        assert_eq!(builds.get(), 2);
+
+        cache.invalidate();
+        cache.get_or_build(None, 1, build);
+        assert_eq!(builds.get(), 3);
+
+        let disabled = StatusCache::new(Duration::ZERO);
+        disabled.get_or_build(None, 0, build);
+        disabled.get_or_build(None, 0, build);
+        assert_eq!(builds.get(), 5);
+    }
+}
@@ -0,0 +1,145 @@
+//! The server icon shown in the server list, loaded once from server-icon.png.
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use base64::{engine::general_purpose, Engine};
+use image::{GenericImageView, ImageFormat};
+use log::{error, warn};
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::consts::file_paths::SERVER_ICON;
+
+#[derive(Error, Debug)]
+pub enum IconError {
+    #[error("Failed to read the server icon: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to decode the server icon: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("The server icon must be in PNG format")]
+    NotPng,
+
+    #[error("The server icon must have dimensions of 64x64, not {0}x{1}")]
+    WrongSize(u32, u32),
+}
+
+static SERVER_ICON_CACHE: Lazy<Arc<RwLock<ServerIcon>>> = Lazy::new(|| {
+    let icon = ServerIcon::load(Path::new(SERVER_ICON)).unwrap_or_else(|e| {
+        error!("{e}, the status won't have an icon");
+        ServerIcon::default()
+    });
+    Arc::new(RwLock::new(icon))
+});
+
+/// Returns the icon of the server.
+pub fn global() -> Arc<RwLock<ServerIcon>> {
+    SERVER_ICON_CACHE.clone()
+}
+
+/// A validated server icon, as the data URI of the status JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerIcon {
+    data_uri: Option<String>,
+}
+
+impl ServerIcon {
+    /// Loads the icon at `path`. There is no icon if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, IconError> {
+        match std::fs::read(path) {
+            Ok(png) => Self::from_png(&png),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                warn!("No {} file, the status won't have an icon", path.display());
+                Ok(Self::default())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Validates `png`, which must be a 64x64 PNG image.
+    pub fn from_png(png: &[u8]) -> Result<Self, IconError> {
+        let format = image::guess_format(png)?;
+        if format != ImageFormat::Png {
+            return Err(IconError::NotPng);
+        }
+
+        let image = image::load_from_memory_with_format(png, format)?;
+        let (width, height) = image.dimensions();
+        if (width, height) != (64, 64) {
+            return Err(IconError::WrongSize(width, height));
+        }
+
+        let base64_icon = general_purpose::STANDARD.encode(png);
+        Ok(Self {
+            data_uri: Some(format!("data:image/png;base64,{base64_icon}")),
+        })
+    }
+
+    /// The `data:image/png;base64,...` URI of the icon, if there is one.
+    pub fn data_uri(&self) -> Option<&str> {
+        self.data_uri.as_deref()
+    }
+
+    /// Loads the icon at `path` again. The current icon is kept if the new one is invalid.
+    pub fn reload(&mut self, path: &Path) -> Result<(), IconError> {
+        *self = Self::load(path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A blank PNG image of `size`x`size` pixels.
+    fn png(size: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::RgbaImage::new(size, size)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_valid_icon() {
+        let icon = ServerIcon::from_png(&png(64)).unwrap();
+        assert!(icon
+            .data_uri()
+            .unwrap()
+            .starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    #[test]
+    fn test_wrong_size() {
+        assert!(matches!(
+            ServerIcon::from_png(&png(32)),
+            Err(IconError::WrongSize(32, 32))
+        ));
+    }
+
+    #[test]
+    fn test_reload_keeps_icon_on_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("server-icon.png");
+        std::fs::write(&path, png(64)).unwrap();
+        let mut icon = ServerIcon::load(&path).unwrap();
+
+        std::fs::write(&path, png(16)).unwrap();
+        assert!(icon.reload(&path).is_err());
+        assert!(icon.data_uri().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+        icon.reload(&path).unwrap();
+        assert_eq!(icon.data_uri(), None);
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let icon = ServerIcon::load(&temp_dir.path().join("server-icon.png")).unwrap();
+        assert_eq!(icon.data_uri(), None);
+    }
+}
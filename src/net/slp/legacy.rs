@@ -0,0 +1,120 @@
+//! The server list ping of the clients older than 1.7, still sent by some uptime monitors.
+//!
+//! It predates the VarInt framing: the client sends `0xFE`, followed by `0x01` since 1.4 and by a
+//! `0xFA` plugin message since 1.6. The server answers with a kick packet, `0xFF` then a string
+//! as UTF-16BE prefixed with its length in code units, and closes the connection.
+use std::sync::atomic::Ordering;
+
+use tokio::net::TcpStream;
+
+use crate::{consts, player};
+
+/// The first byte of a legacy ping.
+pub const LEGACY_PING: u8 = 0xFE;
+
+/// The protocol version announced, like vanilla: above the ones of the legacy clients, which then
+/// show the server as incompatible.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// What a legacy ping answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyStatus {
+    pub motd: String,
+    pub online_players: usize,
+    pub max_players: u32,
+}
+
+impl LegacyStatus {
+    /// The status with the players online now.
+    pub fn new(motd: &str, max_players: u32) -> Self {
+        Self {
+            motd: motd.to_string(),
+            online_players: player::online_players().load(Ordering::Relaxed),
+            max_players,
+        }
+    }
+
+    /// `§1`, the protocol version, the game version, the MOTD, the online and max players,
+    /// separated by NUL characters.
+    pub fn response_string(&self) -> String {
+        format!(
+            "§1\0{LEGACY_PROTOCOL_VERSION}\0{}\0{}\0{}\0{}",
+            consts::minecraft::VERSION,
+            self.motd,
+            self.online_players,
+            self.max_players
+        )
+    }
+
+    /// The kick packet answering the ping.
+    pub fn encode(&self) -> Vec<u8> {
+        let units: Vec<u16> = self.response_string().encode_utf16().collect();
+        let mut bytes = Vec::with_capacity(3 + units.len() * 2);
+        bytes.push(0xFF);
+        bytes.extend((units.len() as u16).to_be_bytes());
+        bytes.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+        bytes
+    }
+}
+
+/// Whether the first bytes received are a legacy ping rather than a modern handshake. A handshake
+/// starts with its length, which is `0xFE 0x01` for a 254-byte one, but then has packet ID `0x00`
+/// where a 1.6 ping has `0xFA`.
+pub fn is_legacy_ping(first_bytes: &[u8]) -> bool {
+    matches!(
+        first_bytes,
+        [LEGACY_PING] | [LEGACY_PING, 0x01] | [LEGACY_PING, 0x01, 0xFA, ..]
+    )
+}
+
+/// Looks at the first bytes sent on `socket`, leaving them to be read.
+pub async fn peek_legacy_ping(socket: &TcpStream) -> bool {
+    let mut first_bytes = [0; 3];
+    match socket.peek(&mut first_bytes).await {
+        Ok(peeked) => is_legacy_ping(&first_bytes[..peeked]),
+        // Reading the handshake will fail too.
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let status = LegacyStatus {
+            motd: "Café ✓".to_string(),
+            online_players: 3,
+            max_players: 20,
+        };
+        assert_eq!(
+            status.response_string(),
+            "§1\u{0}127\u{0}1.21.4\u{0}Café ✓\u{0}3\u{0}20"
+        );
+
+        let bytes = status.encode();
+        // 0xFF, then the length in UTF-16 code units, not in bytes.
+        assert_eq!(bytes[..3], [0xFF, 0x00, 25]);
+        // `§` is 0x00A7, `1` 0x0031 then NUL.
+        assert_eq!(bytes[3..9], [0x00, 0xA7, 0x00, 0x31, 0x00, 0x00]);
+        // `é` and `✓` take one code unit each.
+        let motd = [
+            0x00, 0x43, 0x00, 0x61, 0x00, 0x66, 0x00, 0xE9, 0x00, 0x20, 0x27, 0x13,
+        ];
+        assert!(bytes.windows(motd.len()).any(|window| window == motd));
+        assert_eq!(bytes.len(), 3 + 25 * 2);
+    }
+
+    #[test]
+    fn test_is_legacy_ping() {
+        assert!(is_legacy_ping(&[0xFE]));
+        assert!(is_legacy_ping(&[0xFE, 0x01]));
+        assert!(is_legacy_ping(&[0xFE, 0x01, 0xFA]));
+
+        // A 254-byte handshake.
+        assert!(!is_legacy_ping(&[0xFE, 0x01, 0x00]));
+        assert!(!is_legacy_ping(&[0x10, 0x00, 0x81]));
+        assert!(!is_legacy_ping(&[]));
+    }
+}
@@ -8,37 +8,129 @@
 
 // TODO: Add logging.
 
+pub mod cache;
+pub mod icon;
+pub mod legacy;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
 use log::debug;
 
-use super::packet::{PacketBuilder, PacketError};
-use crate::consts;
-use crate::packet::Packet;
+use super::packet::data_types::varint;
+use super::packet::packet_types::{
+    DecodablePacket, EncodablePacket, PingRequest, PongResponse, StatusResponse,
+};
+use super::packet::PacketError;
+use crate::net::packet::Packet;
+use crate::{consts, player};
+
+/// The response for a Status Request packet. `motd` replaces the one of the settings.
+///
+/// The JSON comes from the status cache, see `cache::StatusCache`.
+pub fn status_response(motd: Option<&str>) -> Result<Packet, PacketError> {
+    StatusResponse {
+        json: cached_status(motd).json.to_string(),
+    }
+    .build()
+}
 
-/// The response for a Status Request packet.
-pub fn status_response() -> Result<Packet, PacketError> {
-    let json_response = consts::protocol::status_response_json();
+/// The wire bytes of the response for a Status Request, straight from the status cache: nothing
+/// is built as long as the cached status is up to date. Same bytes as `status_response`.
+pub fn status_response_bytes(motd: Option<&str>) -> Arc<[u8]> {
+    cached_status(motd).packet
+}
 
-    PacketBuilder::new()
-        .append_string(json_response)
-        .build(0x00)
+fn cached_status(motd: Option<&str>) -> cache::StatusEntry {
+    let online_players = player::online_players().load(Ordering::Relaxed);
+    cache::global().get_or_build(motd, online_players, || {
+        let icon = icon::global();
+        let icon = icon.read().unwrap();
+        consts::protocol::status_response_json(motd, online_players, icon.data_uri())
+    })
 }
 
-/// The response for a Ping Request packet.
+/// Encodes the Status Response packet with `json` in a single allocation.
+fn encode_status_response(json: &str) -> Vec<u8> {
+    let json_length = varint::write(json.len() as i32);
+    // Packet ID + String length + String
+    let length = varint::write((1 + json_length.len() + json.len()) as i32);
+
+    let mut packet = Vec::with_capacity(length.len() + 1 + json_length.len() + json.len());
+    packet.extend_from_slice(&length);
+    packet.push(0x00);
+    packet.extend_from_slice(&json_length);
+    packet.extend_from_slice(json.as_bytes());
+    packet
+}
+
+/// The response for a Ping Request packet: a Pong Response with the same payload.
 pub fn ping_response(ping_request_packet: Packet) -> Result<Packet, PacketError> {
     debug!("Ping packet is: {ping_request_packet}");
-    let payload: &[u8] = ping_request_packet.get_payload();
-    debug!(
-        "Ping packet payload is: {payload:?} and len is {}",
-        payload.len()
-    );
-    if payload.len() == 8 {
-        // Send back the same timestamp as what we received
-        PacketBuilder::new()
-            .append_bytes(&payload[0..8])
-            .build(0x01)
-    } else {
-        Err(PacketError::PayloadDecodeError(
-            "failed to decode timestamp (Long) in the Ping Request packet".to_string(),
-        ))
+    let ping = PingRequest::parse(&ping_request_packet)?;
+
+    // Send back the same timestamp as what we received
+    PongResponse::from(ping).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::PacketBuilder;
+    use std::time::Instant;
+
+    #[test]
+    fn test_fast_path_same_bytes() {
+        let packet = status_response(None).unwrap();
+        assert_eq!(&*status_response_bytes(None), packet.get_full_packet());
+
+        // A JSON longer than 127 bytes has a 2-byte length.
+        let json = "x".repeat(300);
+        let packet = PacketBuilder::new()
+            .append_string(&json)
+            .build(0x00)
+            .unwrap();
+        assert_eq!(encode_status_response(&json), packet.get_full_packet());
+    }
+
+    #[test]
+    fn test_ping_response() {
+        let ping = PacketBuilder::new().append_long(-2).build(0x01).unwrap();
+        let pong = ping_response(ping).unwrap();
+        assert_eq!(
+            pong.get_full_packet(),
+            [9, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]
+        );
+
+        for payload in [&[0; 7][..], &[0; 9]] {
+            let ping = PacketBuilder::new()
+                .append_bytes(payload)
+                .build(0x01)
+                .unwrap();
+            assert!(ping_response(ping).is_err());
+        }
+    }
+
+    /// Run with `cargo test bench_status_response -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_status_response() {
+        const ITERATIONS: u32 = 100_000;
+        // Fills the cache.
+        status_response_bytes(None);
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(status_response(None).unwrap());
+        }
+        let builder = start.elapsed() / ITERATIONS;
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(status_response_bytes(None));
+        }
+        let fast_path = start.elapsed() / ITERATIONS;
+
+        println!("Status response: builder {builder:?}, fast path {fast_path:?}");
     }
 }
@@ -0,0 +1,82 @@
+//! Which packets a client can send in each connection state.
+//!
+//! The IDs are reused from one state to the other: 0x00 is a Handshake, a Status Request or a
+//! Login Start depending on the state. A packet sent in the wrong state, a common protocol bug,
+//! would then be parsed as another one. It is rejected before reaching the handlers instead.
+// TODO: Add the Play packets along with the Play state.
+use super::{ConnectionState, NetError};
+
+/// The serverbound packets of each state, by ID.
+const HANDSHAKE: &[(i32, &str)] = &[(0x00, "Handshake")];
+const STATUS: &[(i32, &str)] = &[(0x00, "Status Request"), (0x01, "Ping Request")];
+const LOGIN: &[(i32, &str)] = &[
+    (0x00, "Login Start"),
+    (0x01, "Encryption Response"),
+    (0x02, "Login Plugin Response"),
+    (0x03, "Login Acknowledged"),
+    (0x04, "Cookie Response"),
+];
+const CONFIGURATION: &[(i32, &str)] = &[
+    (0x00, "Client Information"),
+    (0x01, "Cookie Response"),
+    (0x02, "Serverbound Plugin Message"),
+    (0x03, "Acknowledge Finish Configuration"),
+    (0x04, "Serverbound Keep Alive"),
+    (0x05, "Pong"),
+    (0x06, "Resource Pack Response"),
+    (0x07, "Serverbound Known Packs"),
+];
+
+/// The packets a client can send in `state`, with their names.
+pub fn serverbound_packets(state: ConnectionState) -> &'static [(i32, &'static str)] {
+    match state {
+        ConnectionState::Handshake => HANDSHAKE,
+        ConnectionState::Status => STATUS,
+        // A transfer is a login, from another server.
+        ConnectionState::Login | ConnectionState::Transfer => LOGIN,
+        ConnectionState::Configuration => CONFIGURATION,
+    }
+}
+
+/// Returns the name of the packet `packet_id`, or `NetError::UnexpectedPacket` if it can't be
+/// sent in `state`.
+pub fn validate(state: ConnectionState, packet_id: i32) -> Result<&'static str, NetError> {
+    serverbound_packets(state)
+        .iter()
+        .find(|(id, _)| *id == packet_id)
+        .map(|(_, name)| *name)
+        .ok_or(NetError::UnexpectedPacket(packet_id, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            validate(ConnectionState::Handshake, 0x00).unwrap(),
+            "Handshake"
+        );
+        assert_eq!(
+            validate(ConnectionState::Status, 0x01).unwrap(),
+            "Ping Request"
+        );
+        assert_eq!(
+            validate(ConnectionState::Transfer, 0x03).unwrap(),
+            "Login Acknowledged"
+        );
+        assert_eq!(
+            validate(ConnectionState::Configuration, 0x07).unwrap(),
+            "Serverbound Known Packs"
+        );
+
+        assert!(matches!(
+            validate(ConnectionState::Status, 0x02),
+            Err(NetError::UnexpectedPacket(0x02, ConnectionState::Status))
+        ));
+        // A Play packet during the login.
+        assert!(validate(ConnectionState::Login, 0x1A).is_err());
+        assert!(validate(ConnectionState::Handshake, 0x01).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+//! Status-only mode: the server answers the status and the ping, and refuses every login. Used as
+//! a cheap placeholder, e.g. while the real server boots. The world isn't loaded nor ticked.
+use std::sync::RwLock;
+
+/// The login refusal message when none is given.
+pub const DEFAULT_MESSAGE: &str = "The server is starting, come back in a moment!";
+
+/// The login refusal message, `None` if the mode is off.
+static STATUS_ONLY: RwLock<Option<String>> = RwLock::new(None);
+
+/// Turns the status-only mode on, logins being refused with `message` or `DEFAULT_MESSAGE`.
+pub fn enable(message: Option<String>) {
+    *STATUS_ONLY.write().unwrap() = Some(message.unwrap_or_else(|| DEFAULT_MESSAGE.to_string()));
+}
+
+pub fn is_enabled() -> bool {
+    STATUS_ONLY.read().unwrap().is_some()
+}
+
+/// The message refusing the logins, if the mode is on.
+pub fn login_refusal() -> Option<String> {
+    STATUS_ONLY.read().unwrap().clone()
+}
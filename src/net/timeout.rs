@@ -0,0 +1,202 @@
+//! A time limit on packet handlers, so that a slow or stuck handler can't hang a connection.
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use log::warn;
+
+use super::{ConnectionState, NetError};
+use crate::config::Settings;
+
+/// What to do with a packet whose handler exceeded the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// Closes the connection.
+    Disconnect,
+    /// Ignores the packet and keeps reading the next ones. Only in the Status state, see
+    /// `run_handler`.
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandlerTimeout {
+    pub duration: Duration,
+    pub action: TimeoutAction,
+}
+
+impl HandlerTimeout {
+    /// Reads the non-standard `packet-handler-timeout-ms` and `packet-handler-timeout-action`
+    /// (`disconnect` or `skip`) properties. No timeout if the duration is missing or 0.
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let millis = settings
+            .extra("packet-handler-timeout-ms")?
+            .parse::<u64>()
+            .ok()
+            .filter(|&millis| millis > 0)?;
+        let action = match settings.extra("packet-handler-timeout-action") {
+            Some("skip") => TimeoutAction::Skip,
+            _ => TimeoutAction::Disconnect,
+        };
+
+        Some(Self {
+            duration: Duration::from_millis(millis),
+            action,
+        })
+    }
+}
+
+/// Runs the handler of packet `packet_id`, received in `state`, within `timeout`.
+///
+/// Returns `Ok(None)` if the packet was skipped after a timeout, and `NetError::HandlerTimeout`
+/// if the connection must be closed. Both cases are logged.
+///
+/// Only the packets of the Status state can be skipped: the other handlers change the connection
+/// (its state, its compression), and a handler stopped halfway would leave it inconsistent. The
+/// timeout fires when the handler yields, so heavy work must go through `blocking`.
+pub async fn run_handler<T>(
+    timeout: Option<HandlerTimeout>,
+    packet_id: i32,
+    state: ConnectionState,
+    handler: impl Future<Output = Result<T, NetError>>,
+) -> Result<Option<T>, NetError> {
+    let Some(timeout) = timeout else {
+        return handler.await.map(Some);
+    };
+
+    match tokio::time::timeout(timeout.duration, handler).await {
+        Ok(result) => result.map(Some),
+        Err(_) => {
+            let error = NetError::HandlerTimeout(packet_id, state, timeout.duration);
+            let action = match state {
+                ConnectionState::Status => timeout.action,
+                _ => TimeoutAction::Disconnect,
+            };
+            match action {
+                TimeoutAction::Skip => {
+                    warn!("{error}, skipping the packet");
+                    Ok(None)
+                }
+                TimeoutAction::Disconnect => {
+                    warn!("{error}, closing the connection");
+                    Err(error)
+                }
+            }
+        }
+    }
+}
+
+/// Runs the heavy or blocking work of a handler, e.g. generating chunks, on a blocking thread.
+/// Done inline, it would never yield and the timeout couldn't fire.
+pub async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, NetError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| NetError::Io(io::Error::other(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::capture;
+
+    async fn slow_handler() -> Result<u8, NetError> {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(1)
+    }
+
+    fn timeout(action: TimeoutAction) -> Option<HandlerTimeout> {
+        Some(HandlerTimeout {
+            duration: Duration::from_millis(10),
+            action,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_disconnects() {
+        capture::start();
+        let result = run_handler(
+            timeout(TimeoutAction::Disconnect),
+            0x2A,
+            ConnectionState::Login,
+            slow_handler(),
+        )
+        .await;
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error,
+            NetError::HandlerTimeout(0x2A, ConnectionState::Login, _)
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Handler of packet 0x2A (state Login) timed out after 10ms"
+        );
+        assert_eq!(
+            capture::take(),
+            ["WARN Handler of packet 0x2A (state Login) timed out after 10ms, closing the connection"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_skipped() {
+        capture::start();
+        let result = run_handler(
+            timeout(TimeoutAction::Skip),
+            0x00,
+            ConnectionState::Status,
+            slow_handler(),
+        )
+        .await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            capture::take(),
+            ["WARN Handler of packet 0x00 (state Status) timed out after 10ms, skipping the packet"]
+        );
+
+        // A Login handler may have been stopped halfway.
+        let result = run_handler(
+            timeout(TimeoutAction::Skip),
+            0x00,
+            ConnectionState::Login,
+            slow_handler(),
+        )
+        .await;
+        assert!(matches!(result, Err(NetError::HandlerTimeout(..))));
+    }
+
+    #[tokio::test]
+    async fn test_blocking_handler() {
+        let blocking_handler = blocking(|| {
+            std::thread::sleep(Duration::from_millis(200));
+            1
+        });
+        let result = run_handler(
+            timeout(TimeoutAction::Disconnect),
+            0x00,
+            ConnectionState::Status,
+            blocking_handler,
+        );
+        let result = tokio::time::timeout(Duration::from_millis(100), result).await;
+        assert!(matches!(result, Ok(Err(NetError::HandlerTimeout(..)))));
+
+        assert!(matches!(blocking(|| 1).await, Ok(1)));
+    }
+
+    #[tokio::test]
+    async fn test_fast_handler() {
+        let fast = async { Ok::<u8, NetError>(1) };
+        let result = run_handler(
+            timeout(TimeoutAction::Skip),
+            0,
+            ConnectionState::Status,
+            fast,
+        );
+        assert!(matches!(result.await, Ok(Some(1))));
+
+        let result = run_handler(None, 0, ConnectionState::Status, slow_handler());
+        assert!(tokio::time::timeout(Duration::from_millis(10), result)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,528 @@
+//! The bytes of the packets as sent on the wire: compressed once a threshold is set, then
+//! encrypted once a shared secret is set.
+//!
+//! Compressed packet format:
+//!
+//! Packet Length (VarInt): Length of Data Length + the (compressed) rest
+//! Data Length (VarInt): Length of the uncompressed Packet ID + Data, or 0 if not compressed
+//! Packet ID (VarInt) + Data (Byte Array): zlib-compressed when Data Length is not 0
+//!
+//! Encryption is AES-128 in CFB8 mode, the shared secret being both the key and the IV. It
+//! applies to every byte, including the lengths.
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes128;
+use bytes::{Buf, BytesMut};
+use thiserror::Error;
+
+use super::packet::data_types::varint;
+use super::packet::{Packet, PacketError, Response};
+use crate::region_parser::compression::{self, CompressionScheme};
+
+/// The biggest uncompressed packet a client may send, as in vanilla.
+pub const MAX_UNCOMPRESSED_LENGTH: usize = 8_388_608;
+
+/// The biggest packet on the wire: its length must fit in a 3-byte VarInt.
+pub const MAX_PACKET_LENGTH: usize = 2_097_151;
+
+type Encryptor = cfb8::Encryptor<Aes128>;
+type Decryptor = cfb8::Decryptor<Aes128>;
+
+#[derive(Error, Debug)]
+pub enum TransportError {
+    #[error("Invalid packet length {0}")]
+    InvalidLength(i32),
+
+    #[error("Packet length longer than 3 bytes")]
+    LengthTooLong,
+
+    #[error("Invalid data length {0}")]
+    InvalidDataLength(i32),
+
+    #[error("Packet of {0} bytes sent uncompressed, but the threshold is {1}")]
+    BelowThreshold(usize, usize),
+
+    #[error("Failed to (de)compress a packet: {0}")]
+    Compression(String),
+
+    #[error("Invalid shared secret: expected 16 bytes, got {0}")]
+    InvalidSecret(usize),
+
+    #[error(transparent)]
+    Packet(#[from] PacketError),
+}
+
+/// Turns the packets of a connection into wire bytes and back, according to its negotiated
+/// compression and encryption.
+#[derive(Default)]
+pub struct ConnectionIo {
+    encoder: FrameEncoder,
+    decryptor: Option<Decryptor>,
+    /// Holds the decrypted bytes received that don't make a whole packet yet.
+    decoder: FrameDecoder,
+}
+
+impl ConnectionIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switches both directions to the compressed format.
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.encoder.enable_compression(threshold);
+    }
+
+    /// Encrypts every byte from now on. Bytes already received are not decrypted again.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), TransportError> {
+        let decryptor = Decryptor::new_from_slices(shared_secret, shared_secret)
+            .map_err(|_| TransportError::InvalidSecret(shared_secret.len()))?;
+        self.encoder.enable_encryption(shared_secret)?;
+        self.decryptor = Some(decryptor);
+        Ok(())
+    }
+
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.encoder.compression_threshold
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.decryptor.is_some()
+    }
+
+    /// Returns the wire bytes of `packet`.
+    pub fn encode(&mut self, packet: &Packet) -> Result<Vec<u8>, TransportError> {
+        self.encoder.encode(packet)
+    }
+
+    /// Takes the bytes read from the socket and returns the packets they complete, in order.
+    /// The bytes of an incomplete packet are kept until the next call.
+    pub fn decode(&mut self, received: &[u8]) -> Result<Vec<Packet>, TransportError> {
+        let mut received = received.to_vec();
+        if let Some(decryptor) = &mut self.decryptor {
+            for byte in received.chunks_mut(1) {
+                decryptor.decrypt_block_mut(byte.into());
+            }
+        }
+        self.decoder.extend(&received);
+
+        let mut packets = Vec::new();
+        while let Some(frame) = self.decoder.decode_frame()? {
+            packets.push(match self.encoder.compression_threshold {
+                Some(threshold) => decompress_packet(&frame, threshold)?,
+                None => Packet::new_checked(&frame)?,
+            });
+        }
+        Ok(packets)
+    }
+}
+
+/// Produces the wire bytes of the packets sent on a connection, compressed then encrypted once
+/// they are negotiated.
+#[derive(Default)]
+pub struct FrameEncoder {
+    /// Packets with at least this many bytes (ID + Data) are compressed. `None` before Set
+    /// Compression, where the format has no Data Length.
+    compression_threshold: Option<usize>,
+    encryptor: Option<Encryptor>,
+}
+
+impl FrameEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switches to the compressed format.
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.set_compression(Some(threshold));
+    }
+
+    /// Switches to the compressed format with `threshold`, or back to the uncompressed one if
+    /// `None`.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression_threshold
+    }
+
+    /// Encrypts every byte from now on.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), TransportError> {
+        let encryptor = Encryptor::new_from_slices(shared_secret, shared_secret)
+            .map_err(|_| TransportError::InvalidSecret(shared_secret.len()))?;
+        self.encryptor = Some(encryptor);
+        Ok(())
+    }
+
+    /// Returns the wire bytes of `packet`.
+    pub fn encode(&mut self, packet: &Packet) -> Result<Vec<u8>, TransportError> {
+        let mut bytes = match self.compression_threshold {
+            Some(threshold) => compress_packet(packet, threshold)?,
+            None => packet.get_full_packet().to_vec(),
+        };
+
+        if let Some(encryptor) = &mut self.encryptor {
+            for byte in bytes.chunks_mut(1) {
+                encryptor.encrypt_block_mut(byte.into());
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Returns the wire bytes of the packet of `response`, if it has one.
+    pub fn encode_response(
+        &mut self,
+        response: &Response,
+    ) -> Result<Option<Vec<u8>>, TransportError> {
+        response
+            .get_packet()
+            .map(|packet| self.encode(packet))
+            .transpose()
+    }
+}
+
+/// Splits the received bytes into frames, whatever the size of the reads: a frame can arrive in
+/// several reads, and a read can hold several frames.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    /// Bytes received that don't make a whole frame yet.
+    buffer: BytesMut,
+    /// The threshold of the compressed format, `None` for the uncompressed one.
+    compression_threshold: Option<usize>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the compressed format with `threshold` from now on, or the uncompressed one if
+    /// `None`.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Adds bytes read from the socket.
+    pub fn extend(&mut self, received: &[u8]) {
+        self.buffer.extend_from_slice(received);
+    }
+
+    /// How many bytes are waiting for the rest of their frame.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// How many bytes the first frame still needs, once its length is received. `None` if the
+    /// length isn't whole yet, or is invalid.
+    pub fn missing(&self) -> Option<usize> {
+        let (length, length_size) = read_complete_varint(&self.buffer)?;
+        let body_length = usize::try_from(length).ok()?;
+        Some((length_size + body_length).saturating_sub(self.buffer.len()))
+    }
+
+    /// Splits the first whole frame, length included, off the buffer. `None` until all its
+    /// bytes are received.
+    pub fn decode_frame(&mut self) -> Result<Option<BytesMut>, TransportError> {
+        let Some((length, length_size)) = read_complete_varint(&self.buffer) else {
+            if self.buffer.len() >= 3 {
+                return Err(TransportError::LengthTooLong);
+            }
+            return Ok(None);
+        };
+        let body_length = usize::try_from(length)
+            .ok()
+            .filter(|&length| length > 0 && length <= MAX_PACKET_LENGTH)
+            .ok_or(TransportError::InvalidLength(length))?;
+
+        if self.buffer.len() < length_size + body_length {
+            return Ok(None);
+        }
+        Ok(Some(self.buffer.split_to(length_size + body_length)))
+    }
+
+    /// Returns the next whole packet, decompressed if the compressed format is set.
+    pub fn decode(&mut self) -> Result<Option<Packet>, TransportError> {
+        let Some(frame) = self.decode_frame()? else {
+            return Ok(None);
+        };
+        match self.compression_threshold {
+            Some(threshold) => Ok(Some(decompress_packet(&frame, threshold)?)),
+            None => Ok(Some(Packet::new_checked(&frame)?)),
+        }
+    }
+}
+
+/// Reads the VarInt at the start of `data`, or `None` if its last byte wasn't received yet.
+fn read_complete_varint(data: &[u8]) -> Option<(i32, usize)> {
+    let (value, size) = varint::read(data).ok()?;
+    // `varint::read` stops at the end of the data even if the continue bit is set.
+    if data[size - 1] & 0x80 != 0 {
+        return None;
+    }
+    Some((value, size))
+}
+
+/// Builds the compressed format frame of `packet`: compressed if its ID and data take at least
+/// `threshold` bytes, with a Data Length of 0 otherwise.
+pub fn compress_packet(packet: &Packet, threshold: usize) -> Result<Vec<u8>, TransportError> {
+    let full = packet.get_full_packet();
+    let (_, length_size) = varint::read(full).map_err(PacketError::from)?;
+    let uncompressed = &full[length_size..];
+
+    let (data_length, body) = if uncompressed.len() >= threshold {
+        let compressed = compression::compress(uncompressed, CompressionScheme::Zlib)
+            .map_err(|e| TransportError::Compression(e.to_string()))?;
+        (varint::write(uncompressed.len() as i32), compressed)
+    } else {
+        (varint::write(0), uncompressed.to_vec())
+    };
+
+    let mut frame = varint::write((data_length.len() + body.len()) as i32);
+    frame.extend_from_slice(&data_length);
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+/// Turns a compressed format frame back into a `Packet`. A packet sent uncompressed while above
+/// `threshold`, or compressed while below, is an error.
+pub fn decompress_packet(frame: &[u8], threshold: usize) -> Result<Packet, TransportError> {
+    let (_, length_size) = varint::read(frame).map_err(PacketError::from)?;
+    let mut body = &frame[length_size..];
+    let (data_length, data_length_size) = varint::read(body).map_err(PacketError::from)?;
+    body.advance(data_length_size);
+
+    let uncompressed = if data_length == 0 {
+        if body.len() >= threshold {
+            return Err(TransportError::BelowThreshold(body.len(), threshold));
+        }
+        body.to_vec()
+    } else {
+        let expected = usize::try_from(data_length)
+            .ok()
+            .filter(|&length| length >= threshold && length <= MAX_UNCOMPRESSED_LENGTH)
+            .ok_or(TransportError::InvalidDataLength(data_length))?;
+        let uncompressed =
+            compression::decompress_limited(body, CompressionScheme::Zlib, expected as u64 + 1)
+                .map_err(|e| TransportError::Compression(e.to_string()))?;
+        if uncompressed.len() != expected {
+            return Err(TransportError::InvalidDataLength(data_length));
+        }
+        uncompressed
+    };
+
+    let mut bytes = varint::write(uncompressed.len() as i32);
+    bytes.extend_from_slice(&uncompressed);
+    Ok(Packet::new_checked(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::packet::PacketBuilder;
+
+    const SECRET: [u8; 16] = *b"0123456789abcdef";
+
+    fn packet(id: i32, payload: &[u8]) -> Packet {
+        PacketBuilder::new()
+            .append_bytes(payload)
+            .build(id)
+            .unwrap()
+    }
+
+    /// Both ends of a connection that negotiated compression and encryption.
+    fn negotiated(threshold: usize) -> (ConnectionIo, ConnectionIo) {
+        let mut sender = ConnectionIo::new();
+        let mut receiver = ConnectionIo::new();
+        for io in [&mut sender, &mut receiver] {
+            io.enable_compression(threshold);
+            io.enable_encryption(&SECRET).unwrap();
+        }
+        (sender, receiver)
+    }
+
+    #[test]
+    fn test_compression_then_encryption() {
+        let (mut sender, mut receiver) = negotiated(256);
+        let large = packet(0x27, &b"Cactus ".repeat(100));
+        let small = packet(0x05, b"tiny");
+
+        let large_bytes = sender.encode(&large).unwrap();
+        let small_bytes = sender.encode(&small).unwrap();
+
+        // Compressed, then encrypted: neither the payload nor the zlib stream show in clear.
+        assert!(large_bytes.len() < large.len());
+        assert!(!large_bytes.windows(7).any(|window| window == b"Cactus "));
+        assert!(!small_bytes.windows(4).any(|window| window == b"tiny"));
+
+        let wire = [large_bytes, small_bytes].concat();
+        let received = receiver.decode(&wire).unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].get_full_packet(), large.get_full_packet());
+        assert_eq!(received[1].get_full_packet(), small.get_full_packet());
+    }
+
+    #[test]
+    fn test_sub_threshold_packet_is_encrypted_not_compressed() {
+        let (mut sender, _) = negotiated(256);
+        let small = packet(0x05, b"tiny");
+        let bytes = sender.encode(&small).unwrap();
+
+        let mut decryptor = Decryptor::new_from_slices(&SECRET, &SECRET).unwrap();
+        let mut decrypted = bytes.clone();
+        for byte in decrypted.chunks_mut(1) {
+            decryptor.decrypt_block_mut(byte.into());
+        }
+
+        // Packet Length, Data Length 0, then the packet ID and payload as is.
+        let uncompressed = &small.get_full_packet()[1..];
+        let expected = [&[uncompressed.len() as u8 + 1, 0], uncompressed].concat();
+        assert_eq!(decrypted, expected);
+        assert_ne!(bytes, expected);
+    }
+
+    #[test]
+    fn test_decode_split_reads() {
+        let (mut sender, mut receiver) = negotiated(64);
+        let large = packet(0x27, &[7; 500]);
+        let wire = sender.encode(&large).unwrap();
+
+        let mut received = Vec::new();
+        for byte in &wire {
+            received.extend(receiver.decode(&[*byte]).unwrap());
+        }
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].get_full_packet(), large.get_full_packet());
+    }
+
+    #[test]
+    fn test_plain_roundtrip() {
+        let mut io = ConnectionIo::new();
+        let status = packet(0x00, b"{}");
+        let wire = io.encode(&status).unwrap();
+        assert_eq!(wire, status.get_full_packet());
+        assert_eq!(io.decode(&wire).unwrap()[0].get_full_packet(), wire);
+    }
+
+    #[test]
+    fn test_frame_decoder_byte_by_byte_and_bulk() {
+        let packets = [
+            packet(0x00, &[]),
+            packet(0x01, &[1, 2, 3, 4, 5, 6, 7, 8]),
+            // Its length takes 2 bytes, which arrive in two reads.
+            packet(0x27, &[9; 300]),
+        ];
+        let wire: Vec<u8> = packets
+            .iter()
+            .flat_map(|packet| packet.get_full_packet().to_vec())
+            .collect();
+
+        let mut bulk = FrameDecoder::new();
+        bulk.extend(&wire);
+        let mut bulk_packets = Vec::new();
+        while let Some(packet) = bulk.decode().unwrap() {
+            bulk_packets.push(packet.get_full_packet().to_vec());
+        }
+
+        let mut byte_by_byte = FrameDecoder::new();
+        let mut single_packets = Vec::new();
+        for byte in &wire {
+            byte_by_byte.extend(&[*byte]);
+            while let Some(packet) = byte_by_byte.decode().unwrap() {
+                single_packets.push(packet.get_full_packet().to_vec());
+            }
+        }
+
+        let expected: Vec<Vec<u8>> = packets
+            .iter()
+            .map(|packet| packet.get_full_packet().to_vec())
+            .collect();
+        assert_eq!(bulk_packets, expected);
+        assert_eq!(single_packets, expected);
+        assert_eq!(bulk.buffered(), 0);
+        assert_eq!(byte_by_byte.buffered(), 0);
+    }
+
+    #[test]
+    fn test_frame_decoder_invalid_length() {
+        let mut decoder = FrameDecoder::new();
+        decoder.extend(&[0x00]);
+        assert!(matches!(
+            decoder.decode_frame(),
+            Err(TransportError::InvalidLength(0))
+        ));
+
+        let mut decoder = FrameDecoder::new();
+        decoder.extend(&[0xFF, 0xFF, 0xFF]);
+        assert!(matches!(
+            decoder.decode_frame(),
+            Err(TransportError::LengthTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_frame_encoder_status_response() {
+        let status = crate::net::slp::status_response(None).unwrap();
+        let response = Response::new(Some(status));
+        let bytes = FrameEncoder::new().encode_response(&response).unwrap();
+        assert_eq!(
+            bytes.as_deref(),
+            Some(response.get_packet().unwrap().get_full_packet())
+        );
+
+        assert_eq!(
+            FrameEncoder::new()
+                .encode_response(&Response::new(None))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_frame_encoder_data_length() {
+        let mut encoder = FrameEncoder::new();
+        encoder.enable_compression(256);
+
+        // Packet Length, Data Length 0 then the packet as is.
+        let small = packet(0x01, &[42; 8]);
+        let bytes = encoder.encode(&small).unwrap();
+        assert_eq!(bytes[..2], [10, 0]);
+        assert_eq!(bytes[2..], small.get_full_packet()[1..]);
+
+        // Packet Length, Data Length 301 (ID + payload) then the zlib data.
+        let large = packet(0x27, &[7; 300]);
+        let bytes = encoder.encode(&large).unwrap();
+        let (length, length_size) = varint::read(&bytes).unwrap();
+        assert_eq!(length as usize, bytes.len() - length_size);
+        let (data_length, _) = varint::read(&bytes[length_size..]).unwrap();
+        assert_eq!(data_length, 301);
+    }
+
+    #[test]
+    fn test_compress_packet_roundtrip() {
+        for (payload, compressed) in [(vec![1; 10], false), (vec![2; 255], true)] {
+            let packet = packet(0x27, &payload);
+            let frame = compress_packet(&packet, 256).unwrap();
+            let (_, length_size) = varint::read(&frame).unwrap();
+            let (data_length, _) = varint::read(&frame[length_size..]).unwrap();
+            // The threshold counts the ID, so 1 + 255 bytes are compressed.
+            assert_eq!(data_length != 0, compressed);
+
+            let decompressed = decompress_packet(&frame, 256).unwrap();
+            assert_eq!(decompressed.get_full_packet(), packet.get_full_packet());
+        }
+
+        // Below the threshold of the receiver, but compressed anyway.
+        let frame = compress_packet(&packet(0x27, &[3; 100]), 0).unwrap();
+        assert!(matches!(
+            decompress_packet(&frame, 256),
+            Err(TransportError::InvalidDataLength(101))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_secret() {
+        assert!(matches!(
+            ConnectionIo::new().enable_encryption(&[0; 5]),
+            Err(TransportError::InvalidSecret(5))
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+//! Virtual hosts: several logical servers behind the same port, selected with the address the
+//! client used to connect (`Handshake::server_address`).
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// The settings of a logical server. Missing values are the ones of the main configuration.
+// TODO: Add the level of the logical server once worlds are loaded at login.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VirtualHost {
+    pub hostname: String,
+    pub motd: Option<String>,
+}
+
+/// Maps hostnames to their virtual host.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualHostRouter {
+    hosts: HashMap<String, VirtualHost>,
+}
+
+impl VirtualHostRouter {
+    pub fn new(hosts: Vec<VirtualHost>) -> Self {
+        Self {
+            hosts: hosts
+                .into_iter()
+                .map(|host| (normalize(&host.hostname), host))
+                .collect(),
+        }
+    }
+
+    /// Reads the virtual hosts from a JSON array, like:
+    /// `[{"hostname": "creative.example.com", "motd": "Creative server"}]`.
+    ///
+    /// The router is empty if the file doesn't exist.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(Self::new(serde_json::from_str(&content)?))
+    }
+
+    /// Selects the virtual host of `server_address`, or `None` to use the main configuration.
+    pub fn route(&self, server_address: &str) -> Option<&VirtualHost> {
+        self.hosts.get(&normalize(server_address))
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+}
+
+/// Hostnames are case-insensitive and may end with a dot. Modded clients append data after a
+/// NUL character (e.g. "\0FML3\0"), which is ignored.
+fn normalize(hostname: &str) -> String {
+    let hostname = hostname.split('\0').next().unwrap_or_default();
+    hostname.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn router() -> VirtualHostRouter {
+        VirtualHostRouter::new(vec![VirtualHost {
+            hostname: "creative.example.com".to_string(),
+            motd: Some("Creative server".to_string()),
+        }])
+    }
+
+    #[test]
+    fn test_route_matching_host() {
+        let router = router();
+
+        let host = router.route("creative.example.com").unwrap();
+        assert_eq!(host.motd.as_deref(), Some("Creative server"));
+
+        assert!(router.route("Creative.Example.com.").is_some());
+        assert!(router.route("creative.example.com\0FML3\0").is_some());
+    }
+
+    #[test]
+    fn test_route_unknown_host() {
+        let router = router();
+
+        assert!(router.route("survival.example.com").is_none());
+        assert!(router.route("").is_none());
+        assert!(VirtualHostRouter::default().route("localhost").is_none());
+    }
+
+    #[test]
+    fn test_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("virtual-hosts.json");
+
+        fs::write(
+            &path,
+            r#"[{"hostname": "creative.example.com", "motd": "Creative"}]"#,
+        )
+        .unwrap();
+        let router = VirtualHostRouter::load(&path).unwrap();
+        assert_eq!(router.len(), 1);
+        let host = router.route("creative.example.com").unwrap();
+        assert_eq!(host.motd.as_deref(), Some("Creative"));
+
+        fs::remove_file(&path).unwrap();
+        assert!(VirtualHostRouter::load(&path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Filtering of the chat messages, configured by the `text-filtering-config` property: the path
+//! of a word list, one word per line. Without it, the messages are left as is.
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+static CHAT_FILTER: Lazy<Arc<RwLock<Box<dyn ChatFilter>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(from_settings(&Settings::new()))));
+
+/// Returns the chat filter of the server.
+pub fn global() -> Arc<RwLock<Box<dyn ChatFilter>>> {
+    CHAT_FILTER.clone()
+}
+
+/// Filters a chat message with the filter of the server. The hook of the chat handling.
+// TODO: Call it on the chat messages, once the Play state exists.
+pub fn filter_chat(message: &str) -> Filtered {
+    match CHAT_FILTER.read() {
+        Ok(filter) => filter.filter(message),
+        Err(_) => Filtered::Allowed(message.to_string()),
+    }
+}
+
+/// What becomes of a chat message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filtered {
+    Allowed(String),
+    /// Sent with some of its words hidden.
+    Redacted(String),
+    /// Not sent at all.
+    Blocked,
+}
+
+pub trait ChatFilter: Send + Sync {
+    fn filter(&self, message: &str) -> Filtered;
+}
+
+/// Lets every message through, unchanged.
+#[derive(Debug, Default)]
+pub struct PassthroughFilter;
+
+impl ChatFilter for PassthroughFilter {
+    fn filter(&self, message: &str) -> Filtered {
+        Filtered::Allowed(message.to_string())
+    }
+}
+
+/// Replaces the banned words with `*`, whatever their case. Only whole words are redacted: banning
+/// "ass" leaves "class" alone.
+#[derive(Debug, Default)]
+pub struct WordListFilter {
+    /// In lowercase.
+    words: HashSet<String>,
+}
+
+impl WordListFilter {
+    pub fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(words: I) -> Self {
+        Self {
+            words: words
+                .into_iter()
+                .map(|word| word.as_ref().trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Reads a word list, one word per line. Lines starting with `#` are comments.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(Self::new(
+            content
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#')),
+        ))
+    }
+}
+
+impl ChatFilter for WordListFilter {
+    fn filter(&self, message: &str) -> Filtered {
+        let mut filtered = String::with_capacity(message.len());
+        let mut word = String::new();
+        let mut redacted = false;
+
+        let mut end_word = |word: &mut String, filtered: &mut String| {
+            if self.words.contains(&word.to_lowercase()) {
+                filtered.extend(std::iter::repeat_n('*', word.chars().count()));
+                redacted = true;
+            } else {
+                filtered.push_str(word);
+            }
+            word.clear();
+        };
+        for c in message.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else {
+                end_word(&mut word, &mut filtered);
+                filtered.push(c);
+            }
+        }
+        end_word(&mut word, &mut filtered);
+
+        if redacted {
+            Filtered::Redacted(filtered)
+        } else {
+            Filtered::Allowed(filtered)
+        }
+    }
+}
+
+/// The word list filter of `text-filtering-config` if it is set, the passthrough one otherwise.
+pub fn from_settings(settings: &Settings) -> Box<dyn ChatFilter> {
+    let Some(path) = &settings.text_filtering_config else {
+        return Box::new(PassthroughFilter);
+    };
+    match WordListFilter::load(Path::new(path)) {
+        Ok(filter) => {
+            info!(
+                "Filtering the chat with the {} words of {path}",
+                filter.words.len()
+            );
+            Box::new(filter)
+        }
+        Err(e) => {
+            error!("Failed to read the chat word list {path}: {e}, the chat isn't filtered");
+            Box::new(PassthroughFilter)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passthrough() {
+        let message = "Hello, world! <3";
+        assert_eq!(
+            PassthroughFilter.filter(message),
+            Filtered::Allowed(message.to_string())
+        );
+    }
+
+    #[test]
+    fn test_word_list() {
+        let filter = WordListFilter::new(["darn", " Heck "]);
+
+        assert_eq!(
+            filter.filter("Darn it, what the heck!"),
+            Filtered::Redacted("**** it, what the ****!".to_string())
+        );
+        assert_eq!(
+            filter.filter("darned hecks"),
+            Filtered::Allowed("darned hecks".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_word_list() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("words.txt");
+        fs::write(&path, "# Banned words\nheck\n\n").unwrap();
+
+        let filter = WordListFilter::load(&path).unwrap();
+        assert_eq!(filter.words, HashSet::from(["heck".to_string()]));
+    }
+}
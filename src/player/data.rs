@@ -0,0 +1,151 @@
+//! Player data files (`<uuid>.dat` in the player data directory), gzip-compressed NBT.
+//!
+//! Those files can be edited or replaced by anyone with access to the world, so they are read
+//! with the same NBT limits as chunks.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use log::warn;
+use nbt::Value;
+
+use super::uuid;
+use crate::region_parser::compression::CompressionScheme;
+use crate::region_parser::limits::NbtLimits;
+use crate::region_parser::nbt::read_compressed_root_compound;
+use crate::region_parser::RegionError;
+
+/// Reads the data of the player `uuid` (dashed or not) from `directory`. Returns `None` if the
+/// player has no data yet (first join).
+pub fn read(
+    directory: &Path,
+    uuid: &str,
+    limits: &NbtLimits,
+) -> Result<Option<HashMap<String, Value>>, RegionError> {
+    let name = uuid::to_dashed(uuid).unwrap_or_else(|| uuid.to_string());
+    let bytes = match fs::read(directory.join(format!("{name}.dat"))) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    read_compressed_root_compound(&bytes, CompressionScheme::Gzip, limits).map(Some)
+}
+
+/// Loads the data of the player `uuid` for a login. Like vanilla, data that can't be read is
+/// logged and the player starts over as if it was their first join.
+pub fn load(directory: &Path, uuid: &str, limits: &NbtLimits) -> Option<HashMap<String, Value>> {
+    read(directory, uuid, limits).unwrap_or_else(|e| {
+        warn!("Failed to load the player data of {uuid}: {e}");
+        None
+    })
+}
+
+/// Renames the player data files of `directory` named after an undashed or uppercase UUID to the
+/// canonical dashed form. A file already having the canonical name is kept. Returns how many files
+/// were renamed.
+pub fn migrate_file_names(directory: &Path) -> io::Result<usize> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut renamed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        let Some(stem) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".dat"))
+        else {
+            continue;
+        };
+        let Some(canonical) = uuid::to_dashed(stem).filter(|canonical| canonical != stem) else {
+            continue;
+        };
+
+        let target = directory.join(format!("{canonical}.dat"));
+        if !target.exists() {
+            fs::rename(&path, target)?;
+            renamed += 1;
+        }
+    }
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::capture;
+    use crate::region_parser::compression;
+    use tempfile::TempDir;
+
+    const UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+    fn write_player_data(directory: &Path, nbt: &[u8]) {
+        let compressed = compression::compress(nbt, CompressionScheme::Gzip).unwrap();
+        fs::write(directory.join(format!("{UUID}.dat")), compressed).unwrap();
+    }
+
+    #[test]
+    fn test_read_player_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let limits = NbtLimits::default();
+        assert_eq!(read(temp_dir.path(), UUID, &limits).unwrap(), None);
+
+        // {Health: 20.0f}
+        let nbt = [
+            0x0a, 0, 0, 0x05, 0, 6, b'H', b'e', b'a', b'l', b't', b'h', 0x41, 0xa0, 0, 0, 0,
+        ];
+        write_player_data(temp_dir.path(), &nbt);
+
+        let data = read(temp_dir.path(), UUID, &limits).unwrap().unwrap();
+        assert_eq!(data.get("Health"), Some(&Value::Float(20.0)));
+    }
+
+    #[test]
+    fn test_migrate_file_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let undashed = UUID.replace('-', "").to_uppercase();
+        fs::write(temp_dir.path().join(format!("{undashed}.dat")), []).unwrap();
+        fs::write(temp_dir.path().join("level.dat"), []).unwrap();
+
+        assert_eq!(migrate_file_names(temp_dir.path()).unwrap(), 1);
+        assert!(temp_dir.path().join(format!("{UUID}.dat")).is_file());
+        assert!(temp_dir.path().join("level.dat").is_file());
+        assert_eq!(migrate_file_names(temp_dir.path()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_player_data_limits() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // A Compound nested 1000 times, compressing to a few bytes.
+        let mut nbt = vec![0x0a, 0, 0];
+        for _ in 0..1000 {
+            nbt.extend([0x0a, 0, 0]);
+        }
+        nbt.extend(vec![0; 1001]);
+        write_player_data(temp_dir.path(), &nbt);
+
+        assert!(matches!(
+            read(temp_dir.path(), UUID, &NbtLimits::default()),
+            Err(RegionError::NbtTooDeep(_))
+        ));
+        assert!(matches!(
+            read(temp_dir.path(), UUID, &NbtLimits::new(100, 2000)),
+            Err(RegionError::NbtTooLarge(101, 100))
+        ));
+
+        capture::start();
+        assert_eq!(load(temp_dir.path(), UUID, &NbtLimits::default()), None);
+        assert_eq!(
+            capture::take(),
+            [format!(
+                "WARN Failed to load the player data of {UUID}: NBT too deep, the maximum depth is 512"
+            )]
+        );
+    }
+}
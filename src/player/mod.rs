@@ -1,16 +1,51 @@
+pub mod chat_filter;
+pub mod data;
+pub mod mojang;
+pub mod online;
+pub mod uuid;
+
+use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde_json::Value;
 use std::error::Error;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+
+use mojang::RetryPolicy;
+use uuid::UuidResolver;
+
+static ONLINE_PLAYERS: Lazy<Arc<AtomicUsize>> = Lazy::new(|| Arc::new(AtomicUsize::new(0)));
+
+/// Returns the number of players currently connected to the server.
+pub fn online_players() -> Arc<AtomicUsize> {
+    ONLINE_PLAYERS.clone()
+}
 
+/// Resolves the UUID of a player (without dashes), online or offline depending on
+/// `UuidResolver::current`.
 pub async fn get_uuid(username: &str) -> Result<String, Box<dyn Error>> {
+    resolve_uuid(UuidResolver::current(), username).await
+}
+
+/// Resolves the UUID of a player (without dashes) with `resolver`.
+pub async fn resolve_uuid(
+    resolver: UuidResolver,
+    username: &str,
+) -> Result<String, Box<dyn Error>> {
+    match resolver {
+        UuidResolver::Online => get_online_uuid(username).await,
+        UuidResolver::Offline => Ok(uuid::offline_uuid(username)),
+    }
+}
+
+async fn get_online_uuid(username: &str) -> Result<String, Box<dyn Error>> {
     let url = format!(
         "https://api.mojang.com/users/profiles/minecraft/{}",
         username
     );
     let client = Client::new();
-    let response = client.get(&url).send().await?;
-    let body = response.text().await?;
-    get_id(&body)
+    let response = mojang::get_with_retry(&client, &url, &RetryPolicy::default()).await?;
+    get_id(&response.body)
 }
 
 fn get_id(all: &str) -> Result<String, Box<dyn Error>> {
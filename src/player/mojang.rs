@@ -0,0 +1,272 @@
+//! Calls to Mojang's rate-limited web API, retried with a backoff on transient failures.
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::warn;
+use rand::Rng;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MojangError {
+    #[error("HTTP request failed: {0}")]
+    Http(String),
+
+    #[error("Mojang API still failing after {attempts} attempts (last status: {status:?})")]
+    RetriesExhausted { attempts: u32, status: Option<u16> },
+}
+
+/// The parts of an HTTP response the API calls need.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// The `Retry-After` header, when the server sent one (in seconds).
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Too many requests or server errors, which may succeed later.
+    fn is_transient(&self) -> bool {
+        self.status == 429 || (500..600).contains(&self.status)
+    }
+}
+
+/// A minimal HTTP client, so that tests can replace the network.
+#[async_trait]
+pub trait HttpClient: Send + Sync {
+    async fn get(&self, url: &str) -> Result<HttpResponse, MojangError>;
+}
+
+#[async_trait]
+impl HttpClient for reqwest::Client {
+    async fn get(&self, url: &str) -> Result<HttpResponse, MojangError> {
+        let response = reqwest::Client::get(self, url)
+            .send()
+            .await
+            .map_err(|e| MojangError::Http(e.to_string()))?;
+
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response
+            .text()
+            .await
+            .map_err(|e| MojangError::Http(e.to_string()))?;
+
+        Ok(HttpResponse {
+            status,
+            retry_after,
+            body,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled at each retry.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following attempt number `attempt` (starting at 1): exponential,
+    /// with a random jitter so that clients don't retry all at once.
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        exponential.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// GETs `url`, retrying transient failures (connection errors, 429 and 5xx statuses) according
+/// to `policy`. A `Retry-After` header replaces the computed delay, unless it's longer than
+/// `policy.max_delay`: the call gives up then, rather than making the caller wait that long.
+///
+/// Any other status is returned as is, e.g. 404 when a player doesn't exist.
+pub async fn get_with_retry(
+    client: &dyn HttpClient,
+    url: &str,
+    policy: &RetryPolicy,
+) -> Result<HttpResponse, MojangError> {
+    let mut last_status = None;
+
+    for attempt in 1..=policy.max_attempts {
+        let retry_after = match client.get(url).await {
+            Ok(response) if !response.is_transient() => return Ok(response),
+            Ok(response) => {
+                warn!(
+                    "Mojang API answered {} (attempt {attempt})",
+                    response.status
+                );
+                last_status = Some(response.status);
+                response.retry_after
+            }
+            Err(e) => {
+                warn!("Mojang API request failed (attempt {attempt}): {e}");
+                None
+            }
+        };
+
+        if attempt == policy.max_attempts {
+            break;
+        }
+        let delay = match retry_after {
+            Some(delay) if delay > policy.max_delay => {
+                warn!("Mojang API asked to retry after {delay:?}, giving up");
+                return Err(MojangError::RetriesExhausted {
+                    attempts: attempt,
+                    status: last_status,
+                });
+            }
+            Some(delay) => delay,
+            None => policy.delay(attempt),
+        };
+        tokio::time::sleep(delay).await;
+    }
+
+    Err(MojangError::RetriesExhausted {
+        attempts: policy.max_attempts,
+        status: last_status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Answers with the queued responses, then with 503.
+    struct MockClient {
+        responses: Mutex<VecDeque<Result<HttpResponse, MojangError>>>,
+        calls: Mutex<u32>,
+    }
+
+    impl MockClient {
+        fn new(responses: Vec<Result<HttpResponse, MojangError>>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                calls: Mutex::new(0),
+            }
+        }
+
+        fn calls(&self) -> u32 {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl HttpClient for MockClient {
+        async fn get(&self, _url: &str) -> Result<HttpResponse, MojangError> {
+            *self.calls.lock().unwrap() += 1;
+            self.responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| Ok(response(503, "")))
+        }
+    }
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            retry_after: None,
+            body: body.to_string(),
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_succeeds_on_second_attempt() {
+        let rate_limited = HttpResponse {
+            retry_after: Some(Duration::from_millis(1)),
+            ..response(429, "")
+        };
+        let client = MockClient::new(vec![Ok(rate_limited), Ok(response(200, "{}"))]);
+
+        let result = get_with_retry(&client, "url", &policy()).await;
+        assert_eq!(result, Ok(response(200, "{}")));
+        assert_eq!(client.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted() {
+        let client = MockClient::new(vec![Err(MojangError::Http("reset".to_string()))]);
+
+        let result = get_with_retry(&client, "url", &policy()).await;
+        assert_eq!(
+            result,
+            Err(MojangError::RetriesExhausted {
+                attempts: 3,
+                status: Some(503)
+            })
+        );
+        assert_eq!(client.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_on_long_retry_after() {
+        let rate_limited = HttpResponse {
+            retry_after: Some(Duration::from_secs(60)),
+            ..response(429, "")
+        };
+        let client = MockClient::new(vec![Ok(rate_limited), Ok(response(200, "{}"))]);
+
+        let result = get_with_retry(&client, "url", &policy()).await;
+        assert_eq!(
+            result,
+            Err(MojangError::RetriesExhausted {
+                attempts: 1,
+                status: Some(429)
+            })
+        );
+        assert_eq!(client.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_client_error() {
+        let client = MockClient::new(vec![Ok(response(404, ""))]);
+
+        let result = get_with_retry(&client, "url", &policy()).await;
+        assert_eq!(result.map(|r| r.status), Ok(404));
+        assert_eq!(client.calls(), 1);
+    }
+
+    #[test]
+    fn test_delay_bounds() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        assert!(policy.delay(9) <= Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,64 @@
+//! The players connected to the server, each with the channel of the chat messages sent to it.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+static ONLINE: Lazy<Arc<RwLock<OnlinePlayers>>> =
+    Lazy::new(|| Arc::new(RwLock::new(OnlinePlayers::default())));
+
+/// Returns the players connected to the server.
+pub fn global() -> Arc<RwLock<OnlinePlayers>> {
+    ONLINE.clone()
+}
+
+#[derive(Debug)]
+struct OnlinePlayer {
+    name: String,
+    /// Operator level, 0 if the player isn't an operator.
+    op_level: u8,
+    /// The connection of the player writes what it receives as system chat messages.
+    chat: UnboundedSender<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct OnlinePlayers {
+    players: Vec<OnlinePlayer>,
+}
+
+impl OnlinePlayers {
+    /// Adds a player, replacing one with the same name. Returns the receiver of its chat messages.
+    // TODO: Call it once the players log in.
+    pub fn join(&mut self, name: &str, op_level: u8) -> UnboundedReceiver<String> {
+        self.leave(name);
+        let (chat, receiver) = mpsc::unbounded_channel();
+        self.players.push(OnlinePlayer {
+            name: name.to_string(),
+            op_level,
+            chat,
+        });
+        receiver
+    }
+
+    pub fn leave(&mut self, name: &str) {
+        self.players.retain(|player| player.name != name);
+    }
+
+    /// The names of the players, in the order they joined.
+    pub fn names(&self) -> Vec<String> {
+        self.players
+            .iter()
+            .map(|player| player.name.clone())
+            .collect()
+    }
+
+    /// Sends a chat message to every player of at least `min_op_level`.
+    /// Returns how many players it was sent to.
+    pub fn send_to_ops(&self, message: &str, min_op_level: u8) -> usize {
+        self.players
+            .iter()
+            .filter(|player| player.op_level >= min_op_level)
+            .filter(|player| player.chat.send(message.to_string()).is_ok())
+            .count()
+    }
+}
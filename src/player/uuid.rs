@@ -0,0 +1,117 @@
+//! How player UUIDs are resolved from player names.
+use md5::{Digest, Md5};
+
+use crate::config::Settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UuidResolver {
+    /// Asks Mojang's API.
+    Online,
+    /// Derives the UUID from the name, like vanilla servers with `online-mode=false`. Needs no
+    /// network access, hence the default.
+    #[default]
+    Offline,
+}
+
+impl UuidResolver {
+    pub fn from_settings(settings: &Settings) -> Self {
+        if settings.online_mode {
+            Self::Online
+        } else {
+            Self::Offline
+        }
+    }
+
+    /// The resolver matching the `online-mode` of server.properties.
+    pub fn current() -> Self {
+        Self::from_settings(&Settings::new())
+    }
+}
+
+/// The UUID of a player on an offline server: a version 3 UUID of "OfflinePlayer:<name>", as
+/// Java's `UUID.nameUUIDFromBytes`. Returned without dashes, like Mojang's API.
+pub fn offline_uuid(name: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{name}")).into();
+    hash[6] = (hash[6] & 0x0f) | 0x30; // version 3
+    hash[8] = (hash[8] & 0x3f) | 0x80; // IETF variant
+
+    hash.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The canonical form of a UUID: lowercase and dashed (`8-4-4-4-12`), like the files of vanilla.
+/// Accepts the dashed and undashed forms. `None` if `uuid` isn't a UUID.
+pub fn to_dashed(uuid: &str) -> Option<String> {
+    let hex = to_undashed(uuid)?;
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+/// The UUID without dashes, in lowercase, like Mojang's API. `None` if `uuid` isn't a UUID.
+pub fn to_undashed(uuid: &str) -> Option<String> {
+    let dashed = uuid.len() == 36
+        && [8, 13, 18, 23]
+            .into_iter()
+            .all(|i| uuid.as_bytes()[i] == b'-');
+    let hex: String = match dashed {
+        true => uuid.chars().filter(|&c| c != '-').collect(),
+        false => uuid.to_string(),
+    };
+
+    (hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hex.to_ascii_lowercase())
+}
+
+/// Whether `a` and `b` are the same UUID, dashed or not. Strings that aren't UUIDs are compared
+/// ignoring the case.
+pub fn same_uuid(a: &str, b: &str) -> bool {
+    match (to_undashed(a), to_undashed(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid_forms() {
+        let dashed = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        let undashed = "069a79f444e94726a5befca90e38aaf5";
+
+        assert_eq!(to_dashed(undashed).unwrap(), dashed);
+        assert_eq!(to_dashed(&dashed.to_uppercase()).unwrap(), dashed);
+        assert_eq!(to_undashed(dashed).unwrap(), undashed);
+        assert!(same_uuid(dashed, &undashed.to_uppercase()));
+        assert!(!same_uuid(dashed, "5627dd98e6be3c21b8a8e92344183641"));
+
+        for invalid in [
+            "",
+            "Steve",
+            "069a79f4-44e94726-a5be-fca90e38aaf5-",
+            "069a79f444e94726a5befca90e38aafg",
+        ] {
+            assert_eq!(to_dashed(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_offline_uuid() {
+        assert_eq!(offline_uuid("Steve"), "5627dd98e6be3c21b8a8e92344183641");
+        assert_ne!(offline_uuid("Alex"), offline_uuid("Steve"));
+    }
+
+    #[tokio::test]
+    async fn test_offline_resolver() {
+        let uuid = crate::player::resolve_uuid(UuidResolver::Offline, "Steve")
+            .await
+            .unwrap();
+        assert_eq!(uuid, "5627dd98e6be3c21b8a8e92344183641");
+    }
+}
@@ -0,0 +1,81 @@
+//! The compressed, on-disk form of a chunk.
+use super::compression::{self, CompressionScheme};
+use super::limits::NbtLimits;
+use super::nbt;
+use super::RegionError;
+use crate::chunks_manager::Chunk;
+
+/// A chunk as stored in a region file: compressed NBT data.
+///
+/// Use `from_chunk` and `to_chunk` to convert from and to the canonical `Chunk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedChunk {
+    x: i32,
+    z: i32,
+    scheme: CompressionScheme,
+    data: Vec<u8>,
+}
+
+impl CompressedChunk {
+    /// Wraps already compressed chunk data, e.g. read from a region file.
+    pub fn new(x: i32, z: i32, scheme: CompressionScheme, data: Vec<u8>) -> Self {
+        Self { x, z, scheme, data }
+    }
+
+    /// Serializes a chunk to NBT and compresses it.
+    pub fn from_chunk(chunk: &Chunk, scheme: CompressionScheme) -> Result<Self, RegionError> {
+        let nbt = nbt::chunk_to_nbt(chunk)?;
+        Ok(Self {
+            x: chunk.get_x(),
+            z: chunk.get_z(),
+            scheme,
+            data: compression::compress(&nbt, scheme)?,
+        })
+    }
+
+    /// Decompresses and deserializes the chunk, rejecting data exceeding `limits`.
+    pub fn to_chunk(&self, limits: &NbtLimits) -> Result<Chunk, RegionError> {
+        let max_bytes = limits.max_bytes as u64 + 1;
+        let nbt = compression::decompress_limited(&self.data, self.scheme, max_bytes)?;
+        nbt::chunk_from_nbt(&nbt, limits)
+    }
+
+    /// The chunk X coordinate.
+    pub fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    /// The chunk Z coordinate.
+    pub fn get_z(&self) -> i32 {
+        self.z
+    }
+
+    /// The compression scheme of `data`.
+    pub fn get_scheme(&self) -> CompressionScheme {
+        self.scheme
+    }
+
+    /// The compressed NBT data.
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::generate_world;
+
+    #[test]
+    fn test_generated_chunk_roundtrip() {
+        let chunk = generate_world(3, -2);
+
+        for scheme in [CompressionScheme::Zlib, CompressionScheme::Gzip] {
+            let compressed = CompressedChunk::from_chunk(&chunk, scheme).unwrap();
+
+            assert_eq!(compressed.get_x(), 3);
+            assert_eq!(compressed.get_z(), -2);
+            assert_eq!(compressed.to_chunk(&NbtLimits::default()).unwrap(), chunk);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Compression schemes used for chunks stored inside region files.
+use std::io::{Read, Write};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+use super::RegionError;
+
+/// The compression scheme byte stored in front of each chunk in a region file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionScheme {
+    Gzip = 1,
+    Zlib = 2,
+    Uncompressed = 3,
+}
+
+impl TryFrom<u8> for CompressionScheme {
+    type Error = RegionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Gzip),
+            2 => Ok(Self::Zlib),
+            3 => Ok(Self::Uncompressed),
+            other => Err(RegionError::UnsupportedCompression(other)),
+        }
+    }
+}
+
+/// Compresses `data` with the given scheme.
+pub fn compress(data: &[u8], scheme: CompressionScheme) -> Result<Vec<u8>, RegionError> {
+    match scheme {
+        CompressionScheme::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        CompressionScheme::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        CompressionScheme::Uncompressed => Ok(data.to_vec()),
+    }
+}
+
+/// Decompresses `data` that was compressed with the given scheme.
+pub fn decompress(data: &[u8], scheme: CompressionScheme) -> Result<Vec<u8>, RegionError> {
+    decompress_limited(data, scheme, u64::MAX)
+}
+
+/// Decompresses at most `max_bytes` bytes of `data`, the rest is dropped. Protects against
+/// compression bombs: checking the size of the result tells whether it was truncated.
+pub fn decompress_limited(
+    data: &[u8],
+    scheme: CompressionScheme,
+    max_bytes: u64,
+) -> Result<Vec<u8>, RegionError> {
+    let mut result = Vec::new();
+    match scheme {
+        CompressionScheme::Gzip => {
+            GzDecoder::new(data)
+                .take(max_bytes)
+                .read_to_end(&mut result)?;
+        }
+        CompressionScheme::Zlib => {
+            ZlibDecoder::new(data)
+                .take(max_bytes)
+                .read_to_end(&mut result)?;
+        }
+        CompressionScheme::Uncompressed => {
+            data.take(max_bytes).read_to_end(&mut result)?;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let data = b"Hello, region! Hello, region! Hello, region!".repeat(10);
+
+        for scheme in [
+            CompressionScheme::Gzip,
+            CompressionScheme::Zlib,
+            CompressionScheme::Uncompressed,
+        ] {
+            let compressed = compress(&data, scheme).unwrap();
+            assert_eq!(decompress(&compressed, scheme).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_decompress_limited() {
+        let data = vec![0; 4096];
+        let compressed = compress(&data, CompressionScheme::Zlib).unwrap();
+
+        let result = decompress_limited(&compressed, CompressionScheme::Zlib, 100).unwrap();
+        assert_eq!(result.len(), 100);
+    }
+}
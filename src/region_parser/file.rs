@@ -0,0 +1,115 @@
+//! Writing the region files to disk, as set by the `sync-chunk-writes` property.
+//!
+//! Syncing makes a saved chunk survive a crash or a power loss, since the write has reached the
+//! disk when the save returns. Without it the OS flushes the data when it sees fit, which is much
+//! faster but can lose the last saved chunks.
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::RegionError;
+use crate::config::Settings;
+
+/// Whether the region writes wait for the data to reach the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncMode {
+    /// `File::sync_all` after each write, for durability.
+    #[default]
+    Sync,
+    /// Leave the flushing to the OS, for throughput.
+    NoSync,
+}
+
+impl SyncMode {
+    pub fn from_settings(settings: &Settings) -> Self {
+        if settings.sync_chunk_writes {
+            Self::Sync
+        } else {
+            Self::NoSync
+        }
+    }
+}
+
+/// A destination of region data that can be synced to disk.
+pub trait SyncWrite: Write {
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl SyncWrite for File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
+/// Writes `data` to `writer`, then syncs it if `mode` says so.
+pub fn write_region<W: SyncWrite>(writer: &mut W, data: &[u8], mode: SyncMode) -> io::Result<()> {
+    writer.write_all(data)?;
+    writer.flush()?;
+    if mode == SyncMode::Sync {
+        writer.sync()?;
+    }
+    Ok(())
+}
+
+/// Writes `data` to the region file at `path`, replacing its content.
+pub fn save_to_file(path: &Path, data: &[u8], mode: SyncMode) -> Result<(), RegionError> {
+    let mut file = File::create(path)?;
+    write_region(&mut file, data, mode)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the syncs instead of doing them.
+    #[derive(Default)]
+    struct RecordingWriter {
+        data: Vec<u8>,
+        syncs: usize,
+    }
+
+    impl Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SyncWrite for RecordingWriter {
+        fn sync(&mut self) -> io::Result<()> {
+            self.syncs += 1;
+            Ok(())
+        }
+    }
+
+    fn mode(sync_chunk_writes: &str) -> SyncMode {
+        SyncMode::from_settings(&Settings::with(&[("sync-chunk-writes", sync_chunk_writes)]))
+    }
+
+    #[test]
+    fn test_sync_chunk_writes() {
+        for (property, syncs) in [("true", 1), ("false", 0)] {
+            let mut writer = RecordingWriter::default();
+            write_region(&mut writer, b"region", mode(property)).unwrap();
+
+            assert_eq!(writer.data, b"region");
+            assert_eq!(writer.syncs, syncs, "sync-chunk-writes={property}");
+        }
+    }
+
+    #[test]
+    fn test_save_to_file() -> Result<(), RegionError> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("r.0.0.mca");
+
+        for mode in [SyncMode::Sync, SyncMode::NoSync] {
+            save_to_file(&path, b"region", mode)?;
+            assert_eq!(std::fs::read(&path)?, b"region");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+//! Limits enforced on untrusted NBT before handing it to the parser.
+//!
+//! `nbt::Value::from_reader` trusts the lengths written in the data and recurses on nested tags,
+//! so a crafted file could exhaust the memory (a huge array length) or the stack (deeply nested
+//! compounds). The data is therefore walked once without allocating, checking its size, its
+//! nesting depth and that every length fits in the remaining bytes.
+use std::io;
+
+use super::RegionError;
+use crate::config::Settings;
+
+/// Default maximum size of an uncompressed NBT document, in bytes.
+pub const DEFAULT_MAX_BYTES: usize = 8 * 1024 * 1024;
+/// Default maximum nesting depth of Compounds and Lists.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+const END_TAG: u8 = 0x00;
+const COMPOUND_TAG: u8 = 0x0a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NbtLimits {
+    pub max_bytes: usize,
+    pub max_depth: usize,
+}
+
+impl Default for NbtLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl NbtLimits {
+    pub fn new(max_bytes: usize, max_depth: usize) -> Self {
+        Self {
+            max_bytes,
+            max_depth,
+        }
+    }
+
+    /// Reads the limits from the non-standard `max-nbt-bytes` and `max-nbt-depth` settings.
+    /// A missing, invalid or zero value keeps the default.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let read = |key: &str, default: usize| {
+            settings
+                .extra(key)
+                .and_then(|value| value.parse::<usize>().ok())
+                .filter(|&value| value > 0)
+                .unwrap_or(default)
+        };
+        Self::new(
+            read("max-nbt-bytes", DEFAULT_MAX_BYTES),
+            read("max-nbt-depth", DEFAULT_MAX_DEPTH),
+        )
+    }
+
+    /// Checks that `bytes`, a named root Compound, respects these limits.
+    pub fn check(&self, bytes: &[u8]) -> Result<(), RegionError> {
+        if bytes.len() > self.max_bytes {
+            return Err(RegionError::NbtTooLarge(bytes.len(), self.max_bytes));
+        }
+
+        let mut reader = bytes;
+        if take_u8(&mut reader)? != COMPOUND_TAG {
+            return Err(::nbt::Error::NoRootCompound.into());
+        }
+        skip_string(&mut reader)?;
+        self.skip_payload(&mut reader, COMPOUND_TAG, 1)
+    }
+
+    /// Checks that the network NBT at the beginning of `bytes` respects these limits: a root
+    /// Compound without a name, as sent in the packets. Returns its size, the following bytes being
+    /// ignored.
+    pub fn check_network(&self, bytes: &[u8]) -> Result<usize, RegionError> {
+        let mut reader = bytes;
+        if take_u8(&mut reader)? != COMPOUND_TAG {
+            return Err(::nbt::Error::NoRootCompound.into());
+        }
+        self.skip_payload(&mut reader, COMPOUND_TAG, 1)?;
+
+        let size = bytes.len() - reader.len();
+        if size > self.max_bytes {
+            return Err(RegionError::NbtTooLarge(size, self.max_bytes));
+        }
+        Ok(size)
+    }
+
+    fn skip_payload(&self, reader: &mut &[u8], tag: u8, depth: usize) -> Result<(), RegionError> {
+        match tag {
+            0x01 => skip(reader, 1),
+            0x02 => skip(reader, 2),
+            0x03 | 0x05 => skip(reader, 4),
+            0x04 | 0x06 => skip(reader, 8),
+            0x07 => skip_array(reader, 1),
+            0x08 => skip_string(reader),
+            0x09 => {
+                self.check_depth(depth)?;
+                let element_tag = take_u8(reader)?;
+                let length = take_length(reader)?;
+                if element_tag == END_TAG && length > 0 {
+                    return Err(RegionError::MalformedNbt(
+                        "List of End tags with a non-zero length".to_string(),
+                    ));
+                }
+                for _ in 0..length {
+                    self.skip_payload(reader, element_tag, depth + 1)?;
+                }
+                Ok(())
+            }
+            COMPOUND_TAG => {
+                self.check_depth(depth)?;
+                loop {
+                    let tag = take_u8(reader)?;
+                    if tag == END_TAG {
+                        return Ok(());
+                    }
+                    skip_string(reader)?;
+                    self.skip_payload(reader, tag, depth + 1)?;
+                }
+            }
+            0x0b => skip_array(reader, 4),
+            0x0c => skip_array(reader, 8),
+            _ => Err(RegionError::MalformedNbt(format!("Unknown tag ID {tag}"))),
+        }
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), RegionError> {
+        if depth > self.max_depth {
+            Err(RegionError::NbtTooDeep(self.max_depth))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn skip(reader: &mut &[u8], count: usize) -> Result<(), RegionError> {
+    if reader.len() < count {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    *reader = &reader[count..];
+    Ok(())
+}
+
+fn take_u8(reader: &mut &[u8]) -> Result<u8, RegionError> {
+    let byte = *reader
+        .first()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    *reader = &reader[1..];
+    Ok(byte)
+}
+
+/// Reads the (signed, 4 bytes) length of a List or an array.
+fn take_length(reader: &mut &[u8]) -> Result<usize, RegionError> {
+    let bytes: [u8; 4] = reader
+        .get(..4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    *reader = &reader[4..];
+
+    usize::try_from(i32::from_be_bytes(bytes))
+        .map_err(|_| RegionError::MalformedNbt("Negative length".to_string()))
+}
+
+fn skip_array(reader: &mut &[u8], element_size: usize) -> Result<(), RegionError> {
+    let length = take_length(reader)?;
+    skip(reader, length.saturating_mul(element_size))
+}
+
+fn skip_string(reader: &mut &[u8]) -> Result<(), RegionError> {
+    let bytes: [u8; 2] = reader
+        .get(..2)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    *reader = &reader[2..];
+    skip(reader, u16::from_be_bytes(bytes) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::generate_world;
+    use crate::region_parser::nbt::chunk_to_nbt;
+
+    /// A root Compound containing `depth - 1` nested Compounds.
+    fn nested_compounds(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![COMPOUND_TAG, 0, 0];
+        for _ in 1..depth {
+            bytes.extend([COMPOUND_TAG, 0, 1, b'a']);
+        }
+        bytes.extend(vec![END_TAG; depth]);
+        bytes
+    }
+
+    #[test]
+    fn test_from_settings() {
+        assert_eq!(
+            NbtLimits::from_settings(&Settings::with(&[])),
+            NbtLimits::default()
+        );
+        let settings = Settings::with(&[("max-nbt-bytes", "1024"), ("max-nbt-depth", "0")]);
+        assert_eq!(
+            NbtLimits::from_settings(&settings),
+            NbtLimits::new(1024, DEFAULT_MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn test_valid_nbt() {
+        let bytes = chunk_to_nbt(&generate_world(0, 0)).unwrap();
+        assert!(NbtLimits::default().check(&bytes).is_ok());
+        assert!(NbtLimits::new(1024, 2).check(&nested_compounds(2)).is_ok());
+    }
+
+    #[test]
+    fn test_too_deep_nbt() {
+        let limits = NbtLimits::new(1024, 16);
+
+        assert!(limits.check(&nested_compounds(16)).is_ok());
+        assert!(matches!(
+            limits.check(&nested_compounds(17)),
+            Err(RegionError::NbtTooDeep(16))
+        ));
+    }
+
+    #[test]
+    fn test_too_large_nbt() {
+        let bytes = chunk_to_nbt(&generate_world(0, 0)).unwrap();
+        let limits = NbtLimits::new(bytes.len() - 1, DEFAULT_MAX_DEPTH);
+
+        assert!(matches!(
+            limits.check(&bytes),
+            Err(RegionError::NbtTooLarge(size, _)) if size == bytes.len()
+        ));
+    }
+
+    #[test]
+    fn test_length_larger_than_data() {
+        // A Long Array claiming 2^31 - 1 longs.
+        let bytes = [
+            COMPOUND_TAG,
+            0,
+            0,
+            0x0c,
+            0,
+            1,
+            b'a',
+            0x7f,
+            0xff,
+            0xff,
+            0xff,
+            END_TAG,
+        ];
+        assert!(matches!(
+            NbtLimits::default().check(&bytes),
+            Err(RegionError::Io(_))
+        ));
+    }
+}
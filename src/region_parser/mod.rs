@@ -0,0 +1,41 @@
+//! This module is the interface between the canonical `Chunk` and the way chunks are stored on
+//! disk in Anvil region files (NBT, compressed).
+pub mod chunk;
+pub mod compression;
+pub mod file;
+pub mod limits;
+pub mod nbt;
+pub mod region;
+
+use std::io;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RegionError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("NBT error: {0}")]
+    Nbt(#[from] ::nbt::Error),
+
+    #[error("Missing NBT field: {0}")]
+    MissingField(String),
+
+    #[error("Invalid NBT field: {0}")]
+    InvalidField(String),
+
+    #[error("Unsupported compression scheme: {0}")]
+    UnsupportedCompression(u8),
+
+    #[error("Malformed NBT: {0}")]
+    MalformedNbt(String),
+
+    #[error("NBT too large: {0} bytes, the maximum is {1}")]
+    NbtTooLarge(usize, usize),
+
+    #[error("NBT too deep, the maximum depth is {0}")]
+    NbtTooDeep(usize),
+
+    #[error("Malformed region file: {0}")]
+    MalformedRegion(String),
+}
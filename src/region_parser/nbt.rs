@@ -0,0 +1,306 @@
+//! Conversion between the canonical `Chunk` and its NBT representation.
+//!
+//! The layout follows the Anvil chunk format (`xPos`, `zPos`, `sections`, each section having a
+//! `Y` and a `block_states` compound with a `palette` and packed `data`). Until a block registry
+//! exists, palette entries are stored as block state IDs (Int) instead of `{Name: ...}`
+//! compounds.
+use std::collections::HashMap;
+use std::io::Read;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use nbt::{Blob, Value};
+
+use super::compression::{self, CompressionScheme};
+use super::limits::NbtLimits;
+use super::RegionError;
+use crate::chunks_manager::heightmap::heightmaps_nbt;
+use crate::chunks_manager::{
+    BlockState, Chunk, ChunkSection, OVERWORLD_SECTION_COUNT, SECTION_VOLUME,
+};
+use crate::consts;
+
+/// The NBT tag ID of a Compound.
+const COMPOUND_TAG: u8 = 0x0a;
+
+/// The most sections a chunk can have, those of the overworld. Bounds the sections allocated
+/// for a chunk whatever its `yPos`.
+const MAX_SECTIONS: usize = OVERWORLD_SECTION_COUNT;
+
+/// Minimum number of bits per block used in a section palette.
+const MIN_BITS_PER_BLOCK: usize = 4;
+
+/// Serializes a chunk to uncompressed NBT bytes.
+pub fn chunk_to_nbt(chunk: &Chunk) -> Result<Vec<u8>, RegionError> {
+    let mut sections = Vec::with_capacity(chunk.get_sections().len());
+    for (i, section) in chunk.get_sections().iter().enumerate() {
+        let y = chunk.get_min_section_y() + i as i32;
+        sections.push(section_to_nbt(y as i8, section));
+    }
+
+    let mut blob = Blob::new();
+    blob.insert("DataVersion", consts::minecraft::DATA_VERSION)?;
+    blob.insert("xPos", chunk.get_x())?;
+    blob.insert("zPos", chunk.get_z())?;
+    blob.insert("yPos", chunk.get_min_section_y())?;
+    blob.insert("Status", "minecraft:full")?;
+    blob.insert("Heightmaps", heightmaps_nbt(chunk))?;
+    blob.insert("sections", Value::List(sections))?;
+
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Deserializes a chunk from uncompressed NBT bytes, rejecting data exceeding `limits`.
+pub fn chunk_from_nbt(bytes: &[u8], limits: &NbtLimits) -> Result<Chunk, RegionError> {
+    let root = read_root_compound(bytes, limits)?;
+
+    let x = get_int(&root, "xPos")?;
+    let z = get_int(&root, "zPos")?;
+    let min_section_y = get_int(&root, "yPos")?;
+
+    let sections = match root.get("sections") {
+        Some(Value::List(sections)) => sections,
+        Some(_) => return Err(RegionError::InvalidField("sections".to_string())),
+        None => return Err(RegionError::MissingField("sections".to_string())),
+    };
+
+    let mut parsed: Vec<ChunkSection> = Vec::with_capacity(sections.len());
+    for section in sections {
+        let (y, section) = match section {
+            Value::Compound(section) => section_from_nbt(section)?,
+            _ => return Err(RegionError::InvalidField("sections".to_string())),
+        };
+
+        // Sections can be stored in any order, and missing ones only contain air.
+        let index = (y as i32)
+            .checked_sub(min_section_y)
+            .and_then(|index| usize::try_from(index).ok())
+            .filter(|&index| index < MAX_SECTIONS)
+            .ok_or_else(|| RegionError::InvalidField("Y".to_string()))?;
+        if index >= parsed.len() {
+            parsed.resize(index + 1, ChunkSection::default());
+        }
+        parsed[index] = section;
+    }
+
+    Ok(Chunk::from_sections(x, z, min_section_y, parsed))
+}
+
+/// Reads a root (named) Compound tag and returns its content, rejecting data exceeding `limits`.
+pub fn read_root_compound(
+    bytes: &[u8],
+    limits: &NbtLimits,
+) -> Result<HashMap<String, Value>, RegionError> {
+    limits.check(bytes)?;
+    let mut reader = bytes;
+
+    if reader.read_u8()? != COMPOUND_TAG {
+        return Err(nbt::Error::NoRootCompound.into());
+    }
+
+    // Skip the name of the root tag.
+    let name_length = reader.read_u16::<BigEndian>()?;
+    let mut name = vec![0; name_length as usize];
+    reader.read_exact(&mut name)?;
+
+    match Value::from_reader(COMPOUND_TAG, &mut reader)? {
+        Value::Compound(map) => Ok(map),
+        _ => Err(nbt::Error::NoRootCompound.into()),
+    }
+}
+
+/// Decompresses `bytes` and reads its root Compound, rejecting data exceeding `limits`.
+pub fn read_compressed_root_compound(
+    bytes: &[u8],
+    scheme: CompressionScheme,
+    limits: &NbtLimits,
+) -> Result<HashMap<String, Value>, RegionError> {
+    // One byte more than the limit, so that too large data is detected and not truncated.
+    let max_bytes = limits.max_bytes as u64 + 1;
+    let nbt = compression::decompress_limited(bytes, scheme, max_bytes)?;
+    read_root_compound(&nbt, limits)
+}
+
+fn section_to_nbt(y: i8, section: &ChunkSection) -> Value {
+    let mut palette: Vec<BlockState> = Vec::new();
+    let mut indices: Vec<u64> = Vec::with_capacity(SECTION_VOLUME);
+
+    for state in section.states() {
+        let index = match palette.iter().position(|&s| s == state) {
+            Some(index) => index,
+            None => {
+                palette.push(state);
+                palette.len() - 1
+            }
+        };
+        indices.push(index as u64);
+    }
+
+    let mut block_states = HashMap::new();
+    if palette.len() > 1 {
+        let data = pack(&indices, bits_per_block(palette.len()));
+        block_states.insert("data".to_string(), Value::LongArray(data));
+    }
+    block_states.insert(
+        "palette".to_string(),
+        Value::List(palette.iter().map(|&s| Value::Int(s as i32)).collect()),
+    );
+
+    let mut compound = HashMap::new();
+    compound.insert("Y".to_string(), Value::Byte(y));
+    compound.insert("block_states".to_string(), Value::Compound(block_states));
+    Value::Compound(compound)
+}
+
+fn section_from_nbt(section: &HashMap<String, Value>) -> Result<(i8, ChunkSection), RegionError> {
+    let y = match section.get("Y") {
+        Some(Value::Byte(y)) => *y,
+        Some(_) => return Err(RegionError::InvalidField("Y".to_string())),
+        None => return Err(RegionError::MissingField("Y".to_string())),
+    };
+
+    let block_states = match section.get("block_states") {
+        Some(Value::Compound(block_states)) => block_states,
+        Some(_) => return Err(RegionError::InvalidField("block_states".to_string())),
+        // A section without block states only contains air.
+        None => return Ok((y, ChunkSection::default())),
+    };
+
+    let palette = match block_states.get("palette") {
+        Some(Value::List(palette)) => palette
+            .iter()
+            .map(|entry| match entry {
+                Value::Int(state) => BlockState::try_from(*state)
+                    .map_err(|_| RegionError::InvalidField("palette".to_string())),
+                _ => Err(RegionError::InvalidField("palette".to_string())),
+            })
+            .collect::<Result<Vec<BlockState>, RegionError>>()?,
+        Some(_) => return Err(RegionError::InvalidField("palette".to_string())),
+        None => return Err(RegionError::MissingField("palette".to_string())),
+    };
+
+    let states: Vec<BlockState> = match (palette.len(), block_states.get("data")) {
+        (0, _) => return Err(RegionError::InvalidField("palette".to_string())),
+        (1, _) => vec![palette[0]; SECTION_VOLUME],
+        (_, Some(Value::LongArray(data))) => {
+            unpack(data, bits_per_block(palette.len()), SECTION_VOLUME)
+                .into_iter()
+                .map(|index| {
+                    palette
+                        .get(index as usize)
+                        .copied()
+                        .ok_or_else(|| RegionError::InvalidField("data".to_string()))
+                })
+                .collect::<Result<Vec<BlockState>, RegionError>>()?
+        }
+        (_, _) => return Err(RegionError::MissingField("data".to_string())),
+    };
+
+    let section = ChunkSection::from_states(&states)
+        .ok_or_else(|| RegionError::InvalidField("data".to_string()))?;
+    Ok((y, section))
+}
+
+fn get_int(compound: &HashMap<String, Value>, key: &str) -> Result<i32, RegionError> {
+    match compound.get(key) {
+        Some(Value::Int(value)) => Ok(*value),
+        Some(_) => Err(RegionError::InvalidField(key.to_string())),
+        None => Err(RegionError::MissingField(key.to_string())),
+    }
+}
+
+/// Number of bits needed to index a palette of `palette_len` entries.
+fn bits_per_block(palette_len: usize) -> usize {
+    let needed = (usize::BITS - (palette_len.max(1) - 1).leading_zeros()) as usize;
+    needed.max(MIN_BITS_PER_BLOCK)
+}
+
+/// Packs `values` into longs, `bits` bits per value. Values never span two longs.
+pub fn pack(values: &[u64], bits: usize) -> Vec<i64> {
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    let mut longs = vec![0u64; values.len().div_ceil(per_long)];
+
+    for (i, value) in values.iter().enumerate() {
+        longs[i / per_long] |= (value & mask) << ((i % per_long) * bits);
+    }
+
+    longs.into_iter().map(|long| long as i64).collect()
+}
+
+/// Unpacks `count` values of `bits` bits from `longs`. Missing longs read as 0.
+pub fn unpack(longs: &[i64], bits: usize, count: usize) -> Vec<u64> {
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+
+    (0..count)
+        .map(|i| {
+            let long = longs.get(i / per_long).copied().unwrap_or(0) as u64;
+            (long >> ((i % per_long) * bits)) & mask
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        let values: Vec<u64> = (0..100).map(|i| i % 16).collect();
+
+        for bits in [4, 5, 9, 15] {
+            let packed = pack(&values, bits);
+            assert_eq!(packed.len(), values.len().div_ceil(64 / bits));
+            assert_eq!(unpack(&packed, bits, values.len()), values);
+        }
+    }
+
+    #[test]
+    fn test_bits_per_block() {
+        assert_eq!(bits_per_block(2), 4);
+        assert_eq!(bits_per_block(16), 4);
+        assert_eq!(bits_per_block(17), 5);
+        assert_eq!(bits_per_block(256), 8);
+    }
+
+    #[test]
+    fn test_chunk_nbt_roundtrip() {
+        let mut chunk = Chunk::new(4, -7, -1, 2);
+        chunk.set_block(64, -16, -112, 1).unwrap();
+        chunk.set_block(79, 15, -97, 300).unwrap();
+
+        let bytes = chunk_to_nbt(&chunk).unwrap();
+        let root = read_root_compound(&bytes, &NbtLimits::default()).unwrap();
+
+        assert_eq!(root.get("xPos"), Some(&Value::Int(4)));
+        assert_eq!(root.get("zPos"), Some(&Value::Int(-7)));
+        assert_eq!(root.get("yPos"), Some(&Value::Int(-1)));
+        assert_eq!(
+            chunk_from_nbt(&bytes, &NbtLimits::default()).unwrap(),
+            chunk
+        );
+    }
+
+    #[test]
+    fn test_chunk_from_nbt_far_off_y_pos() {
+        let bytes = chunk_to_nbt(&Chunk::new(0, 0, -4, 1)).unwrap();
+        let with_y_pos = |y_pos: i32| {
+            let mut blob = Blob::from_reader(&mut bytes.as_slice()).unwrap();
+            blob.insert("yPos", y_pos).unwrap();
+            let mut bytes = Vec::new();
+            blob.to_writer(&mut bytes).unwrap();
+            chunk_from_nbt(&bytes, &NbtLimits::default())
+        };
+
+        assert!(with_y_pos(-4).is_ok());
+        // Would allocate billions of sections, or overflow.
+        for y_pos in [-2_000_000_000, i32::MIN, -4 - MAX_SECTIONS as i32] {
+            assert!(matches!(
+                with_y_pos(y_pos),
+                Err(RegionError::InvalidField(field)) if field == "Y"
+            ));
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Anvil region files: the chunks of a 32x32 region, each stored in whole 4 KiB sectors.
+//!
+//! The file starts with two tables of 1024 big-endian entries indexed by
+//! `ChunkPos::index_in_region`: the locations (3-byte offset then 1-byte size, in sectors) and
+//! the times of the last saves. A chunk starts with its length (4 bytes, counting the compression
+//! byte), its compression scheme (1 byte), then the compressed data, padded to a whole sector.
+//!
+//! A chunk growing past its sectors is moved to the end of the file, leaving its old sectors
+//! unused. `Region::compact` packs the chunks back together.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::chunk::CompressedChunk;
+use super::compression::CompressionScheme;
+use super::file::{self, SyncMode};
+use super::limits::NbtLimits;
+use super::RegionError;
+use crate::chunks_manager::pos::ChunkPos;
+use crate::chunks_manager::Chunk;
+
+pub const SECTOR_SIZE: usize = 4096;
+pub const CHUNKS_PER_REGION: usize = 1024;
+
+/// The location table then the timestamp table.
+const HEADER_SECTORS: usize = 2;
+
+/// A chunk as stored in a region file. The compression byte and the data are kept as is, so that
+/// rewriting the file doesn't change the chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionChunk {
+    /// When the chunk was last saved, in seconds since the epoch.
+    pub timestamp: u32,
+    /// See `CompressionScheme`.
+    pub compression: u8,
+    pub data: Vec<u8>,
+}
+
+impl RegionChunk {
+    /// The number of sectors the chunk takes: the length, the compression byte and the data.
+    fn sectors(&self) -> usize {
+        (5 + self.data.len()).div_ceil(SECTOR_SIZE)
+    }
+}
+
+/// The sizes of a region file before and after `Region::compact`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Indexed by `ChunkPos::index_in_region`.
+    chunks: Vec<Option<RegionChunk>>,
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self {
+            chunks: vec![None; CHUNKS_PER_REGION],
+        }
+    }
+}
+
+impl Region {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the chunks of a region file. An empty file is an empty region.
+    pub fn parse(bytes: &[u8]) -> Result<Self, RegionError> {
+        let mut region = Self::new();
+        if bytes.is_empty() {
+            return Ok(region);
+        }
+        if bytes.len() < HEADER_SECTORS * SECTOR_SIZE {
+            return Err(RegionError::MalformedRegion(format!(
+                "{} bytes, shorter than the header",
+                bytes.len()
+            )));
+        }
+
+        for index in 0..CHUNKS_PER_REGION {
+            let location = read_u32(bytes, index * 4);
+            let (offset, sectors) = ((location >> 8) as usize, (location & 0xFF) as usize);
+            if location == 0 {
+                continue;
+            }
+            let malformed = |reason: &str| {
+                RegionError::MalformedRegion(format!("chunk {index} at sector {offset}: {reason}"))
+            };
+
+            if offset < HEADER_SECTORS || (offset + sectors) * SECTOR_SIZE > bytes.len() {
+                return Err(malformed("outside the file"));
+            }
+            let start = offset * SECTOR_SIZE;
+            let length = read_u32(bytes, start) as usize;
+            if length == 0 || 4 + length > sectors * SECTOR_SIZE {
+                return Err(malformed("invalid length"));
+            }
+
+            region.chunks[index] = Some(RegionChunk {
+                timestamp: read_u32(bytes, SECTOR_SIZE + index * 4),
+                compression: bytes[start + 4],
+                data: bytes[start + 5..start + 4 + length].to_vec(),
+            });
+        }
+        Ok(region)
+    }
+
+    /// The chunk at `index`, see `ChunkPos::index_in_region`.
+    pub fn get(&self, index: usize) -> Option<&RegionChunk> {
+        self.chunks.get(index)?.as_ref()
+    }
+
+    /// Replaces the chunk at `index`, or removes it if `None`.
+    pub fn set(&mut self, index: usize, chunk: Option<RegionChunk>) {
+        self.chunks[index] = chunk;
+    }
+
+    /// Decompresses and deserializes the chunk at `pos`, or `None` if it was never saved.
+    pub fn read_chunk(
+        &self,
+        pos: ChunkPos,
+        limits: &NbtLimits,
+    ) -> Result<Option<Chunk>, RegionError> {
+        let Some(stored) = self.get(pos.index_in_region()) else {
+            return Ok(None);
+        };
+        let scheme = CompressionScheme::try_from(stored.compression)?;
+        let compressed = CompressedChunk::new(pos.x, pos.z, scheme, stored.data.clone());
+        compressed.to_chunk(limits).map(Some)
+    }
+
+    /// Serializes and compresses `chunk`, replacing the one saved at its position. `timestamp` is
+    /// the time of the save, in seconds since the epoch.
+    pub fn write_chunk(
+        &mut self,
+        chunk: &Chunk,
+        scheme: CompressionScheme,
+        timestamp: u32,
+    ) -> Result<(), RegionError> {
+        let compressed = CompressedChunk::from_chunk(chunk, scheme)?;
+        let pos = ChunkPos::new(chunk.get_x(), chunk.get_z());
+        self.set(
+            pos.index_in_region(),
+            Some(RegionChunk {
+                timestamp,
+                compression: scheme as u8,
+                data: compressed.get_data().to_vec(),
+            }),
+        );
+        Ok(())
+    }
+
+    /// Reads the region file at `path`. A missing file is an empty region.
+    pub fn load(path: &Path) -> Result<Self, RegionError> {
+        match fs::read(path) {
+            Ok(bytes) => Self::parse(&bytes),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the region to `path`, returning the size of the file.
+    pub fn save(&self, path: &Path, mode: SyncMode) -> Result<u64, RegionError> {
+        let bytes = self.to_bytes();
+        // Written next to the file first, so that a crash can't lose the region.
+        let temporary = path.with_extension("mca.tmp");
+        file::save_to_file(&temporary, &bytes, mode)?;
+        fs::rename(&temporary, path)?;
+        Ok(bytes.len() as u64)
+    }
+
+    /// The region file, with the chunks packed in index order right after the header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let Some(chunk) = chunk else {
+                continue;
+            };
+            let offset = bytes.len() / SECTOR_SIZE;
+            let location = (offset as u32) << 8 | chunk.sectors() as u32;
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            let timestamp = SECTOR_SIZE + index * 4;
+            bytes[timestamp..timestamp + 4].copy_from_slice(&chunk.timestamp.to_be_bytes());
+
+            bytes.extend((1 + chunk.data.len() as u32).to_be_bytes());
+            bytes.push(chunk.compression);
+            bytes.extend(&chunk.data);
+            bytes.resize((offset + chunk.sectors()) * SECTOR_SIZE, 0);
+        }
+        bytes
+    }
+
+    /// Rewrites the region file at `path` with its chunks packed tightly, dropping the unused
+    /// sectors. The chunks are unchanged. A region without chunks is left as is, as its header
+    /// alone may be bigger than the file.
+    pub fn compact(path: &Path, mode: SyncMode) -> Result<Compaction, RegionError> {
+        let bytes = fs::read(path)?;
+        let region = Self::parse(&bytes)?;
+        if region.chunks.iter().all(Option::is_none) {
+            return Ok(Compaction {
+                size_before: bytes.len() as u64,
+                size_after: bytes.len() as u64,
+            });
+        }
+        Ok(Compaction {
+            size_before: bytes.len() as u64,
+            size_after: region.save(path, mode)?,
+        })
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::generate_world;
+    use tempfile::TempDir;
+
+    fn chunk(timestamp: u32, length: usize) -> RegionChunk {
+        RegionChunk {
+            timestamp,
+            compression: 2,
+            data: (0..length).map(|i| i as u8).collect(),
+        }
+    }
+
+    /// A region whose chunks are scattered, with unused sectors between them.
+    fn fragmented() -> (Vec<u8>, Vec<(usize, RegionChunk)>) {
+        let chunks = vec![
+            (0, chunk(100, 10)),
+            (33, chunk(200, 5000)),
+            (1023, chunk(300, 4091)),
+        ];
+        let mut bytes = vec![0; 20 * SECTOR_SIZE];
+        for ((index, chunk), offset) in chunks.iter().zip([4, 9, 15]) {
+            let location = (offset as u32) << 8 | chunk.sectors() as u32;
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            let timestamp = SECTOR_SIZE + index * 4;
+            bytes[timestamp..timestamp + 4].copy_from_slice(&chunk.timestamp.to_be_bytes());
+
+            let start = offset * SECTOR_SIZE;
+            let length = 1 + chunk.data.len() as u32;
+            bytes[start..start + 4].copy_from_slice(&length.to_be_bytes());
+            bytes[start + 4] = chunk.compression;
+            bytes[start + 5..start + 5 + chunk.data.len()].copy_from_slice(&chunk.data);
+        }
+        (bytes, chunks)
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let (bytes, chunks) = fragmented();
+        let region = Region::parse(&bytes).unwrap();
+        for (index, chunk) in &chunks {
+            assert_eq!(region.get(*index), Some(chunk));
+        }
+        assert_eq!(region.get(1), None);
+        assert_eq!(Region::parse(&region.to_bytes()).unwrap(), region);
+
+        assert_eq!(Region::parse(&[]).unwrap(), Region::new());
+        assert!(Region::parse(&bytes[..SECTOR_SIZE]).is_err());
+        // The last chunk would end after the file.
+        assert!(Region::parse(&bytes[..15 * SECTOR_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_compact() -> Result<(), RegionError> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("r.0.0.mca");
+        let (bytes, chunks) = fragmented();
+        fs::write(&path, &bytes)?;
+
+        let compaction = Region::compact(&path, SyncMode::NoSync)?;
+        // The header, then 1 + 2 + 1 sectors of chunks.
+        assert_eq!(
+            compaction,
+            Compaction {
+                size_before: 20 * SECTOR_SIZE as u64,
+                size_after: 6 * SECTOR_SIZE as u64,
+            }
+        );
+
+        let compacted = fs::read(&path)?;
+        assert_eq!(compacted.len(), 6 * SECTOR_SIZE);
+        let region = Region::parse(&compacted)?;
+        for (index, chunk) in &chunks {
+            assert_eq!(region.get(*index), Some(chunk));
+        }
+        assert_eq!(read_u32(&compacted, 33 * 4), 3 << 8 | 2);
+
+        // Compacting again changes nothing.
+        let compaction = Region::compact(&path, SyncMode::NoSync)?;
+        assert_eq!(compaction.size_before, compaction.size_after);
+        assert_eq!(fs::read(&path)?, compacted);
+
+        // An empty file doesn't get a header.
+        fs::write(&path, [])?;
+        let compaction = Region::compact(&path, SyncMode::NoSync)?;
+        assert_eq!(compaction.size_after, 0);
+        assert!(fs::read(&path)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_generated_chunk() -> Result<(), RegionError> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("r.0.-1.mca");
+        let chunk = generate_world(3, -2);
+        let pos = ChunkPos::new(3, -2);
+
+        let mut region = Region::load(&path)?;
+        assert_eq!(region, Region::new());
+        region.write_chunk(&chunk, CompressionScheme::Zlib, 100)?;
+        region.save(&path, SyncMode::NoSync)?;
+
+        let region = Region::load(&path)?;
+        assert_eq!(region.get(pos.index_in_region()).unwrap().timestamp, 100);
+        let limits = NbtLimits::default();
+        assert_eq!(region.read_chunk(pos, &limits)?, Some(chunk));
+        assert_eq!(region.read_chunk(ChunkPos::new(4, -2), &limits)?, None);
+        Ok(())
+    }
+}
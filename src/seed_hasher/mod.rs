@@ -1,6 +1,7 @@
 use sha2::{Sha256, Digest};
 
-fn generate_seed(input: String) -> i64 {
+/// The seed of a world from the `level-seed` property: the number itself, or a hash of the text.
+pub fn world_seed(input: &str) -> i64 {
     // Try to parse the input string as an i64 directly
     if let Ok(parsed) = input.parse::<i64>() {
         return parsed;
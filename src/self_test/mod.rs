@@ -0,0 +1,177 @@
+//! The `--self-test` checks: round-trips through the codecs the server relies on, to tell
+//! quickly whether a build is sane.
+use std::fmt;
+
+use colored::Colorize;
+
+use crate::net::packet::data_types::{string, varint, varlong};
+use crate::net::packet::packet_types::{DecodablePacket, EncodablePacket, LoginStart};
+use crate::net::transport::ConnectionIo;
+use crate::player::uuid::offline_uuid;
+use crate::region_parser::compression::{self, CompressionScheme};
+
+/// A check, returning what went wrong if it fails.
+type CheckFn = fn() -> Result<(), String>;
+
+/// The outcome of a check, `Err` with what went wrong.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(()) => write!(f, "{} {}", "[PASS]".green().bold(), self.name),
+            Err(e) => write!(f, "{} {}: {e}", "[FAIL]".red().bold(), self.name),
+        }
+    }
+}
+
+/// Runs every check, in order.
+pub fn run() -> Vec<Check> {
+    let checks: [(&'static str, CheckFn); 6] = [
+        ("VarInt round-trip", check_varint),
+        ("VarLong round-trip", check_varlong),
+        ("String round-trip", check_string),
+        ("UUID round-trip", check_uuid),
+        ("Packet build/parse cycle", check_packet),
+        ("Region compress/decompress cycle", check_region_compression),
+    ];
+    checks
+        .into_iter()
+        .map(|(name, check)| Check {
+            name,
+            result: check(),
+        })
+        .collect()
+}
+
+/// Runs the checks, prints their outcome, and returns whether they all passed.
+pub fn run_and_report() -> bool {
+    let checks = run();
+    for check in &checks {
+        println!("{check}");
+    }
+    let failed = checks.iter().filter(|check| check.result.is_err()).count();
+    if failed == 0 {
+        println!("All {} checks passed", checks.len());
+    } else {
+        println!("{failed} of {} checks failed", checks.len());
+    }
+    failed == 0
+}
+
+/// Fails with `what` if `actual` isn't `expected`.
+fn expect_eq<T: PartialEq + fmt::Debug>(what: &str, actual: T, expected: T) -> Result<(), String> {
+    if actual == expected {
+        return Ok(());
+    }
+    Err(format!("{what}: expected {expected:?}, got {actual:?}"))
+}
+
+fn check_varint() -> Result<(), String> {
+    for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1, i32::MIN] {
+        let bytes = varint::write(value);
+        let read = varint::read(&bytes).map_err(|e| e.to_string())?;
+        expect_eq(&format!("VarInt {value}"), read, (value, bytes.len()))?;
+    }
+    expect_eq("VarInt -1 size", varint::write(-1).len(), 5)
+}
+
+fn check_varlong() -> Result<(), String> {
+    for value in [0, 1, 127, 128, 2147483647, i64::MAX, -1, i64::MIN] {
+        let bytes = varlong::write(value);
+        let read = varlong::read(&bytes).map_err(|e| e.to_string())?;
+        expect_eq(&format!("VarLong {value}"), read, (value, bytes.len()))?;
+    }
+    expect_eq("VarLong -1 size", varlong::write(-1).len(), 10)
+}
+
+fn check_string() -> Result<(), String> {
+    for value in ["", "Hello, world!", "Cactus 🌵 é", &"a".repeat(300)] {
+        let bytes = string::write(value).map_err(|e| e.to_string())?;
+        let (read, size) = string::read(&bytes).map_err(|e| e.to_string())?;
+        expect_eq("String", (read.as_str(), size), (value, bytes.len()))?;
+    }
+    Ok(())
+}
+
+/// The UUIDs are sent as 16 big-endian bytes, and shown as 32 hexadecimal digits.
+fn check_uuid() -> Result<(), String> {
+    let text = offline_uuid("Steve");
+    expect_eq(
+        "Offline UUID",
+        text.as_str(),
+        "5627dd98e6be3c21b8a8e92344183641",
+    )?;
+
+    let uuid = u128::from_str_radix(&text, 16).map_err(|e| e.to_string())?;
+    let read = u128::from_be_bytes(uuid.to_be_bytes());
+    expect_eq("UUID", format!("{read:032x}"), text)
+}
+
+/// Builds a Login Start, sends it through a compressed connection and parses it back.
+fn check_packet() -> Result<(), String> {
+    let login_start = LoginStart {
+        name: "Steve".repeat(3),
+        uuid: 0x5627dd98e6be3c21b8a8e92344183641,
+    };
+    let packet = login_start.build().map_err(|e| e.to_string())?;
+
+    for threshold in [None, Some(0), Some(256)] {
+        let mut io = ConnectionIo::new();
+        if let Some(threshold) = threshold {
+            io.enable_compression(threshold);
+        }
+        let wire = io.encode(&packet).map_err(|e| e.to_string())?;
+        let received = io.decode(&wire).map_err(|e| e.to_string())?;
+        expect_eq("Packets received", received.len(), 1)?;
+
+        let parsed = LoginStart::parse(&received[0]).map_err(|e| e.to_string())?;
+        expect_eq("Login Start", &parsed, &login_start)?;
+    }
+    Ok(())
+}
+
+fn check_region_compression() -> Result<(), String> {
+    let chunk: Vec<u8> = (0..4096u32).map(|i| (i * 7 % 251) as u8).collect();
+    for scheme in [
+        CompressionScheme::Gzip,
+        CompressionScheme::Zlib,
+        CompressionScheme::Uncompressed,
+    ] {
+        let compressed = compression::compress(&chunk, scheme).map_err(|e| e.to_string())?;
+        let decompressed =
+            compression::decompress(&compressed, scheme).map_err(|e| e.to_string())?;
+        if decompressed != chunk {
+            return Err(format!("{scheme:?} changed the data"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        for check in run() {
+            assert!(check.result.is_ok(), "{check}");
+        }
+        assert!(run_and_report());
+    }
+
+    #[test]
+    fn test_failed_check() {
+        let check = Check {
+            name: "Broken",
+            result: expect_eq("Value", 1, 2),
+        };
+        assert!(check
+            .to_string()
+            .contains("Broken: Value: expected 2, got 1"));
+    }
+}
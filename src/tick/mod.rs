@@ -0,0 +1,282 @@
+//! The tick loop, running the game logic 20 times per second.
+//!
+//! When nobody is connected for a while, there is no reason to burn CPU ticking the world at
+//! full rate: the scheduler then ticks at `IDLE_TICK_INTERVAL` until a player joins.
+//!
+//! The loop runs on its own thread, away from the async runtime: a slow tick never delays the
+//! network tasks (keep alives, chunk sending...), and the network never delays a tick. Late ticks
+//! are caught up by ticking back to back, unless the loop is too far behind, then they're skipped.
+pub mod watchdog;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::config::Settings;
+use crate::metrics;
+use watchdog::Watchdog;
+
+/// Number of ticks per second at full rate.
+pub const TICKS_PER_SECOND: u32 = 20;
+/// Interval between two ticks at full rate.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+/// Interval between two ticks when the server is idle.
+pub const IDLE_TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// Default of the non-standard `max-tick-catch-up-ms` property, vanilla's.
+pub const DEFAULT_MAX_CATCH_UP: Duration = Duration::from_secs(2);
+/// Minimal time between two "Can't keep up!" warnings.
+const OVERLOAD_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct TickScheduler {
+    /// Number of connected players.
+    players: Arc<AtomicUsize>,
+    /// How long the server must be empty before idling. `None` never idles.
+    idle_after: Option<Duration>,
+    /// When the last player left (or when the scheduler started without players).
+    empty_since: Option<Instant>,
+    idle: bool,
+    /// Watches over the ticks taking too long, see `max-tick-time`.
+    watchdog: Option<Watchdog>,
+    /// How late the loop can be and still catch up. Beyond, the late ticks are skipped.
+    max_catch_up: Duration,
+    last_overload_warning: Option<Instant>,
+}
+
+impl TickScheduler {
+    pub fn new(players: Arc<AtomicUsize>, idle_after: Option<Duration>) -> Self {
+        Self {
+            players,
+            idle_after,
+            empty_since: None,
+            idle: false,
+            watchdog: None,
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+            last_overload_warning: None,
+        }
+    }
+
+    /// Catches up the ticks as long as the loop is at most `max_catch_up` late. 0 never catches
+    /// up, skipping the late ticks right away.
+    pub fn with_max_catch_up(mut self, max_catch_up: Duration) -> Self {
+        self.max_catch_up = max_catch_up;
+        self
+    }
+
+    /// Watches the ticks with `watchdog`, started along with the loop.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Uses the `pause-when-empty-seconds` and `max-tick-time` properties, and the non-standard
+    /// `max-tick-catch-up-ms`.
+    pub fn from_settings(settings: &Settings, players: Arc<AtomicUsize>) -> Self {
+        let idle_after = u64::try_from(settings.pause_when_empty_seconds)
+            .ok()
+            .filter(|&seconds| seconds > 0)
+            .map(Duration::from_secs);
+
+        let max_catch_up = settings
+            .extra("max-tick-catch-up-ms")
+            .and_then(|millis| millis.parse::<u64>().ok())
+            .map_or(DEFAULT_MAX_CATCH_UP, Duration::from_millis);
+
+        let scheduler = Self::new(players, idle_after).with_max_catch_up(max_catch_up);
+        match Watchdog::from_settings(settings) {
+            Some(watchdog) => scheduler.with_watchdog(watchdog),
+            None => scheduler,
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Updates the idle state at `now` and returns the interval to wait before the next tick.
+    pub fn next_interval(&mut self, now: Instant) -> Duration {
+        if self.players.load(Ordering::Relaxed) > 0 {
+            if self.idle {
+                info!("A player joined, resuming ticking at full rate");
+            }
+            self.empty_since = None;
+            self.idle = false;
+            return TICK_INTERVAL;
+        }
+
+        let empty_since = *self.empty_since.get_or_insert(now);
+        if let Some(idle_after) = self.idle_after {
+            if !self.idle && now.duration_since(empty_since) >= idle_after {
+                info!(
+                    "Server empty for {} seconds, slowing down ticking",
+                    idle_after.as_secs()
+                );
+                self.idle = true;
+            }
+        }
+
+        if self.idle {
+            IDLE_TICK_INTERVAL
+        } else {
+            TICK_INTERVAL
+        }
+    }
+
+    /// When to tick next, the previous tick having been planned at `planned` and `now` being
+    /// after it. Past `now` means the loop is late and ticks right away to catch up.
+    pub fn next_tick(&mut self, planned: Instant, now: Instant) -> Instant {
+        let next = planned + self.next_interval(now);
+        let behind = now.saturating_duration_since(next);
+        if behind <= self.max_catch_up {
+            return next;
+        }
+
+        let warned_recently = self
+            .last_overload_warning
+            .is_some_and(|warned| now.duration_since(warned) < OVERLOAD_WARNING_INTERVAL);
+        if !warned_recently {
+            warn!(
+                "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+                behind.as_millis(),
+                behind.as_millis() / TICK_INTERVAL.as_millis()
+            );
+            self.last_overload_warning = Some(now);
+        }
+        now
+    }
+
+    /// Calls `tick` on a dedicated thread, at the rate decided by `next_tick`, until stopped with
+    /// the returned handle.
+    pub fn spawn(self, tick: impl FnMut() + Send + 'static) -> TickHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::Builder::new()
+            .name("Server thread".to_string())
+            .spawn(move || self.run(tick, &stopped))
+            .expect("Failed to spawn the tick thread");
+        TickHandle { stop, thread }
+    }
+
+    fn run(mut self, mut tick: impl FnMut(), stop: &AtomicBool) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.clone().spawn();
+        }
+        let mut planned = Instant::now();
+        let mut previous: Option<Instant> = None;
+        while !stop.load(Ordering::Relaxed) {
+            let started = Instant::now();
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.tick_started(started);
+            }
+            tick();
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.tick_finished();
+            }
+
+            let interval = previous.map_or(TICK_INTERVAL, |previous| started - previous);
+            metrics::global().tick_recorded(started.elapsed(), interval);
+            previous = Some(started);
+
+            planned = self.next_tick(planned, Instant::now());
+            thread::sleep(planned.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
+/// The tick loop started by `TickScheduler::spawn`. Dropping it leaves the loop running.
+pub struct TickHandle {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl TickHandle {
+    /// Stops the loop after the current tick and waits for it. `Err` if a tick panicked.
+    pub fn stop(self) -> thread::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_without_players() {
+        let players = Arc::new(AtomicUsize::new(0));
+        let mut scheduler = TickScheduler::new(players.clone(), Some(Duration::from_secs(60)));
+        let start = Instant::now();
+
+        // Not idle before the delay
+        assert_eq!(scheduler.next_interval(start), TICK_INTERVAL);
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(59)),
+            TICK_INTERVAL
+        );
+
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(60)),
+            IDLE_TICK_INTERVAL
+        );
+        assert!(scheduler.is_idle());
+
+        // A player joins
+        players.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(61)),
+            TICK_INTERVAL
+        );
+        assert!(!scheduler.is_idle());
+
+        // The delay starts over when the player leaves
+        players.fetch_sub(1, Ordering::Relaxed);
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(62)),
+            TICK_INTERVAL
+        );
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(122)),
+            IDLE_TICK_INTERVAL
+        );
+    }
+
+    #[test]
+    fn test_idle_disabled() {
+        let mut scheduler = TickScheduler::new(Arc::new(AtomicUsize::new(0)), None);
+        let start = Instant::now();
+
+        scheduler.next_interval(start);
+        assert_eq!(
+            scheduler.next_interval(start + Duration::from_secs(3600)),
+            TICK_INTERVAL
+        );
+        assert!(!scheduler.is_idle());
+    }
+
+    #[test]
+    fn test_catch_up_then_skip() {
+        let players = Arc::new(AtomicUsize::new(1));
+        let mut scheduler =
+            TickScheduler::new(players, None).with_max_catch_up(Duration::from_millis(500));
+        let start = Instant::now();
+
+        // On time
+        let next = scheduler.next_tick(start, start + Duration::from_millis(10));
+        assert_eq!(next, start + TICK_INTERVAL);
+
+        // A 300ms tick: the next ones are run back to back to catch up.
+        let late = start + Duration::from_millis(300);
+        assert_eq!(scheduler.next_tick(start, late), start + TICK_INTERVAL);
+
+        // Too late: the late ticks are skipped.
+        let very_late = start + Duration::from_secs(3);
+        assert_eq!(scheduler.next_tick(start, very_late), very_late);
+        assert!(scheduler.last_overload_warning.is_some());
+
+        // Never catching up.
+        let mut scheduler = scheduler.with_max_catch_up(Duration::ZERO);
+        assert_eq!(scheduler.next_tick(start, late), late);
+    }
+}
@@ -0,0 +1,186 @@
+//! Watches over the tick loop: a tick taking longer than `max-tick-time` milliseconds means the
+//! server is stuck. Like vanilla, the server is then stopped, unless the non-standard
+//! `max-tick-time-action` property is `warn`, which only logs the stall.
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{error, warn};
+
+use crate::config::Settings;
+
+/// What to do when a tick exceeds the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Logs the stall, once per stalled tick.
+    Warn,
+    /// Stops the server, the vanilla behavior.
+    Abort,
+}
+
+/// What the watchdog reports about a stalled tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stall {
+    /// How many ticks completed before this one.
+    pub tick: u64,
+    /// How long the tick has been running.
+    pub duration: Duration,
+    /// The `max-tick-time` it exceeded.
+    pub max_tick_time: Duration,
+}
+
+/// The state of the tick loop, shared with the watchdog thread.
+#[derive(Debug, Default)]
+struct TickState {
+    /// When the running tick started, `None` between two ticks.
+    started: Option<Instant>,
+    completed: u64,
+    /// Whether the running tick was already reported.
+    reported: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    max_tick_time: Duration,
+    action: WatchdogAction,
+    state: Arc<Mutex<TickState>>,
+}
+
+impl Watchdog {
+    pub fn new(max_tick_time: Duration, action: WatchdogAction) -> Self {
+        Self {
+            max_tick_time,
+            action,
+            state: Arc::default(),
+        }
+    }
+
+    /// Uses the `max-tick-time` property, disabled if `<= 0`, and the non-standard
+    /// `max-tick-time-action` (`abort` or `warn`).
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let millis = u64::try_from(settings.max_tick_time)
+            .ok()
+            .filter(|&millis| millis > 0)?;
+        let action = match settings.extra("max-tick-time-action") {
+            Some("warn") => WatchdogAction::Warn,
+            _ => WatchdogAction::Abort,
+        };
+
+        Some(Self::new(Duration::from_millis(millis), action))
+    }
+
+    /// Called by the tick loop before each tick.
+    pub fn tick_started(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.started = Some(now);
+        state.reported = false;
+    }
+
+    /// Called by the tick loop after each tick.
+    pub fn tick_finished(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.started = None;
+        state.completed += 1;
+    }
+
+    /// The stall of the running tick at `now`, if it exceeds the limit and wasn't reported yet.
+    pub fn check(&self, now: Instant) -> Option<Stall> {
+        let mut state = self.state.lock().unwrap();
+        let duration = now.saturating_duration_since(state.started?);
+        if state.reported || duration <= self.max_tick_time {
+            return None;
+        }
+        state.reported = true;
+        Some(Stall {
+            tick: state.completed,
+            duration,
+            max_tick_time: self.max_tick_time,
+        })
+    }
+
+    /// Starts the watchdog thread, logging the stalls and stopping the server if the action is
+    /// `Abort`.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        self.spawn_with(|action, stall| {
+            let message = format!(
+                "A single server tick took {:.2} seconds (should be max {:.2}), tick {} is stuck",
+                stall.duration.as_secs_f64(),
+                stall.max_tick_time.as_secs_f64(),
+                stall.tick,
+            );
+            match action {
+                WatchdogAction::Warn => warn!("{message}"),
+                WatchdogAction::Abort => {
+                    error!("{message}");
+                    error!("Considering it to be crashed, server will forcibly shutdown.");
+                    crate::gracefully_exit(1);
+                }
+            }
+        })
+    }
+
+    /// Starts the watchdog thread, calling `on_stall` for each stalled tick.
+    fn spawn_with(
+        self,
+        on_stall: impl Fn(WatchdogAction, Stall) + Send + 'static,
+    ) -> thread::JoinHandle<()> {
+        // Often enough to catch a stall soon after the limit, without busy looping.
+        let interval = (self.max_tick_time / 4).max(Duration::from_millis(1));
+        thread::Builder::new()
+            .name("Server Watchdog".to_string())
+            .spawn(move || loop {
+                thread::sleep(interval);
+                if let Some(stall) = self.check(Instant::now()) {
+                    on_stall(self.action, stall);
+                }
+            })
+            .expect("Failed to spawn the watchdog thread")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_check() {
+        let watchdog = Watchdog::new(Duration::from_millis(100), WatchdogAction::Warn);
+        let start = Instant::now();
+        assert_eq!(watchdog.check(start + Duration::from_secs(1)), None);
+
+        watchdog.tick_started(start);
+        assert_eq!(watchdog.check(start + Duration::from_millis(100)), None);
+        let stall = watchdog.check(start + Duration::from_millis(150)).unwrap();
+        assert_eq!(stall.tick, 0);
+        assert_eq!(stall.duration, Duration::from_millis(150));
+        // Reported once per tick.
+        assert_eq!(watchdog.check(start + Duration::from_millis(200)), None);
+
+        watchdog.tick_finished();
+        assert_eq!(watchdog.check(start + Duration::from_secs(1)), None);
+        watchdog.tick_started(start);
+        assert_eq!(
+            watchdog
+                .check(start + Duration::from_secs(1))
+                .map(|stall| stall.tick),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_stalled_tick_triggers_action() {
+        let watchdog = Watchdog::new(Duration::from_millis(20), WatchdogAction::Abort);
+        let (sender, receiver) = mpsc::channel();
+        watchdog.clone().spawn_with(move |action, stall| {
+            let _ = sender.send((action, stall));
+        });
+
+        // A tick stuck for much longer than the limit.
+        watchdog.tick_started(Instant::now());
+        let (action, stall) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(action, WatchdogAction::Abort);
+        assert!(stall.duration > Duration::from_millis(20));
+        watchdog.tick_finished();
+    }
+}
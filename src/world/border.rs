@@ -0,0 +1,121 @@
+//! The world border, derived from the `max-world-size` setting.
+use crate::chunks_manager::SECTION_WIDTH;
+use crate::config::Settings;
+
+/// Warning distance (in blocks) sent to the client, the vanilla default.
+pub const DEFAULT_WARNING_BLOCKS: i32 = 5;
+
+/// Warning time (in seconds) sent to the client, the vanilla default.
+pub const DEFAULT_WARNING_TIME: i32 = 15;
+
+/// A square border centered on (`center_x`, `center_z`) that players and generation can't cross.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldBorder {
+    center_x: f64,
+    center_z: f64,
+    diameter: f64,
+    /// `max-world-size`, also used as the portal teleport boundary.
+    max_world_size: u32,
+}
+
+impl WorldBorder {
+    /// Creates a border centered on (0, 0). `max_world_size` is a radius, like the
+    /// `max-world-size` setting, so the diameter is twice as big.
+    pub fn from_max_world_size(max_world_size: u32) -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            diameter: max_world_size as f64 * 2.0,
+            max_world_size,
+        }
+    }
+
+    /// Creates the border from the server settings.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::from_max_world_size(settings.max_world_size)
+    }
+
+    /// Moves the center of the border.
+    pub fn with_center(mut self, x: f64, z: f64) -> Self {
+        self.center_x = x;
+        self.center_z = z;
+        self
+    }
+
+    pub fn get_center(&self) -> (f64, f64) {
+        (self.center_x, self.center_z)
+    }
+
+    pub fn get_diameter(&self) -> f64 {
+        self.diameter
+    }
+
+    pub fn get_max_world_size(&self) -> u32 {
+        self.max_world_size
+    }
+
+    /// Returns (min x, min z, max x, max z) of the border.
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let radius = self.diameter / 2.0;
+        (
+            self.center_x - radius,
+            self.center_z - radius,
+            self.center_x + radius,
+            self.center_z + radius,
+        )
+    }
+
+    /// Whether the block at `x`, `z` is entirely inside the border.
+    pub fn is_inside(&self, x: i32, z: i32) -> bool {
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        let (x, z) = (x as f64, z as f64);
+
+        x >= min_x && x + 1.0 <= max_x && z >= min_z && z + 1.0 <= max_z
+    }
+
+    /// Whether at least one block of the chunk at chunk coordinates `x`, `z` is inside the
+    /// border. Chunks entirely outside of it must not be generated.
+    pub fn is_chunk_inside(&self, x: i32, z: i32) -> bool {
+        let width = SECTION_WIDTH as i64;
+        let (min_x, min_z, max_x, max_z) = self.bounds();
+        let (x, z) = ((x as i64 * width) as f64, (z as i64 * width) as f64);
+
+        x + width as f64 > min_x && x < max_x && z + width as f64 > min_z && z < max_z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_border_edge() {
+        let border = WorldBorder::from_max_world_size(100);
+
+        assert_eq!(border.get_diameter(), 200.0);
+        assert!(border.is_inside(0, 0));
+        assert!(border.is_inside(99, -100));
+        assert!(!border.is_inside(100, 0));
+        assert!(!border.is_inside(0, -101));
+    }
+
+    #[test]
+    fn test_border_with_center() {
+        let border = WorldBorder::from_max_world_size(10).with_center(1000.0, -1000.0);
+
+        assert!(!border.is_inside(0, 0));
+        assert!(border.is_inside(1009, -1010));
+        assert!(!border.is_inside(1010, -1000));
+    }
+
+    #[test]
+    fn test_border_chunks() {
+        let border = WorldBorder::from_max_world_size(100);
+
+        // Chunk 6 spans x=96..112, partially inside.
+        assert!(border.is_chunk_inside(6, 0));
+        assert!(border.is_chunk_inside(-7, -7));
+        assert!(!border.is_chunk_inside(7, 0));
+        assert!(!border.is_chunk_inside(0, -8));
+    }
+}
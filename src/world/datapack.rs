@@ -0,0 +1,215 @@
+//! The data packs of the world, from the `initial-enabled-packs` and `initial-disabled-packs`
+//! properties. Each enabled built-in pack turns a feature flag on.
+//!
+//! Besides the built-in packs, each folder or zip file of the world's `datapacks` folder is a
+//! pack named `file/<name>`.
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+/// The built-in packs of the version, each with the feature flag it enables.
+pub const BUILT_IN_PACKS: [(&str, &str); 4] = [
+    ("vanilla", "minecraft:vanilla"),
+    ("trade_rebalance", "minecraft:trade_rebalance"),
+    ("redstone_experiments", "minecraft:redstone_experiments"),
+    ("minecart_improvements", "minecraft:minecart_improvements"),
+];
+
+static DATA_PACKS: Lazy<Arc<RwLock<DataPacks>>> =
+    Lazy::new(|| Arc::new(RwLock::new(DataPacks::from_settings(&Settings::new()))));
+
+/// Returns the data packs of the world.
+pub fn global() -> Arc<RwLock<DataPacks>> {
+    DATA_PACKS.clone()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataPacks {
+    /// In the order they are applied, the last one overriding the others.
+    enabled: Vec<String>,
+    disabled: Vec<String>,
+}
+
+impl Default for DataPacks {
+    fn default() -> Self {
+        Self {
+            enabled: vec!["vanilla".to_string()],
+            disabled: Vec::new(),
+        }
+    }
+}
+
+impl DataPacks {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let disabled = parse_list(settings.initial_disabled_packs.as_deref().unwrap_or(""));
+        let enabled = parse_list(&settings.initial_enabled_packs)
+            .into_iter()
+            .filter(|pack| !disabled.contains(pack))
+            .collect();
+        Self { enabled, disabled }
+    }
+
+    pub fn enabled(&self) -> &[String] {
+        &self.enabled
+    }
+
+    pub fn disabled(&self) -> &[String] {
+        &self.disabled
+    }
+
+    pub fn is_enabled(&self, pack: &str) -> bool {
+        self.enabled.iter().any(|enabled| enabled == pack)
+    }
+
+    /// Enables `pack`, last in the order. Returns `false` if it was already enabled.
+    pub fn enable(&mut self, pack: &str) -> bool {
+        if self.is_enabled(pack) {
+            return false;
+        }
+        self.disabled.retain(|disabled| disabled != pack);
+        self.enabled.push(pack.to_string());
+        true
+    }
+
+    /// Disables `pack`. Returns `false` if it wasn't enabled.
+    pub fn disable(&mut self, pack: &str) -> bool {
+        if !self.is_enabled(pack) {
+            return false;
+        }
+        self.enabled.retain(|enabled| enabled != pack);
+        self.disabled.push(pack.to_string());
+        true
+    }
+
+    /// The feature flags sent to the clients, those of the enabled built-in packs.
+    pub fn feature_flags(&self) -> Vec<&'static str> {
+        BUILT_IN_PACKS
+            .iter()
+            .filter(|(pack, _)| self.is_enabled(pack))
+            .map(|(_, flag)| *flag)
+            .collect()
+    }
+}
+
+/// The packs that can be enabled: the built-in ones, then those of `dir`, sorted by name. Only the
+/// built-in ones if `dir` can't be read.
+pub fn available(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let path = entry.path();
+            path.is_dir() || path.extension().is_some_and(|extension| extension == "zip")
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|name| format!("file/{name}"))
+        .collect();
+    files.sort();
+
+    BUILT_IN_PACKS
+        .iter()
+        .map(|(pack, _)| pack.to_string())
+        .chain(files)
+        .collect()
+}
+
+/// Splits a comma separated list of packs, ignoring the blanks.
+fn parse_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pack| !pack.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A warning if the pack lists of `settings` contradict each other.
+pub fn validate(settings: &Settings) -> Option<String> {
+    let disabled = parse_list(settings.initial_disabled_packs.as_deref().unwrap_or(""));
+    let both: Vec<String> = parse_list(&settings.initial_enabled_packs)
+        .into_iter()
+        .filter(|pack| disabled.contains(pack))
+        .collect();
+    if both.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{} in both initial-enabled-packs and initial-disabled-packs, they are disabled",
+        both.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(enabled: &str, disabled: &str) -> Settings {
+        Settings::with(&[
+            ("initial-enabled-packs", enabled),
+            ("initial-disabled-packs", disabled),
+        ])
+    }
+
+    #[test]
+    fn test_default_enables_vanilla() {
+        let settings = Settings::with(&[]);
+
+        let packs = DataPacks::from_settings(&settings);
+        assert_eq!(packs, DataPacks::default());
+        assert_eq!(packs.feature_flags(), vec!["minecraft:vanilla"]);
+        assert_eq!(validate(&settings), None);
+    }
+
+    #[test]
+    fn test_enabled_and_disabled() {
+        let settings = settings("vanilla, trade_rebalance,file/custom", "file/custom");
+
+        let packs = DataPacks::from_settings(&settings);
+        assert_eq!(packs.enabled(), ["vanilla", "trade_rebalance"]);
+        assert_eq!(
+            packs.feature_flags(),
+            vec!["minecraft:vanilla", "minecraft:trade_rebalance"]
+        );
+        assert_eq!(
+            validate(&settings).unwrap(),
+            "file/custom in both initial-enabled-packs and initial-disabled-packs, \
+             they are disabled"
+        );
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        let mut packs = DataPacks::default();
+        assert!(packs.enable("bundle"));
+        assert!(!packs.enable("bundle"));
+        assert!(packs.disable("vanilla"));
+        assert!(!packs.disable("vanilla"));
+        assert_eq!(packs.enabled(), ["bundle"]);
+        assert_eq!(packs.disabled(), ["vanilla"]);
+    }
+
+    #[test]
+    fn test_available() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("custom")).unwrap();
+        fs::write(temp_dir.path().join("archived.zip"), b"").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), b"").unwrap();
+
+        let packs = available(temp_dir.path());
+        assert_eq!(packs.len(), BUILT_IN_PACKS.len() + 2);
+        assert_eq!(packs[0], "vanilla");
+        assert_eq!(
+            &packs[BUILT_IN_PACKS.len()..],
+            ["file/archived.zip", "file/custom"]
+        );
+
+        assert_eq!(
+            available(&temp_dir.path().join("missing")).len(),
+            BUILT_IN_PACKS.len()
+        );
+    }
+}
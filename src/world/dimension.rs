@@ -0,0 +1,38 @@
+//! The dimensions of the world.
+use crate::chunks_manager::{OVERWORLD_HEIGHT, OVERWORLD_MIN_Y};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    pub const ALL: [Self; 3] = [Self::Overworld, Self::Nether, Self::End];
+
+    /// The namespaced ID of the dimension.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Overworld => "minecraft:overworld",
+            Self::Nether => "minecraft:the_nether",
+            Self::End => "minecraft:the_end",
+        }
+    }
+
+    /// The lowest block Y coordinate.
+    pub fn min_y(self) -> i32 {
+        match self {
+            Self::Overworld => OVERWORLD_MIN_Y,
+            Self::Nether | Self::End => 0,
+        }
+    }
+
+    /// The number of blocks between the lowest and the highest block.
+    pub fn height(self) -> i32 {
+        match self {
+            Self::Overworld => OVERWORLD_HEIGHT,
+            Self::Nether | Self::End => 256,
+        }
+    }
+}
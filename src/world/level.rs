@@ -0,0 +1,215 @@
+//! The `level.dat` file, storing the global information of a world (time, seed, ...).
+//!
+//! It's a gzip-compressed NBT file whose root compound contains a `Data` compound. Unknown fields
+//! are kept as is, so that saving the file doesn't lose information written by other tools.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use log::info;
+use nbt::{Blob, Value};
+
+use crate::chunks_manager::pos::BlockPos;
+use crate::config::Settings;
+use crate::region_parser::compression::{self, CompressionScheme};
+use crate::region_parser::limits::NbtLimits;
+use crate::region_parser::nbt::read_compressed_root_compound;
+use crate::region_parser::RegionError;
+use crate::{consts, seed_hasher};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LevelData {
+    /// The content of the `Data` compound.
+    data: HashMap<String, Value>,
+}
+
+impl LevelData {
+    /// Loads `level.dat` from `path`. Returns a default `LevelData` if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, RegionError> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut root =
+            read_compressed_root_compound(&bytes, CompressionScheme::Gzip, &NbtLimits::default())?;
+        match root.remove("Data") {
+            Some(Value::Compound(data)) => Ok(Self { data }),
+            Some(_) => Err(RegionError::InvalidField("Data".to_string())),
+            None => Err(RegionError::MissingField("Data".to_string())),
+        }
+    }
+
+    /// Writes `level.dat` to `path`, replacing the previous file.
+    pub fn save(&self, path: &Path) -> Result<(), RegionError> {
+        let mut data = self.data.clone();
+        data.insert(
+            "DataVersion".to_string(),
+            Value::Int(consts::minecraft::DATA_VERSION),
+        );
+
+        let mut blob = Blob::new();
+        blob.insert("Data", Value::Compound(data))?;
+
+        let mut nbt = Vec::new();
+        blob.to_writer(&mut nbt)?;
+        fs::write(path, compression::compress(&nbt, CompressionScheme::Gzip)?)?;
+        Ok(())
+    }
+
+    fn get_long(&self, key: &str) -> Option<i64> {
+        match self.data.get(key) {
+            Some(Value::Long(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Total number of ticks since the world was created (`Time`).
+    pub fn get_time(&self) -> Option<i64> {
+        self.get_long("Time")
+    }
+
+    pub fn set_time(&mut self, time: i64) {
+        self.data.insert("Time".to_string(), Value::Long(time));
+    }
+
+    /// The time of the day (`DayTime`).
+    pub fn get_day_time(&self) -> Option<i64> {
+        self.get_long("DayTime")
+    }
+
+    pub fn set_day_time(&mut self, day_time: i64) {
+        self.data
+            .insert("DayTime".to_string(), Value::Long(day_time));
+    }
+
+    /// The world spawn point (`SpawnX`, `SpawnY` and `SpawnZ`).
+    pub fn get_spawn(&self) -> Option<BlockPos> {
+        let coordinate = |key: &str| match self.data.get(key) {
+            Some(Value::Int(value)) => Some(*value),
+            _ => None,
+        };
+        Some(BlockPos::new(
+            coordinate("SpawnX")?,
+            coordinate("SpawnY")?,
+            coordinate("SpawnZ")?,
+        ))
+    }
+
+    pub fn set_spawn(&mut self, spawn: BlockPos) {
+        for (key, value) in [
+            ("SpawnX", spawn.x),
+            ("SpawnY", spawn.y),
+            ("SpawnZ", spawn.z),
+        ] {
+            self.data.insert(key.to_string(), Value::Int(value));
+        }
+    }
+
+    /// The seed of the world (`WorldGenSettings.seed`).
+    pub fn get_seed(&self) -> Option<i64> {
+        match self.data.get("WorldGenSettings") {
+            Some(Value::Compound(settings)) => match settings.get("seed") {
+                Some(Value::Long(seed)) => Some(*seed),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn set_seed(&mut self, seed: i64) {
+        let settings = self
+            .data
+            .entry("WorldGenSettings".to_string())
+            .or_insert_with(|| Value::Compound(HashMap::new()));
+        if !matches!(settings, Value::Compound(_)) {
+            *settings = Value::Compound(HashMap::new());
+        }
+        if let Value::Compound(settings) = settings {
+            settings.insert("seed".to_string(), Value::Long(seed));
+        }
+    }
+}
+
+/// Returns the seed of the world whose `level.dat` is at `path`. A new world gets the seed of
+/// `level-seed`, random if it's empty, saved to `level.dat`. Like vanilla, the property is ignored
+/// once the world exists.
+pub fn init_seed(path: &Path, settings: &Settings) -> Result<i64, RegionError> {
+    let mut level = LevelData::load(path)?;
+    if let Some(seed) = level.get_seed() {
+        return Ok(seed);
+    }
+
+    let seed = match settings.level_seed.as_deref().map(str::trim) {
+        Some(level_seed) if !level_seed.is_empty() => seed_hasher::world_seed(level_seed),
+        _ => rand::random(),
+    };
+    level.set_seed(seed);
+    level.save(path)?;
+    info!("New world, with the seed {seed}");
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_level_data_roundtrip() -> Result<(), RegionError> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("level.dat");
+
+        // A missing file is a new world.
+        let mut level = LevelData::load(&path)?;
+        assert_eq!(level.get_day_time(), None);
+
+        level.set_time(123456);
+        level.set_day_time(6000);
+        level.save(&path)?;
+
+        let level = LevelData::load(&path)?;
+        assert_eq!(level.get_time(), Some(123456));
+        assert_eq!(level.get_day_time(), Some(6000));
+        Ok(())
+    }
+
+    fn settings(level_seed: &str) -> Settings {
+        Settings::with(&[("level-seed", level_seed)])
+    }
+
+    #[test]
+    fn test_init_seed() -> Result<(), RegionError> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("level.dat");
+
+        // The first start hashes the property and saves it, keeping the other fields.
+        let mut level = LevelData::default();
+        level.set_time(42);
+        level.save(&path)?;
+        let seed = init_seed(&path, &settings("Cactus"))?;
+        assert_eq!(seed, seed_hasher::world_seed("Cactus"));
+        let level = LevelData::load(&path)?;
+        assert_eq!(level.get_seed(), Some(seed));
+        assert_eq!(level.get_time(), Some(42));
+
+        // Then the saved seed wins over the property.
+        assert_eq!(init_seed(&path, &settings("12345"))?, seed);
+        assert_eq!(init_seed(&path, &settings(""))?, seed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_numeric_seed() -> Result<(), RegionError> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("level.dat");
+
+        assert_eq!(
+            init_seed(&path, &settings("-4172144997902289642"))?,
+            -4172144997902289642
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Which mobs may spawn, from the `spawn-animals`, `spawn-monsters` and `spawn-npcs` properties and
+//! the difficulty.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::config::{Difficulty, Settings};
+
+static MOB_SPAWNING: Lazy<Arc<RwLock<MobSpawning>>> =
+    Lazy::new(|| Arc::new(RwLock::new(MobSpawning::from_settings(&Settings::new()))));
+
+/// Returns the spawning rules of the world.
+// TODO: Check them in the mob spawner, once there is one.
+pub fn global() -> Arc<RwLock<MobSpawning>> {
+    MOB_SPAWNING.clone()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnCategory {
+    Animal,
+    /// Hostile mobs, never spawning on peaceful.
+    Monster,
+    /// Villagers and wandering traders.
+    Npc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MobSpawning {
+    animals: bool,
+    monsters: bool,
+    npcs: bool,
+    difficulty: Difficulty,
+}
+
+impl MobSpawning {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            animals: settings.spawn_animals,
+            monsters: settings.spawn_monsters,
+            npcs: settings.spawn_npcs,
+            difficulty: settings.difficulty,
+        }
+    }
+
+    /// Whether the mobs of `category` may spawn.
+    pub fn can_spawn(&self, category: SpawnCategory) -> bool {
+        match category {
+            SpawnCategory::Animal => self.animals,
+            SpawnCategory::Monster => self.monsters && self.difficulty != Difficulty::Peaceful,
+            SpawnCategory::Npc => self.npcs,
+        }
+    }
+
+    /// Allows or forbids the spawning of `category`. Monsters still don't spawn on peaceful.
+    pub fn set_enabled(&mut self, category: SpawnCategory, enabled: bool) {
+        match category {
+            SpawnCategory::Animal => self.animals = enabled,
+            SpawnCategory::Monster => self.monsters = enabled,
+            SpawnCategory::Npc => self.npcs = enabled,
+        }
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(spawn_monsters: &str, difficulty: &str) -> Settings {
+        Settings::with(&[
+            ("spawn-monsters", spawn_monsters),
+            ("difficulty", difficulty),
+        ])
+    }
+
+    #[test]
+    fn test_spawn_monsters_disabled() {
+        let spawning = MobSpawning::from_settings(&settings("false", "hard"));
+        assert!(!spawning.can_spawn(SpawnCategory::Monster));
+        assert!(spawning.can_spawn(SpawnCategory::Animal));
+        assert!(spawning.can_spawn(SpawnCategory::Npc));
+
+        let spawning = MobSpawning::from_settings(&settings("true", "hard"));
+        assert!(spawning.can_spawn(SpawnCategory::Monster));
+    }
+
+    #[test]
+    fn test_peaceful_blocks_monsters() {
+        let mut spawning = MobSpawning::from_settings(&settings("true", "peaceful"));
+        assert!(!spawning.can_spawn(SpawnCategory::Monster));
+        assert!(spawning.can_spawn(SpawnCategory::Animal));
+
+        spawning.set_enabled(SpawnCategory::Monster, true);
+        assert!(!spawning.can_spawn(SpawnCategory::Monster));
+        spawning.set_difficulty(Difficulty::Easy);
+        assert!(spawning.can_spawn(SpawnCategory::Monster));
+
+        spawning.set_enabled(SpawnCategory::Animal, false);
+        assert!(!spawning.can_spawn(SpawnCategory::Animal));
+    }
+}
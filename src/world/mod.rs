@@ -0,0 +1,11 @@
+//! This module holds the state of the world that isn't stored in chunks.
+pub mod border;
+pub mod datapack;
+pub mod dimension;
+pub mod level;
+pub mod mob_spawning;
+pub mod pvp;
+pub mod spawn;
+pub mod time;
+pub mod view_distance;
+pub mod weather;
@@ -0,0 +1,72 @@
+//! Whether players can hurt each other, initially from the `pvp` property.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::config::Settings;
+
+static PVP: Lazy<Arc<RwLock<Pvp>>> =
+    Lazy::new(|| Arc::new(RwLock::new(Pvp::from_settings(&Settings::new()))));
+
+/// Returns the pvp rule of the world. It's changed at runtime with the `pvp` command, without
+/// touching `server.properties`.
+pub fn global() -> Arc<RwLock<Pvp>> {
+    PVP.clone()
+}
+
+/// What deals damage, or receives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combatant {
+    Player,
+    /// Anything else: mobs, projectiles of mobs, the environment...
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pvp {
+    enabled: bool,
+}
+
+impl Default for Pvp {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Pvp {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            enabled: settings.pvp,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Whether `attacker` can damage `target`. Only the damage between players depends on pvp.
+    // TODO: Check it in the combat handler, once players can attack.
+    pub fn allows_damage(&self, attacker: Combatant, target: Combatant) -> bool {
+        self.enabled || attacker != Combatant::Player || target != Combatant::Player
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_damage() {
+        let mut pvp = Pvp::default();
+        assert!(pvp.allows_damage(Combatant::Player, Combatant::Player));
+
+        pvp.set_enabled(false);
+        assert!(!pvp.allows_damage(Combatant::Player, Combatant::Player));
+        assert!(pvp.allows_damage(Combatant::Other, Combatant::Player));
+        assert!(pvp.allows_damage(Combatant::Player, Combatant::Other));
+    }
+}
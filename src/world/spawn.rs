@@ -0,0 +1,104 @@
+//! The world spawn point: where new players appear, around the spawn chunks.
+use crate::chunks_manager::generator::{Generators, LAVA, WATER};
+use crate::chunks_manager::heightmap::{Heightmap, HeightmapKind};
+use crate::chunks_manager::pos::{BlockPos, ChunkPos};
+use crate::chunks_manager::{Chunk, SECTION_WIDTH};
+use crate::world::dimension::Dimension;
+
+/// The radius, in chunks, of the spawn chunks around the spawn point, vanilla's default
+/// `spawnChunkRadius`.
+pub const SPAWN_CHUNK_RADIUS: i32 = 2;
+
+/// The chunks within `radius` chunks of `center`, the closest first.
+pub fn spawn_chunks(center: ChunkPos, radius: i32) -> Vec<ChunkPos> {
+    let mut chunks: Vec<ChunkPos> = (-radius..=radius)
+        .flat_map(|dz| (-radius..=radius).map(move |dx| (dx, dz)))
+        .map(|(dx, dz)| ChunkPos::new(center.x + dx, center.z + dz))
+        .collect();
+    chunks.sort_by_key(|chunk| (chunk.x - center.x).pow(2) + (chunk.z - center.z).pow(2));
+    chunks
+}
+
+/// Generates the spawn chunks of the overworld around the origin, and returns the safe spawn
+/// point the closest to it: the block above a solid surface, with room for a player, inside the
+/// world border. `None` if the overworld has no generator or no column is safe.
+pub fn find_spawn(generators: &Generators) -> Option<BlockPos> {
+    let chunks = spawn_chunks(ChunkPos::new(0, 0), SPAWN_CHUNK_RADIUS)
+        .into_iter()
+        .filter(|pos| generators.is_chunk_inside(pos.x, pos.z))
+        .map(|pos| generators.generate_chunk(Dimension::Overworld, pos.x, pos.z))
+        .collect::<Option<Vec<Chunk>>>()?;
+
+    let border = generators.get_border();
+    chunks
+        .iter()
+        .flat_map(safe_columns)
+        .filter(|pos| border.is_none_or(|border| border.is_inside(pos.x, pos.z)))
+        .min_by_key(|pos| (pos.x.pow(2) + pos.z.pow(2), pos.x, pos.z))
+}
+
+/// The spawn position of each column of `chunk` a player can stand on.
+fn safe_columns(chunk: &Chunk) -> Vec<BlockPos> {
+    let heightmap = Heightmap::compute(chunk, HeightmapKind::MotionBlocking);
+    let max_y = chunk.get_min_y() + chunk.get_height() - 1;
+    let (min_x, min_z) = ChunkPos::new(chunk.get_x(), chunk.get_z()).min_block();
+
+    let mut columns = Vec::new();
+    for z in 0..SECTION_WIDTH {
+        for x in 0..SECTION_WIDTH {
+            let Some(top) = heightmap.get_top_block_y(x, z) else {
+                continue;
+            };
+            let (block_x, block_z) = (min_x + x as i32, min_z + z as i32);
+            let surface = chunk.get_block(block_x, top, block_z);
+            // Two blocks of air for the player, and not in a lake.
+            if top + 2 <= max_y && !matches!(surface, Ok(WATER | LAVA)) {
+                columns.push(BlockPos::new(block_x, top + 1, block_z));
+            }
+        }
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunks_manager::generator::{NoiseGenerator, SuperflatGenerator};
+    use crate::world::border::WorldBorder;
+
+    #[test]
+    fn test_spawn_chunks() {
+        let chunks = spawn_chunks(ChunkPos::new(3, -1), 2);
+        assert_eq!(chunks.len(), 25);
+        assert_eq!(chunks[0], ChunkPos::new(3, -1));
+        assert_eq!(chunks.last(), Some(&ChunkPos::new(5, 1)));
+    }
+
+    #[test]
+    fn test_find_spawn() {
+        assert_eq!(find_spawn(&Generators::new()), None);
+
+        let mut generators = Generators::new();
+        generators.register(Dimension::Overworld, SuperflatGenerator::overworld());
+        assert_eq!(find_spawn(&generators), Some(BlockPos::new(0, -60, 0)));
+
+        // The closest column to the origin inside the border.
+        generators.set_border(WorldBorder::from_max_world_size(4).with_center(40.0, 40.0));
+        assert_eq!(find_spawn(&generators), Some(BlockPos::new(36, -60, 36)));
+        // No spawn chunk inside it.
+        generators.set_border(WorldBorder::from_max_world_size(4).with_center(1000.0, 0.0));
+        assert_eq!(find_spawn(&generators), None);
+
+        // The noise terrain has a spawn point above its surface too.
+        let mut generators = Generators::new();
+        generators.register(Dimension::Overworld, NoiseGenerator::overworld(42));
+        let spawn = find_spawn(&generators).unwrap();
+        let chunk = generators
+            .generate_chunk(Dimension::Overworld, spawn.chunk().x, spawn.chunk().z)
+            .unwrap();
+        assert!(!matches!(
+            chunk.get_block(spawn.x, spawn.y - 1, spawn.z),
+            Ok(0 | WATER | LAVA)
+        ));
+    }
+}
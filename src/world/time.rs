@@ -0,0 +1,154 @@
+//! The time of the world, in ticks.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::net::packet::packet_types::UpdateTime;
+use crate::world::level::LevelData;
+
+/// Number of ticks in a Minecraft day.
+pub const TICKS_PER_DAY: i64 = 24000;
+
+static TIME: Lazy<Arc<RwLock<WorldTime>>> =
+    Lazy::new(|| Arc::new(RwLock::new(WorldTime::default())));
+
+/// Returns the time of the world.
+pub fn global() -> Arc<RwLock<WorldTime>> {
+    TIME.clone()
+}
+
+/// Named times of the day, as used by the `time set` command.
+pub fn named_time(name: &str) -> Option<i64> {
+    match name {
+        "day" => Some(1000),
+        "noon" => Some(6000),
+        "night" => Some(13000),
+        "midnight" => Some(18000),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorldTime {
+    /// Total number of ticks since the world was created. Never changed by commands.
+    game_time: i64,
+    /// Time of the day, from 0 to `TICKS_PER_DAY` (excluded).
+    day_time: i64,
+}
+
+impl WorldTime {
+    pub fn new(game_time: i64, day_time: i64) -> Self {
+        Self {
+            game_time,
+            day_time: day_time.rem_euclid(TICKS_PER_DAY),
+        }
+    }
+
+    /// The time saved in `level.dat`, 0 for a new world.
+    pub fn from_level(level: &LevelData) -> Self {
+        Self::new(
+            level.get_time().unwrap_or(0),
+            level.get_day_time().unwrap_or(0),
+        )
+    }
+
+    /// Saves the time into `level`, see `from_level`.
+    pub fn write_to(&self, level: &mut LevelData) {
+        level.set_time(self.game_time);
+        level.set_day_time(self.day_time);
+    }
+
+    pub fn get_game_time(&self) -> i64 {
+        self.game_time
+    }
+
+    pub fn get_day_time(&self) -> i64 {
+        self.day_time
+    }
+
+    /// The number of days since the world was created.
+    pub fn get_day(&self) -> i64 {
+        self.game_time / TICKS_PER_DAY
+    }
+
+    /// Advances the time by one tick.
+    pub fn tick(&mut self) {
+        self.game_time += 1;
+        self.add_day_time(1);
+    }
+
+    /// Sets the time of the day, wrapping around at `TICKS_PER_DAY`.
+    pub fn set_day_time(&mut self, day_time: i64) {
+        self.day_time = day_time.rem_euclid(TICKS_PER_DAY);
+    }
+
+    /// Adds `ticks` to the time of the day, wrapping around at `TICKS_PER_DAY`.
+    pub fn add_day_time(&mut self, ticks: i64) {
+        // Reduced first, as `ticks` comes from commands and can be close to `i64::MAX`.
+        self.set_day_time(self.day_time + ticks.rem_euclid(TICKS_PER_DAY));
+    }
+
+    /// The Update Time packet telling the clients about this time.
+    pub fn to_packet(self) -> UpdateTime {
+        UpdateTime {
+            world_age: self.game_time,
+            time_of_day: self.day_time,
+            time_of_day_increasing: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_day_time_wraparound() {
+        let mut time = WorldTime::new(0, 23990);
+
+        time.add_day_time(20);
+        assert_eq!(time.get_day_time(), 10);
+
+        time.add_day_time(-20);
+        assert_eq!(time.get_day_time(), 23990);
+
+        time.set_day_time(48000 + 6000);
+        assert_eq!(time.get_day_time(), 6000);
+
+        time.add_day_time(i64::MAX);
+        assert_eq!(time.get_day_time(), 13807);
+        time.set_day_time(6000);
+        time.add_day_time(i64::MIN);
+        assert_eq!(time.get_day_time(), 22192);
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut time = WorldTime::new(TICKS_PER_DAY - 1, TICKS_PER_DAY - 1);
+
+        time.tick();
+        assert_eq!(time.get_game_time(), TICKS_PER_DAY);
+        assert_eq!(time.get_day_time(), 0);
+        assert_eq!(time.get_day(), 1);
+    }
+
+    #[test]
+    fn test_level_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("level.dat");
+        assert_eq!(
+            WorldTime::from_level(&LevelData::load(&path).unwrap()),
+            WorldTime::default()
+        );
+
+        let time = WorldTime::new(3 * TICKS_PER_DAY + 500, 18000);
+        let mut level = LevelData::load(&path).unwrap();
+        time.write_to(&mut level);
+        level.save(&path).unwrap();
+        assert_eq!(
+            WorldTime::from_level(&LevelData::load(&path).unwrap()),
+            time
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! The view distance of each dimension: `view-distance`, unless a non-standard
+//! `view-distance.<dimension>` property overrides it, e.g. `view-distance.the_nether=6`.
+use std::collections::HashMap;
+
+use super::dimension::Dimension;
+use crate::config::Settings;
+
+/// The range of the view distances, in chunks.
+const RANGE: std::ops::RangeInclusive<u8> = 3..=32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewDistances {
+    global: u8,
+    overrides: HashMap<Dimension, u8>,
+}
+
+impl ViewDistances {
+    /// The view distances of `settings`. The invalid overrides are ignored, see `validate`.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let overrides = Dimension::ALL
+            .into_iter()
+            .filter_map(|dimension| {
+                let distance = override_of(settings, dimension)?.parse::<u8>().ok()?;
+                Some((dimension, distance)).filter(|(_, distance)| RANGE.contains(distance))
+            })
+            .collect();
+        Self {
+            global: settings.view_distance,
+            overrides,
+        }
+    }
+
+    /// The view distance of `dimension`, in chunks.
+    pub fn get(&self, dimension: Dimension) -> u8 {
+        self.overrides
+            .get(&dimension)
+            .copied()
+            .unwrap_or(self.global)
+    }
+
+    /// The chunks to stream to a player in `dimension` standing in the chunk
+    /// (`center_x`, `center_z`), the closest first.
+    pub fn chunks_in_view(
+        &self,
+        dimension: Dimension,
+        center_x: i32,
+        center_z: i32,
+    ) -> Vec<(i32, i32)> {
+        let distance = self.get(dimension) as i32;
+        let mut chunks: Vec<(i32, i32)> = (-distance..=distance)
+            .flat_map(|dx| (-distance..=distance).map(move |dz| (dx, dz)))
+            .collect();
+        chunks.sort_by_key(|&(dx, dz)| dx * dx + dz * dz);
+        chunks
+            .into_iter()
+            .map(|(dx, dz)| (center_x + dx, center_z + dz))
+            .collect()
+    }
+}
+
+/// The `view-distance.<dimension>` property of `dimension`, the dimension being named without its
+/// namespace.
+fn override_of(settings: &Settings, dimension: Dimension) -> Option<&str> {
+    let name = dimension.name().trim_start_matches("minecraft:");
+    settings.extra(&format!("view-distance.{name}"))
+}
+
+/// Warns about the overrides that are ignored.
+pub fn validate(settings: &Settings) -> Vec<String> {
+    Dimension::ALL
+        .into_iter()
+        .filter_map(|dimension| {
+            let value = override_of(settings, dimension)?;
+            match value.parse::<u8>() {
+                Ok(distance) if RANGE.contains(&distance) => None,
+                _ => Some(format!(
+                    "view-distance.{}={value} is invalid, use a value between 3 and 32",
+                    dimension.name().trim_start_matches("minecraft:")
+                )),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(overrides: &[(&str, &str)]) -> Settings {
+        Settings::with(&[[("view-distance", "10")].as_slice(), overrides].concat())
+    }
+
+    #[test]
+    fn test_nether_view_distance() {
+        let settings = settings(&[("view-distance.the_nether", "4")]);
+        let distances = ViewDistances::from_settings(&settings);
+
+        assert_eq!(distances.get(Dimension::Nether), 4);
+        assert_eq!(distances.get(Dimension::Overworld), 10);
+        assert_eq!(distances.get(Dimension::End), 10);
+
+        let nether = distances.chunks_in_view(Dimension::Nether, 5, -3);
+        assert_eq!(nether.len(), 9 * 9);
+        assert_eq!(nether[0], (5, -3));
+        assert!(nether.contains(&(9, 1)));
+        assert!(!nether.contains(&(10, -3)));
+
+        let overworld = distances.chunks_in_view(Dimension::Overworld, 0, 0);
+        assert_eq!(overworld.len(), 21 * 21);
+        assert!(overworld.contains(&(-10, 10)));
+    }
+
+    #[test]
+    fn test_invalid_override() {
+        let settings = settings(&[
+            ("view-distance.the_end", "64"),
+            ("view-distance.overworld", "far"),
+        ]);
+
+        let distances = ViewDistances::from_settings(&settings);
+        assert_eq!(distances.get(Dimension::End), 10);
+        assert_eq!(distances.get(Dimension::Overworld), 10);
+        assert_eq!(validate(&settings).len(), 2);
+    }
+}
@@ -0,0 +1,53 @@
+//! The weather of the world.
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::net::packet::packet_types::{GameEvent, GameEventKind};
+
+static WEATHER: Lazy<Arc<RwLock<Weather>>> = Lazy::new(|| Arc::new(RwLock::new(Weather::Clear)));
+
+/// Returns the weather of the world.
+pub fn global() -> Arc<RwLock<Weather>> {
+    WEATHER.clone()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    /// The Game Events to send so that a client displays this weather.
+    pub fn game_events(self) -> Vec<GameEvent> {
+        let (rain_level, thunder_level) = match self {
+            Weather::Clear => (0.0, 0.0),
+            Weather::Rain => (1.0, 0.0),
+            Weather::Thunder => (1.0, 1.0),
+        };
+
+        let raining = if self == Weather::Clear {
+            GameEventKind::EndRaining
+        } else {
+            GameEventKind::BeginRaining
+        };
+
+        vec![
+            GameEvent::new(raining, 0.0),
+            GameEvent::new(GameEventKind::RainLevelChange, rain_level),
+            GameEvent::new(GameEventKind::ThunderLevelChange, thunder_level),
+        ]
+    }
+
+    /// A human readable name, as shown in command outputs.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Thunder => "rain & thunder",
+        }
+    }
+}